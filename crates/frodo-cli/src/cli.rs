@@ -1,4 +1,15 @@
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
+use frodo_core::{recurrence::Recurrence, tasks::Priority, timetrack::GroupBy};
+
+use crate::{
+    config::{BackendKind, KeyProviderKind},
+    export::ExportFormat,
+    git,
+    import::ImportFormat,
+    output::OutputFormat,
+};
 
 /// CLI surface definition. Kept tiny for now; will expand with task/sync/chat commands.
 #[derive(Parser, Debug)]
@@ -9,6 +20,14 @@ use clap::{Parser, Subcommand};
     propagate_version = true
 )]
 pub struct Cli {
+    /// Output format: text, json or ndjson. Accepted before or after the
+    /// subcommand (`frodo task list --format json`).
+    #[arg(long, value_name = "FORMAT", default_value = "text", global = true)]
+    pub format: OutputFormat,
+    /// Work on tasks from every workspace instead of only the one detected
+    /// from the current directory.
+    #[arg(long, global = true)]
+    pub all: bool,
     /// Optional subcommand; defaults to launching the TUI when absent.
     #[command(subcommand)]
     pub command: Option<Command>,
@@ -40,6 +59,63 @@ pub enum Command {
     /// Manage tasks.
     #[command(subcommand)]
     Task(TaskCommand),
+    /// Show workspaces and move tasks between them.
+    #[command(subcommand)]
+    Workspace(WorkspaceCommand),
+    /// Maintain the encrypted data store.
+    #[command(subcommand)]
+    Store(StoreCommand),
+    /// Show the highest-scoring open tasks and why they rank there.
+    Next {
+        /// How many tasks to show.
+        #[arg(short = 'n', long, default_value_t = 5)]
+        limit: usize,
+    },
+    /// Summaries of tracked work.
+    #[command(subcommand)]
+    Report(ReportCommand),
+    /// Revert the most recent task changes.
+    Undo {
+        /// How many operations to revert.
+        #[arg(short = 'n', long, default_value_t = 1)]
+        count: usize,
+    },
+    /// Full-text search over task titles, tags and descriptions.
+    Search {
+        /// Words to search for (prefixes of three or more letters also match).
+        #[arg(required_unless_present = "reindex")]
+        terms: Vec<String>,
+        /// Maximum number of results.
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+        /// Rebuild the search index from the stored tasks.
+        #[arg(long)]
+        reindex: bool,
+    },
+    /// Export tasks as JSON (lossless, re-importable), CSV, Markdown or iCalendar.
+    Export {
+        /// json, csv, markdown or ics (`--format` picks the output envelope).
+        #[arg(id = "as", long = "as", value_name = "FORMAT", default_value = "json")]
+        format: ExportFormat,
+        /// Write to this file instead of stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Query terms selecting the tasks to export (default: all).
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        query: Vec<String>,
+    },
+    /// Import tasks from a Markdown checklist, todo.txt, CSV or JSON file.
+    /// Tasks whose title already exists are skipped.
+    Import {
+        /// File to read.
+        file: PathBuf,
+        /// markdown, todotxt, csv or json (guessed from the extension if omitted).
+        #[arg(id = "as", long = "as", value_name = "FORMAT")]
+        format: Option<ImportFormat>,
+        /// Show what would be imported without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Self update from the latest GitHub release.
     SelfUpdate {
         /// Only check for updates, do not install.
@@ -48,6 +124,40 @@ pub enum Command {
     },
 }
 
+impl Command {
+    /// Stable name used as `command` in JSON output.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Tui => "tui",
+            Command::Version => "version",
+            Command::Health => "health",
+            Command::Config(ConfigCommand::Init) => "config.init",
+            Command::Ask { .. } => "ask",
+            Command::Sync { .. } => "sync",
+            Command::Task(cmd) => cmd.name(),
+            Command::Workspace(WorkspaceCommand::Show) => "workspace.show",
+            Command::Workspace(WorkspaceCommand::List) => "workspace.list",
+            Command::Workspace(WorkspaceCommand::Assign { .. }) => "workspace.assign",
+            Command::Store(StoreCommand::RotateKey) => "store.rotate-key",
+            Command::Store(StoreCommand::RotateKek) => "store.rotate-kek",
+            Command::Store(StoreCommand::AddUnlock { .. }) => "store.add-unlock",
+            Command::Store(StoreCommand::Migrate { .. }) => "store.migrate",
+            Command::Store(StoreCommand::BackupKey { .. }) => "store.backup-key",
+            Command::Store(StoreCommand::Recover { .. }) => "store.recover",
+            Command::Store(StoreCommand::Backup { .. }) => "store.backup",
+            Command::Store(StoreCommand::Restore { .. }) => "store.restore",
+            Command::Store(StoreCommand::Fsck { .. }) => "store.fsck",
+            Command::Next { .. } => "next",
+            Command::Report(ReportCommand::Time { .. }) => "report.time",
+            Command::Undo { .. } => "undo",
+            Command::Search { .. } => "search",
+            Command::Export { .. } => "export",
+            Command::Import { .. } => "import",
+            Command::SelfUpdate { .. } => "self-update",
+        }
+    }
+}
+
 #[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
 pub enum ConfigCommand {
     /// Create a default config file if one does not exist.
@@ -55,9 +165,133 @@ pub enum ConfigCommand {
 }
 
 #[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
-pub enum TaskCommand {
-    /// List tasks.
+pub enum WorkspaceCommand {
+    /// Show the workspace detected from the current directory.
+    Show,
+    /// List configured workspaces and those that tasks belong to.
     List,
+    /// Move tasks into a workspace (the active one unless `--to` is given).
+    Assign {
+        /// Tasks to move (id, short id, list index, remote key or title).
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// Target workspace name.
+        #[arg(long, conflicts_with = "clear")]
+        to: Option<String>,
+        /// Remove the tasks from their workspace instead.
+        #[arg(long)]
+        clear: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum StoreCommand {
+    /// Generate a new data key and re-encrypt every stored item with it.
+    /// Re-running after an interruption finishes the same rotation.
+    RotateKey,
+    /// Replace the key that unlocks the store (keychain entry or passphrase)
+    /// and the master key behind it, without re-encrypting any data. Other
+    /// unlock keys and recovery codes stop working.
+    RotateKek,
+    /// Let another key provider unlock this store as well, e.g. before
+    /// switching `[storage] key_provider`.
+    AddUnlock {
+        /// keyring or passphrase.
+        provider: KeyProviderKind,
+    },
+    /// Upgrade stored items to the current encryption format, or with `--to`
+    /// move them into another storage backend. Safe to re-run.
+    Migrate {
+        /// file or sqlite.
+        #[arg(long)]
+        to: Option<BackendKind>,
+    },
+    /// Print a recovery code (or write a passphrase-protected key backup
+    /// with `--file`) that restores access if the keychain is lost.
+    BackupKey {
+        /// Write a key backup file instead of printing a recovery code.
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// Read the backup passphrase from this environment variable instead of prompting.
+        #[arg(long, value_name = "VAR", requires = "file")]
+        passphrase_env: Option<String>,
+    },
+    /// Restore access to a copied data dir with a recovery code or key backup.
+    Recover {
+        /// Recovery code (prompted for when neither this nor `--file` is given).
+        #[arg(long, conflicts_with = "file")]
+        code: Option<String>,
+        /// Key backup file written by `store backup-key --file`.
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// Read the backup passphrase from this environment variable instead of prompting.
+        #[arg(long, value_name = "VAR", requires = "file")]
+        passphrase_env: Option<String>,
+    },
+    /// Write every stored item to one encrypted archive that restores into
+    /// any store, protected by a passphrase or age public keys.
+    Backup {
+        /// Archive to create (an existing file is never replaced).
+        file: PathBuf,
+        /// Encrypt to this age public key (`age1...`) instead of a passphrase; repeatable.
+        #[arg(long, value_name = "KEY")]
+        recipient: Vec<String>,
+        /// Read the archive passphrase from this environment variable instead of prompting.
+        #[arg(long, value_name = "VAR", conflicts_with = "recipient")]
+        passphrase_env: Option<String>,
+    },
+    /// Replace the store's contents with an archive from `store backup`,
+    /// then read every item back to verify it.
+    Restore {
+        /// Archive to read.
+        file: PathBuf,
+        /// age identity file for archives encrypted to a public key.
+        #[arg(long)]
+        identity: Option<PathBuf>,
+        /// Read the archive passphrase from this environment variable instead of prompting.
+        #[arg(long, value_name = "VAR", conflicts_with = "identity")]
+        passphrase_env: Option<String>,
+        /// Only decrypt and check the archive; change nothing.
+        #[arg(long)]
+        check: bool,
+        /// Restore over a store that already holds items, deleting those not in the archive.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Decrypt and validate every stored item and look for temp files left
+    /// by interrupted writes; offers to quarantine anything broken.
+    Fsck {
+        /// Quarantine corrupt items and delete orphaned temp files without asking.
+        #[arg(long)]
+        repair: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum ReportCommand {
+    /// Tracked time since a point in time, grouped by task, tag or day.
+    Time {
+        /// Start of the window: a weekday (`monday`), `today`, `yesterday`,
+        /// YYYY-MM-DD, or an offset such as `7d` / `2w` ago.
+        #[arg(long, default_value = "monday")]
+        since: String,
+        /// Grouping: task, tag or day.
+        #[arg(long, default_value = "task")]
+        by: GroupBy,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum TaskCommand {
+    /// List tasks, optionally filtered by a query such as
+    /// `status:todo tag:backend due<7d -tag:someday "search words"` or a saved `@view`.
+    List {
+        /// Query terms; all must match.
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        query: Vec<String>,
+    },
+    /// Show saved views from the config file.
+    Views,
     /// Add a new task.
     Add {
         /// Title for the task.
@@ -68,14 +302,103 @@ pub enum TaskCommand {
         /// Tags for grouping (repeat flag).
         #[arg(short, long)]
         tag: Vec<String>,
+        /// Priority: low, medium, high or urgent.
+        #[arg(short, long)]
+        priority: Option<Priority>,
+        /// Due date: YYYY-MM-DD, RFC 3339, `today`, `tomorrow`, or relative (`3d`, `2w`).
+        #[arg(long)]
+        due: Option<String>,
+        /// Parent task id, making this a subtask.
+        #[arg(long)]
+        parent: Option<String>,
+        /// Task ids that must be done first (repeat flag).
+        #[arg(long)]
+        blocked_by: Vec<String>,
+        /// Repeat rule: daily, every:3d, weekly[:mon,thu], monthly[:15], cron:<expr>, after:3d.
+        #[arg(long)]
+        repeat: Option<Recurrence>,
     },
     /// Mark a task as done.
     Done {
-        /// Task id (UUID).
+        /// Task id, short id, index from the last `task list`, remote key or title.
+        id: String,
+    },
+    /// Start a timer on a task and mark it in progress (one timer at a time).
+    Start {
+        /// Task id, short id, index from the last `task list`, remote key or title.
+        id: String,
+    },
+    /// Stop the running timer.
+    Stop,
+    /// Show tasks as a tree of parents and subtasks.
+    Tree,
+    /// Show who changed a task, when, and what changed.
+    History {
+        /// Task id, short id, index from the last `task list`, remote key or title.
+        id: String,
+    },
+    /// Mark a task as blocked by another task.
+    Block {
+        /// Task that is blocked (id, short id, list index, remote key or title).
+        id: String,
+        /// Task it waits on.
+        on: String,
+    },
+    /// Remove a "blocked by" link.
+    Unblock {
+        /// Task that was blocked (id, short id, list index, remote key or title).
         id: String,
+        /// Task it no longer waits on.
+        on: String,
+    },
+    /// Create a git branch named after a task (and its issue key) at HEAD and switch to it.
+    Branch {
+        /// Task id, short id, index from the last `task list`, remote key or title.
+        id: String,
+        /// Only create the branch; stay on the current one.
+        #[arg(long)]
+        no_switch: bool,
+    },
+    /// Show the task for the current git branch and commits mentioning it.
+    Current,
+    /// List commits reachable from HEAD that mention a task's issue key or short id.
+    Commits {
+        /// Task id, short id, index from the last `task list`, remote key or title.
+        id: String,
+        /// How many commits to read, newest first.
+        #[arg(long, default_value_t = git::DEFAULT_SCAN)]
+        scan: usize,
+    },
+    /// Move a task under a parent, or back to the top level when no parent is given.
+    Parent {
+        /// Task to move (id, short id, list index, remote key or title).
+        id: String,
+        /// New parent task.
+        parent: Option<String>,
     },
 }
 
+impl TaskCommand {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TaskCommand::List { .. } => "task.list",
+            TaskCommand::Views => "task.views",
+            TaskCommand::Add { .. } => "task.add",
+            TaskCommand::Done { .. } => "task.done",
+            TaskCommand::Start { .. } => "task.start",
+            TaskCommand::Stop => "task.stop",
+            TaskCommand::Tree => "task.tree",
+            TaskCommand::History { .. } => "task.history",
+            TaskCommand::Block { .. } => "task.block",
+            TaskCommand::Unblock { .. } => "task.unblock",
+            TaskCommand::Parent { .. } => "task.parent",
+            TaskCommand::Branch { .. } => "task.branch",
+            TaskCommand::Current => "task.current",
+            TaskCommand::Commits { .. } => "task.commits",
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,6 +438,270 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parses_task_list_query_with_negations() {
+        let cli = Cli::try_parse_from([
+            "frodo",
+            "task",
+            "list",
+            "tag:backend",
+            "-tag:someday",
+            "due<7d",
+        ])
+        .expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Task(TaskCommand::List {
+                query: vec!["tag:backend".into(), "-tag:someday".into(), "due<7d".into()]
+            }))
+        );
+    }
+
+    #[test]
+    fn parses_undo_and_history() {
+        let cli = Cli::try_parse_from(["frodo", "undo"]).expect("parse ok");
+        assert_eq!(cli.command, Some(Command::Undo { count: 1 }));
+        let cli = Cli::try_parse_from(["frodo", "undo", "-n", "3"]).expect("parse ok");
+        assert_eq!(cli.command, Some(Command::Undo { count: 3 }));
+        let cli = Cli::try_parse_from(["frodo", "task", "history", "abc"]).expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Task(TaskCommand::History { id: "abc".into() }))
+        );
+    }
+
+    #[test]
+    fn parses_timers_and_time_report() {
+        let cli = Cli::try_parse_from(["frodo", "task", "start", "abc"]).expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Task(TaskCommand::Start { id: "abc".into() }))
+        );
+        let cli =
+            Cli::try_parse_from(["frodo", "report", "time", "--by", "tag"]).expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Report(ReportCommand::Time {
+                since: "monday".into(),
+                by: GroupBy::Tag,
+            }))
+        );
+        assert!(Cli::try_parse_from(["frodo", "report", "time", "--by", "colour"]).is_err());
+    }
+
+    #[test]
+    fn parses_search() {
+        let cli = Cli::try_parse_from(["frodo", "search", "signing", "keys", "-n", "3"])
+            .expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Search {
+                terms: vec!["signing".into(), "keys".into()],
+                limit: 3,
+                reindex: false,
+            })
+        );
+        assert!(Cli::try_parse_from(["frodo", "search"]).is_err());
+        assert!(Cli::try_parse_from(["frodo", "search", "--reindex"]).is_ok());
+    }
+
+    #[test]
+    fn parses_output_format_anywhere() {
+        let cli =
+            Cli::try_parse_from(["frodo", "--format", "ndjson", "task", "list"]).expect("parse ok");
+        assert_eq!(cli.format, OutputFormat::Ndjson);
+        assert_eq!(cli.command.as_ref().map(Command::name), Some("task.list"));
+        let cli = Cli::try_parse_from(["frodo", "task", "list", "--format", "json", "is:open"])
+            .expect("parse ok");
+        assert_eq!(cli.format, OutputFormat::Json);
+        assert_eq!(
+            cli.command,
+            Some(Command::Task(TaskCommand::List {
+                query: vec!["is:open".into()]
+            }))
+        );
+        let cli = Cli::try_parse_from(["frodo", "health"]).expect("parse ok");
+        assert_eq!(cli.format, OutputFormat::Text);
+        assert!(Cli::try_parse_from(["frodo", "--format", "yaml", "health"]).is_err());
+    }
+
+    #[test]
+    fn parses_all_flag_and_workspace_commands() {
+        let cli =
+            Cli::try_parse_from(["frodo", "task", "list", "--all", "tag:x"]).expect("parse ok");
+        assert!(cli.all);
+        assert_eq!(
+            cli.command,
+            Some(Command::Task(TaskCommand::List {
+                query: vec!["tag:x".into()]
+            }))
+        );
+        let cli = Cli::try_parse_from(["frodo", "--all", "next"]).expect("parse ok");
+        assert!(cli.all);
+
+        let cli = Cli::try_parse_from(["frodo", "workspace", "assign", "1", "2", "--to", "frodo"])
+            .expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Workspace(WorkspaceCommand::Assign {
+                ids: vec!["1".into(), "2".into()],
+                to: Some("frodo".into()),
+                clear: false,
+            }))
+        );
+        assert!(
+            Cli::try_parse_from(["frodo", "workspace", "assign", "1", "--to", "x", "--clear"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_store_commands() {
+        let cli = Cli::try_parse_from(["frodo", "store", "rotate-key"]).expect("parse ok");
+        assert_eq!(cli.command, Some(Command::Store(StoreCommand::RotateKey)));
+        assert_eq!(
+            cli.command.as_ref().map(Command::name),
+            Some("store.rotate-key")
+        );
+        let cli =
+            Cli::try_parse_from(["frodo", "store", "add-unlock", "passphrase"]).expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Store(StoreCommand::AddUnlock {
+                provider: KeyProviderKind::Passphrase
+            }))
+        );
+        assert!(Cli::try_parse_from(["frodo", "store", "add-unlock", "yubikey"]).is_err());
+
+        let cli = Cli::try_parse_from(["frodo", "store", "migrate"]).expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Store(StoreCommand::Migrate { to: None }))
+        );
+        let cli =
+            Cli::try_parse_from(["frodo", "store", "migrate", "--to", "sqlite"]).expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Store(StoreCommand::Migrate {
+                to: Some(BackendKind::Sqlite)
+            }))
+        );
+        assert!(Cli::try_parse_from(["frodo", "store", "migrate", "--to", "redis"]).is_err());
+
+        let cli = Cli::try_parse_from(["frodo", "store", "recover", "--code", "ABCD-1234"])
+            .expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Store(StoreCommand::Recover {
+                code: Some("ABCD-1234".into()),
+                file: None,
+                passphrase_env: None,
+            }))
+        );
+        assert!(Cli::try_parse_from([
+            "frodo", "store", "recover", "--code", "x", "--file", "k.json"
+        ])
+        .is_err());
+        assert!(
+            Cli::try_parse_from(["frodo", "store", "backup-key", "--passphrase-env", "X"]).is_err()
+        );
+
+        let cli = Cli::try_parse_from([
+            "frodo",
+            "store",
+            "backup",
+            "all.age",
+            "--recipient",
+            "age1a",
+            "--recipient",
+            "age1b",
+        ])
+        .expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Store(StoreCommand::Backup {
+                file: "all.age".into(),
+                recipient: vec!["age1a".into(), "age1b".into()],
+                passphrase_env: None,
+            }))
+        );
+        assert!(Cli::try_parse_from([
+            "frodo",
+            "store",
+            "backup",
+            "all.age",
+            "--recipient",
+            "age1a",
+            "--passphrase-env",
+            "X"
+        ])
+        .is_err());
+
+        let cli = Cli::try_parse_from(["frodo", "store", "restore", "all.age", "--check"])
+            .expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Store(StoreCommand::Restore {
+                file: "all.age".into(),
+                identity: None,
+                passphrase_env: None,
+                check: true,
+                force: false,
+            }))
+        );
+
+        let cli = Cli::try_parse_from(["frodo", "store", "fsck", "--repair"]).expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Store(StoreCommand::Fsck { repair: true }))
+        );
+    }
+
+    #[test]
+    fn parses_export() {
+        let cli = Cli::try_parse_from([
+            "frodo",
+            "export",
+            "--as",
+            "ics",
+            "-o",
+            "tasks.ics",
+            "tag:backend",
+            "-is:done",
+        ])
+        .expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Export {
+                format: ExportFormat::Ics,
+                output: Some("tasks.ics".into()),
+                query: vec!["tag:backend".into(), "-is:done".into()],
+            })
+        );
+    }
+
+    #[test]
+    fn parses_import() {
+        let cli = Cli::try_parse_from([
+            "frodo",
+            "import",
+            "todo.txt",
+            "--as",
+            "todotxt",
+            "--dry-run",
+        ])
+        .expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Import {
+                file: "todo.txt".into(),
+                format: Some(ImportFormat::Todotxt),
+                dry_run: true,
+            })
+        );
+        assert!(Cli::try_parse_from(["frodo", "import", "x", "--as", "xml"]).is_err());
+    }
+
     #[test]
     fn parses_task_add() {
         let cli = Cli::try_parse_from([
@@ -136,10 +723,71 @@ mod tests {
                 title: "title".into(),
                 description: Some("desc".into()),
                 tag: vec!["one".into(), "two".into()],
+                priority: None,
+                due: None,
+                parent: None,
+                blocked_by: vec![],
+                repeat: None,
+            }))
+        );
+    }
+
+    #[test]
+    fn parses_task_add_priority_and_due() {
+        let cli = Cli::try_parse_from([
+            "frodo",
+            "task",
+            "add",
+            "title",
+            "--priority",
+            "high",
+            "--due",
+            "3d",
+        ])
+        .expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Task(TaskCommand::Add {
+                title: "title".into(),
+                description: None,
+                tag: vec![],
+                priority: Some(Priority::High),
+                due: Some("3d".into()),
+                parent: None,
+                blocked_by: vec![],
+                repeat: None,
             }))
         );
     }
 
+    #[test]
+    fn parses_task_add_repeat() {
+        let cli = Cli::try_parse_from(["frodo", "task", "add", "t", "--repeat", "weekly:mon"])
+            .expect("parse ok");
+        assert!(matches!(
+            cli.command,
+            Some(Command::Task(TaskCommand::Add { repeat: Some(ref r), .. }))
+                if r.to_string() == "weekly:mon"
+        ));
+        assert!(
+            Cli::try_parse_from(["frodo", "task", "add", "t", "--repeat", "sometimes"]).is_err()
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_priority() {
+        let err = Cli::try_parse_from(["frodo", "task", "add", "t", "--priority", "meh"]);
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn parses_next_with_limit() {
+        let cli = Cli::try_parse_from(["frodo", "next", "-n", "3"]).expect("parse ok");
+        assert_eq!(cli.command, Some(Command::Next { limit: 3 }));
+        let cli = Cli::try_parse_from(["frodo", "next"]).expect("parse ok");
+        assert_eq!(cli.command, Some(Command::Next { limit: 5 }));
+    }
+
     #[test]
     fn parses_task_done() {
         let cli = Cli::try_parse_from(["frodo", "task", "done", "123"]).expect("parse ok");
@@ -149,6 +797,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parses_task_graph_commands() {
+        let cli = Cli::try_parse_from(["frodo", "task", "tree"]).expect("parse ok");
+        assert_eq!(cli.command, Some(Command::Task(TaskCommand::Tree)));
+
+        let cli = Cli::try_parse_from(["frodo", "task", "block", "a", "b"]).expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Task(TaskCommand::Block {
+                id: "a".into(),
+                on: "b".into()
+            }))
+        );
+
+        let cli = Cli::try_parse_from(["frodo", "task", "parent", "a"]).expect("parse ok");
+        assert_eq!(
+            cli.command,
+            Some(Command::Task(TaskCommand::Parent {
+                id: "a".into(),
+                parent: None
+            }))
+        );
+
+        let cli = Cli::try_parse_from([
+            "frodo",
+            "task",
+            "add",
+            "sub",
+            "--parent",
+            "p",
+            "--blocked-by",
+            "x",
+            "--blocked-by",
+            "y",
+        ])
+        .expect("parse ok");
+        assert!(matches!(
+            cli.command,
+            Some(Command::Task(TaskCommand::Add { parent: Some(ref p), ref blocked_by, .. }))
+                if p == "p" && blocked_by == &vec!["x".to_string(), "y".to_string()]
+        ));
+    }
+
     #[test]
     fn parses_sync_subcommand() {
         let cli = Cli::try_parse_from(["frodo", "sync"]).expect("parse ok");
@@ -1,12 +1,19 @@
 use std::{
+    collections::BTreeMap,
     fs,
     path::{Path, PathBuf},
 };
 
 use color_eyre::Result;
 use dirs::config_dir;
+use frodo_core::{
+    query::Query,
+    workspace::{Scope, Workspace},
+};
 use serde::{Deserialize, Serialize};
 
+use crate::output::{categorized, ErrorCategory};
+
 /// User-level configuration loaded from `~/.config/frodo/config.toml` (platform-specific).
 #[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq, Eq)]
 pub struct Config {
@@ -18,6 +25,140 @@ pub struct Config {
     pub jira: Option<frodo_sync::JiraConfig>,
     /// GitHub configuration (optional).
     pub github: Option<frodo_sync::GitHubConfig>,
+    /// Prioritization weights for `frodo next` and the TUI priority sort.
+    pub priority: Option<frodo_core::priority::PriorityConfig>,
+    /// Named task queries, usable as `@name` in `frodo task list` and the TUI filter bar.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub views: BTreeMap<String, Query>,
+    /// Sync behaviour shared by all providers.
+    pub sync: Option<SyncSettings>,
+    /// Named workspaces (`[[workspaces]]`); git repositories not listed here
+    /// become implicit workspaces named after their directory.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workspaces: Vec<Workspace>,
+    /// Where the data encryption key comes from.
+    pub storage: Option<StorageSettings>,
+}
+
+impl Config {
+    /// Parse a task query, expanding `@name` references to saved views.
+    pub fn query(&self, input: &str) -> Result<Query> {
+        Query::parse_with_views(input, &self.views).map_err(|e| {
+            categorized(
+                ErrorCategory::Usage,
+                format!("invalid query `{input}`: {e}"),
+            )
+        })
+    }
+
+    /// Scope for the current directory; everything when `all` is set.
+    pub fn scope(&self, all: bool) -> Scope {
+        match std::env::current_dir() {
+            Ok(cwd) => Scope::resolve(&cwd, &self.workspaces, all),
+            Err(_) => Scope::All,
+        }
+    }
+
+    /// This config with the workspace's sync targets (GitHub `owner/repo`,
+    /// Jira project) in place of the global ones. Credentials stay global.
+    pub fn for_workspace(&self, workspace: Option<&Workspace>) -> Config {
+        let mut cfg = self.clone();
+        let Some(workspace) = workspace else {
+            return cfg;
+        };
+        if let (Some(github), Some((owner, repo))) = (
+            cfg.github.as_mut(),
+            workspace.github.as_deref().and_then(|s| s.split_once('/')),
+        ) {
+            github.owner = owner.to_string();
+            github.repo = repo.to_string();
+        }
+        if let (Some(jira), Some(project)) = (cfg.jira.as_mut(), &workspace.jira_project) {
+            jira.project_key = project.clone();
+        }
+        cfg
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq, Eq)]
+pub struct SyncSettings {
+    /// Only local tasks matching this query are pushed upstream.
+    pub scope: Option<Query>,
+}
+
+/// `[storage]`: backend and key management for the encrypted store.
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq, Eq)]
+pub struct StorageSettings {
+    /// `keyring` (OS keychain, default) or `passphrase` (Argon2id-wrapped key
+    /// file in the data dir, for machines without a keychain).
+    #[serde(default)]
+    pub key_provider: KeyProviderKind,
+    /// `file` (one encrypted file per item, default) or `sqlite` for new data
+    /// dirs; existing ones switch with `frodo store migrate --to`.
+    #[serde(default)]
+    pub backend: BackendKind,
+    /// Read the passphrase from this environment variable
+    /// (default: `FRODO_PASSPHRASE` when set, otherwise prompt).
+    pub passphrase_env: Option<String>,
+    /// Read the passphrase from this open file descriptor instead.
+    pub passphrase_fd: Option<i32>,
+    /// Argon2id cost used when the key file is created.
+    pub kdf: Option<frodo_storage::key_provider::KdfParams>,
+    /// How long an entered passphrase is remembered (seconds; 0 disables).
+    pub session_ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyProviderKind {
+    #[default]
+    Keyring,
+    Passphrase,
+}
+
+impl std::str::FromStr for KeyProviderKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keyring" => Ok(KeyProviderKind::Keyring),
+            "passphrase" => Ok(KeyProviderKind::Passphrase),
+            other => Err(format!(
+                "unknown key provider `{other}` (expected keyring or passphrase)"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    #[default]
+    File,
+    Sqlite,
+}
+
+impl BackendKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            BackendKind::File => "file",
+            BackendKind::Sqlite => "sqlite",
+        }
+    }
+}
+
+impl std::str::FromStr for BackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(BackendKind::File),
+            "sqlite" => Ok(BackendKind::Sqlite),
+            other => Err(format!(
+                "unknown storage backend `{other}` (expected file or sqlite)"
+            )),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq, Eq)]
@@ -96,6 +237,28 @@ mod tests {
             owner = "acme"
             repo = "proj"
             token = "ghp_xxx"
+            [priority]
+            due_soon_days = 3
+            [priority.tag_weights]
+            backend = 10
+            someday = -30
+            [views]
+            backend = "status:todo tag:backend -tag:someday"
+            [sync]
+            scope = "-tag:private"
+            [[workspaces]]
+            name = "frodo"
+            paths = ["/src/frodo"]
+            github = "frodo-cli/frodo-cli"
+            jira_project = "FRODO"
+            [storage]
+            key_provider = "passphrase"
+            backend = "sqlite"
+            passphrase_env = "FRODO_KEY"
+            session_ttl_secs = 600
+            [storage.kdf]
+            memory_kib = 19456
+            iterations = 2
         "#;
         let dir = tempfile::tempdir().expect("tempdir");
         let path = dir.path().join("config.toml");
@@ -124,8 +287,52 @@ mod tests {
                     token: "ghp_xxx".into(),
                     api_base: None,
                 }),
+                priority: Some(frodo_core::priority::PriorityConfig {
+                    due_soon_days: 3,
+                    tag_weights: [("backend".to_string(), 10), ("someday".to_string(), -30)]
+                        .into_iter()
+                        .collect(),
+                    ..Default::default()
+                }),
+                views: [(
+                    "backend".to_string(),
+                    Query::parse("status:todo tag:backend -tag:someday").unwrap(),
+                )]
+                .into_iter()
+                .collect(),
+                sync: Some(SyncSettings {
+                    scope: Some(Query::parse("-tag:private").unwrap()),
+                }),
+                workspaces: vec![Workspace {
+                    name: "frodo".into(),
+                    paths: vec![PathBuf::from("/src/frodo")],
+                    github: Some("frodo-cli/frodo-cli".into()),
+                    jira_project: Some("FRODO".into()),
+                }],
+                storage: Some(StorageSettings {
+                    key_provider: KeyProviderKind::Passphrase,
+                    backend: BackendKind::Sqlite,
+                    passphrase_env: Some("FRODO_KEY".into()),
+                    passphrase_fd: None,
+                    kdf: Some(frodo_storage::key_provider::KdfParams {
+                        memory_kib: 19456,
+                        iterations: 2,
+                        parallelism: 1,
+                    }),
+                    session_ttl_secs: Some(600),
+                }),
             }
         );
+
+        let scoped = cfg.for_workspace(cfg.workspaces.first());
+        let github = scoped.github.expect("github");
+        assert_eq!(
+            (github.owner.as_str(), github.repo.as_str()),
+            ("frodo-cli", "frodo-cli")
+        );
+        assert_eq!(github.token, "ghp_xxx");
+        assert_eq!(scoped.jira.expect("jira").project_key, "FRODO");
+        assert_eq!(cfg.for_workspace(None), cfg);
     }
 
     #[test]
@@ -137,6 +344,11 @@ mod tests {
             openai: None,
             jira: None,
             github: None,
+            priority: None,
+            views: BTreeMap::new(),
+            sync: None,
+            workspaces: Vec::new(),
+            storage: None,
         };
 
         write_to_path_if_missing(&cfg, &path).expect("write should succeed");
@@ -147,6 +359,16 @@ mod tests {
         assert_eq!(loaded, cfg);
     }
 
+    #[test]
+    fn query_expands_saved_views() {
+        let mut cfg = Config::default();
+        cfg.views
+            .insert("mine".into(), Query::parse("tag:me").unwrap());
+        let query = cfg.query("@mine is:open").expect("query");
+        assert_eq!(query.to_string(), "tag:me is:open");
+        assert!(cfg.query("@theirs").is_err());
+    }
+
     fn write_to_path_if_missing(config: &Config, path: &Path) -> Result<PathBuf> {
         if path.exists() {
             return Ok(path.to_path_buf());
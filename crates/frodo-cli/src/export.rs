@@ -0,0 +1,466 @@
+//! `frodo export`: write tasks out as versioned JSON (lossless, re-importable
+//! with `frodo import`), CSV, Markdown checklists or iCalendar VTODOs.
+
+use std::{collections::HashMap, path::Path, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use color_eyre::{eyre::eyre, Result};
+use frodo_core::{
+    graph::TaskGraph,
+    recurrence::Recurrence,
+    tasks::{Priority, Task, TaskRepository, TaskStatus},
+    workspace::Scope,
+};
+use frodo_task::SecureStoreTaskRepo;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    config,
+    output::{lib_err, Output},
+    storage,
+    tasks::join_query_args,
+};
+
+/// Version of the JSON export schema; bump when `Task` changes incompatibly.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Supported output formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Markdown,
+    Ics,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "ics" | "ical" | "icalendar" => Ok(ExportFormat::Ics),
+            other => Err(format!(
+                "unknown export format `{other}` (expected json, csv, markdown or ics)"
+            )),
+        }
+    }
+}
+
+/// Top-level JSON export document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportFile {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub tasks: Vec<Task>,
+}
+
+/// Export tasks in `scope` matching `query` to `output`, or stdout when absent.
+pub async fn run(
+    cfg: &config::Config,
+    scope: &Scope,
+    format: ExportFormat,
+    query: &[String],
+    output: Option<&Path>,
+    out: &Output,
+) -> Result<()> {
+    let query = cfg.query(&join_query_args(query))?;
+    let repo = SecureStoreTaskRepo::new(storage::store_from_config(cfg)?);
+    let tasks = repo.list().await.map_err(lib_err)?;
+    let now = Utc::now();
+    let graph = TaskGraph::new(&tasks);
+    let selected: Vec<Task> = tasks
+        .iter()
+        .filter(|task| scope.includes(task) && query.matches(task, &graph, now))
+        .cloned()
+        .collect();
+
+    let rendered = render(format, &selected, now)?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, rendered)
+                .map_err(|e| eyre!("failed to write {}: {e}", path.display()))?;
+            if !out.is_text() {
+                return out.object(
+                    "export",
+                    &serde_json::json!({ "count": selected.len(), "path": path }),
+                );
+            }
+            eprintln!("Exported {} task(s) to {}", selected.len(), path.display());
+        }
+        // Without -o the export itself is the result, carried as a string.
+        None if !out.is_text() => {
+            return out.object(
+                "export",
+                &serde_json::json!({ "count": selected.len(), "content": rendered }),
+            );
+        }
+        None => print!("{rendered}"),
+    }
+    Ok(())
+}
+
+/// Render `tasks` in the given format.
+pub fn render(format: ExportFormat, tasks: &[Task], now: DateTime<Utc>) -> Result<String> {
+    match format {
+        ExportFormat::Json => {
+            let file = ExportFile {
+                version: EXPORT_VERSION,
+                exported_at: now,
+                tasks: tasks.to_vec(),
+            };
+            Ok(serde_json::to_string_pretty(&file)? + "\n")
+        }
+        ExportFormat::Csv => render_csv(tasks),
+        ExportFormat::Markdown => Ok(render_markdown(tasks)),
+        ExportFormat::Ics => Ok(render_ics(tasks, now)),
+    }
+}
+
+fn status_label(status: &TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Todo => "todo",
+        TaskStatus::InProgress => "in-progress",
+        TaskStatus::Done => "done",
+    }
+}
+
+/// One row per task; column names match what `frodo import` reads.
+fn render_csv(tasks: &[Task]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record([
+        "id",
+        "title",
+        "description",
+        "tags",
+        "status",
+        "priority",
+        "due",
+        "repeat",
+        "parent_id",
+        "blocked_by",
+        "remote_key",
+        "created_at",
+        "updated_at",
+    ])?;
+    let time = |t: DateTime<Utc>| t.to_rfc3339();
+    for task in tasks {
+        let blocked_by: Vec<String> = task.blocked_by.iter().map(Uuid::to_string).collect();
+        writer.write_record([
+            task.id.to_string(),
+            task.title.clone(),
+            task.description.clone().unwrap_or_default(),
+            task.tags.join(","),
+            status_label(&task.status).to_string(),
+            task.priority.label().to_string(),
+            task.due_at.map(time).unwrap_or_default(),
+            task.recurrence
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            task.parent_id.map(|id| id.to_string()).unwrap_or_default(),
+            blocked_by.join(","),
+            task.remote_key.clone().unwrap_or_default(),
+            time(task.created_at),
+            time(task.updated_at),
+        ])?;
+    }
+    let bytes = writer.into_inner().map_err(lib_err)?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// Checklists under one heading per status, subtasks nested under their
+/// parent. Items use todo.txt tokens (`(A)`, `+tag`, `due:`) so the file
+/// imports back with `--as markdown`.
+fn render_markdown(tasks: &[Task]) -> String {
+    let present: HashMap<Uuid, &Task> = tasks.iter().map(|t| (t.id, t)).collect();
+    let mut children: HashMap<Uuid, Vec<&Task>> = HashMap::new();
+    let mut roots = Vec::new();
+    for task in tasks {
+        match task.parent_id.filter(|p| present.contains_key(p)) {
+            Some(parent) => children.entry(parent).or_default().push(task),
+            None => roots.push(task),
+        }
+    }
+
+    let mut out = String::new();
+    for (status, heading) in [
+        (TaskStatus::InProgress, "In progress"),
+        (TaskStatus::Todo, "Todo"),
+        (TaskStatus::Done, "Done"),
+    ] {
+        let section: Vec<&Task> = roots
+            .iter()
+            .copied()
+            .filter(|t| t.status == status)
+            .collect();
+        if section.is_empty() {
+            continue;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("## {heading}\n\n"));
+        for task in section {
+            markdown_item(&mut out, task, &children, 0);
+        }
+    }
+    out
+}
+
+fn markdown_item(
+    out: &mut String,
+    task: &Task,
+    children: &HashMap<Uuid, Vec<&Task>>,
+    depth: usize,
+) {
+    let indent = "  ".repeat(depth);
+    let mark = if task.status == TaskStatus::Done {
+        'x'
+    } else {
+        ' '
+    };
+    let mut line = format!("{indent}- [{mark}] ");
+    if let Some(letter) = priority_letter(task.priority) {
+        line.push_str(&format!("({letter}) "));
+    }
+    line.push_str(&task.title);
+    for tag in &task.tags {
+        line.push_str(&format!(" +{tag}"));
+    }
+    if let Some(due) = task.due_at {
+        line.push_str(&format!(" due:{}", due.format("%Y-%m-%d")));
+    }
+    out.push_str(&line);
+    out.push('\n');
+    for note in task.description.iter().flat_map(|d| d.lines()) {
+        if !note.trim().is_empty() {
+            out.push_str(&format!("{indent}  {}\n", note.trim()));
+        }
+    }
+    for child in children.get(&task.id).into_iter().flatten() {
+        markdown_item(out, child, children, depth + 1);
+    }
+}
+
+/// Inverse of the importer's todo.txt mapping; medium is the default and
+/// gets no letter.
+fn priority_letter(priority: Priority) -> Option<char> {
+    match priority {
+        Priority::Urgent => Some('A'),
+        Priority::High => Some('B'),
+        Priority::Medium => None,
+        Priority::Low => Some('D'),
+    }
+}
+
+/// An iCalendar document with one VTODO per task (RFC 5545).
+fn render_ics(tasks: &[Task], now: DateTime<Utc>) -> String {
+    let stamp = |t: DateTime<Utc>| t.format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//frodo//frodo-cli//EN".to_string(),
+    ];
+    for task in tasks {
+        lines.push("BEGIN:VTODO".into());
+        lines.push(format!("UID:{}@frodo", task.id));
+        lines.push(format!("DTSTAMP:{}", stamp(now)));
+        lines.push(format!("CREATED:{}", stamp(task.created_at)));
+        lines.push(format!("LAST-MODIFIED:{}", stamp(task.updated_at)));
+        lines.push(format!("SUMMARY:{}", ics_text(&task.title)));
+        if let Some(description) = &task.description {
+            lines.push(format!("DESCRIPTION:{}", ics_text(description)));
+        }
+        if let Some(due) = task.due_at {
+            lines.push(format!("DUE:{}", stamp(due)));
+        }
+        lines.push(format!(
+            "STATUS:{}",
+            match task.status {
+                TaskStatus::Todo => "NEEDS-ACTION",
+                TaskStatus::InProgress => "IN-PROCESS",
+                TaskStatus::Done => "COMPLETED",
+            }
+        ));
+        if task.status == TaskStatus::Done {
+            lines.push(format!("COMPLETED:{}", stamp(task.updated_at)));
+        }
+        // 1 is the highest iCalendar priority, 9 the lowest.
+        let priority = match task.priority {
+            Priority::Urgent => 1,
+            Priority::High => 3,
+            Priority::Medium => 5,
+            Priority::Low => 9,
+        };
+        lines.push(format!("PRIORITY:{priority}"));
+        if !task.tags.is_empty() {
+            let tags: Vec<String> = task.tags.iter().map(|t| ics_text(t)).collect();
+            lines.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        if let Some(parent) = task.parent_id {
+            lines.push(format!("RELATED-TO:{parent}@frodo"));
+        }
+        if let Some(rule) = &task.recurrence {
+            match rrule(rule) {
+                Some(rrule) => lines.push(format!("RRULE:{rrule}")),
+                None => lines.push(format!("X-FRODO-REPEAT:{}", ics_text(&rule.to_string()))),
+            }
+        }
+        lines.push("END:VTODO".into());
+    }
+    lines.push("END:VCALENDAR".into());
+
+    let mut out = String::new();
+    for line in lines {
+        fold(&mut out, &line);
+    }
+    out
+}
+
+/// RRULE for calendar-based rules; cron and after-completion rules have no
+/// faithful equivalent.
+fn rrule(rule: &Recurrence) -> Option<String> {
+    match rule {
+        Recurrence::Days(n) => Some(format!("FREQ=DAILY;INTERVAL={n}")),
+        Recurrence::Weekly(days) if days.is_empty() => Some("FREQ=WEEKLY".into()),
+        Recurrence::Weekly(days) => {
+            let days: Vec<String> = days
+                .iter()
+                .map(|d| d.to_string()[..2].to_ascii_uppercase())
+                .collect();
+            Some(format!("FREQ=WEEKLY;BYDAY={}", days.join(",")))
+        }
+        Recurrence::Monthly(None) => Some("FREQ=MONTHLY".into()),
+        Recurrence::Monthly(Some(day)) => Some(format!("FREQ=MONTHLY;BYMONTHDAY={day}")),
+        Recurrence::Cron(_) | Recurrence::AfterCompletion(_) => None,
+    }
+}
+
+/// Escape a TEXT value.
+fn ics_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Append `line` with CRLF, folded at 75 octets without splitting characters.
+fn fold(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            // The leading space counts towards the continuation line.
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::import::{self, ImportFormat};
+
+    fn sample() -> Vec<Task> {
+        let mut epic = Task::new(
+            "Ship 1.0".into(),
+            Some("Cut the release".into()),
+            vec!["release".into()],
+        );
+        epic.priority = Priority::High;
+        epic.due_at = Some(Utc.with_ymd_and_hms(2026, 10, 20, 23, 59, 59).unwrap());
+        let mut child = Task::new("Write changelog".into(), None, vec![]);
+        child.parent_id = Some(epic.id);
+        child.status = TaskStatus::Done;
+        let mut standup = Task::new("Standup, notes; misc".into(), None, vec![]);
+        standup.status = TaskStatus::InProgress;
+        standup.recurrence = Some("weekly:mon,thu".parse().unwrap());
+        vec![epic, child, standup]
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn json_round_trips_losslessly() {
+        let tasks = sample();
+        let json = render(ExportFormat::Json, &tasks, now()).unwrap();
+        let file: ExportFile = serde_json::from_str(&json).unwrap();
+        assert_eq!(file.version, EXPORT_VERSION);
+        assert_eq!(file.tasks, tasks);
+
+        let drafts = import::parse(ImportFormat::Json, &json, now()).unwrap();
+        assert_eq!(drafts.len(), 3);
+        assert_eq!(drafts[1].parent, Some(0));
+        assert_eq!(drafts[2].recurrence, tasks[2].recurrence);
+    }
+
+    #[test]
+    fn markdown_groups_by_status_and_reimports() {
+        let tasks = sample();
+        let markdown = render(ExportFormat::Markdown, &tasks, now()).unwrap();
+        assert_eq!(
+            markdown,
+            "## In progress\n\n\
+             - [ ] Standup, notes; misc\n\
+             \n## Todo\n\n\
+             - [ ] (B) Ship 1.0 +release due:2026-10-20\n  Cut the release\n  \
+             - [x] Write changelog\n"
+        );
+
+        let drafts = import::parse(ImportFormat::Markdown, &markdown, now()).unwrap();
+        let epic = drafts.iter().find(|d| d.title == "Ship 1.0").unwrap();
+        assert_eq!(epic.priority, Priority::High);
+        assert_eq!(epic.tags, vec!["release"]);
+        assert_eq!(epic.due_at, tasks[0].due_at);
+        assert_eq!(epic.description.as_deref(), Some("Cut the release"));
+        assert_eq!(drafts[2].parent, Some(1));
+        assert_eq!(drafts[2].status, TaskStatus::Done);
+    }
+
+    #[test]
+    fn csv_escapes_and_reimports() {
+        let tasks = sample();
+        let csv = render(ExportFormat::Csv, &tasks, now()).unwrap();
+        assert!(csv.contains("\"Standup, notes; misc\""));
+        let drafts = import::parse(ImportFormat::Csv, &csv, now()).unwrap();
+        assert_eq!(drafts[0].priority, Priority::High);
+        assert_eq!(drafts[0].due_at, tasks[0].due_at);
+        assert_eq!(drafts[2].status, TaskStatus::InProgress);
+        assert_eq!(drafts[2].recurrence, tasks[2].recurrence);
+    }
+
+    #[test]
+    fn ics_emits_vtodos() {
+        let tasks = sample();
+        let ics = render(ExportFormat::Ics, &tasks, now()).unwrap();
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VTODO").count(), 3);
+        assert!(ics.contains(&format!("UID:{}@frodo\r\n", tasks[0].id)));
+        assert!(ics.contains("DUE:20261020T235959Z\r\n"));
+        assert!(ics.contains("PRIORITY:3\r\n"));
+        assert!(ics.contains("STATUS:COMPLETED\r\n"));
+        assert!(ics.contains("SUMMARY:Standup\\, notes\\; misc\r\n"));
+        assert!(ics.contains("RRULE:FREQ=WEEKLY;BYDAY=MO,TH\r\n"));
+        assert!(ics.contains(&format!("RELATED-TO:{}@frodo", tasks[0].id)));
+
+        let mut long = String::new();
+        fold(&mut long, &format!("SUMMARY:{}", "é".repeat(60)));
+        assert!(long
+            .split("\r\n")
+            .all(|line| line.len() <= 75 && !line.contains('\u{FFFD}')));
+    }
+}
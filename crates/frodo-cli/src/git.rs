@@ -0,0 +1,823 @@
+//! Read-only access to the local git repository (plus creating branches),
+//! straight from `.git` so it works without a `git` binary or network:
+//! HEAD and refs (loose and packed), loose objects, and v2 pack files with
+//! delta chains. Only SHA-1 repositories are supported.
+
+use std::{
+    collections::{BinaryHeap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result,
+};
+use flate2::read::ZlibDecoder;
+use frodo_core::{select::short_id, tasks::Task, workspace};
+use serde::Serialize;
+
+use crate::output::{categorized, ErrorCategory};
+
+/// Longest slug taken from a task title for branch names.
+const MAX_SLUG_LEN: usize = 48;
+/// Commits read by default when listing commits for a task.
+pub const DEFAULT_SCAN: usize = 1000;
+/// Longest delta chain followed before giving up on a corrupt pack.
+const MAX_DELTA_DEPTH: usize = 64;
+
+/// Raw 20-byte SHA-1 object id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObjectId([u8; 20]);
+
+impl ObjectId {
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim();
+        if hex.len() != 40 {
+            return None;
+        }
+        let mut id = [0u8; 20];
+        for (i, byte) in id.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+        }
+        Some(Self(id))
+    }
+
+    pub fn to_hex(self) -> String {
+        self.0.iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+/// A commit as shown on a task.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Commit {
+    pub id: String,
+    pub author: String,
+    pub time: DateTime<Utc>,
+    /// First line of the message.
+    pub summary: String,
+    #[serde(skip)]
+    message: String,
+    #[serde(skip)]
+    parents: Vec<ObjectId>,
+}
+
+/// What HEAD points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Head {
+    /// On a branch (`refs/heads/<name>`), possibly without commits yet.
+    Branch(String),
+    Detached(ObjectId),
+}
+
+/// A repository found from a working directory.
+#[derive(Debug, Clone)]
+pub struct Repo {
+    /// Per-worktree git dir (holds HEAD).
+    git_dir: PathBuf,
+    /// Shared dir with refs, packed-refs and objects.
+    common_dir: PathBuf,
+}
+
+impl Repo {
+    /// The repository enclosing `dir`, if any.
+    pub fn discover(dir: &Path) -> Option<Self> {
+        let git_dir = workspace::git_dir(&workspace::repo_root(dir)?)?;
+        Some(Self {
+            common_dir: workspace::common_dir(&git_dir),
+            git_dir,
+        })
+    }
+
+    /// The repository enclosing the current directory, or a usage error.
+    pub fn current() -> Result<Self> {
+        let cwd = std::env::current_dir()?;
+        Self::discover(&cwd)
+            .ok_or_else(|| categorized(ErrorCategory::Usage, "not inside a git repository"))
+    }
+
+    pub fn head(&self) -> Result<Head> {
+        let head = fs::read_to_string(self.git_dir.join("HEAD"))?;
+        let head = head.trim();
+        if let Some(name) = head.strip_prefix("ref: refs/heads/") {
+            return Ok(Head::Branch(name.to_string()));
+        }
+        ObjectId::from_hex(head)
+            .map(Head::Detached)
+            .ok_or_else(|| eyre!("unrecognised HEAD `{head}`"))
+    }
+
+    /// Commit HEAD points at; `None` on a branch without commits.
+    pub fn head_commit(&self) -> Result<Option<ObjectId>> {
+        match self.head()? {
+            Head::Branch(name) => self.branch_target(&name),
+            Head::Detached(id) => Ok(Some(id)),
+        }
+    }
+
+    /// Commit a local branch points at, from its loose ref or `packed-refs`.
+    pub fn branch_target(&self, name: &str) -> Result<Option<ObjectId>> {
+        let refname = format!("refs/heads/{name}");
+        match fs::read_to_string(self.common_dir.join(&refname)) {
+            Ok(value) => return Ok(ObjectId::from_hex(&value)),
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+            Err(_) => {}
+        }
+        let packed = match fs::read_to_string(self.common_dir.join("packed-refs")) {
+            Ok(packed) => packed,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(packed
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+            .filter_map(|line| line.split_once(' '))
+            .find(|(_, name)| *name == refname)
+            .and_then(|(id, _)| ObjectId::from_hex(id)))
+    }
+
+    /// Create `name` at the HEAD commit and, with `switch`, point HEAD at it.
+    /// Switching never touches the index or working tree, which already
+    /// match the new branch since it starts at HEAD.
+    pub fn create_branch(&self, name: &str, switch: bool) -> Result<()> {
+        if !valid_branch_name(name) {
+            bail!("`{name}` is not a valid branch name");
+        }
+        let Some(target) = self.head_commit()? else {
+            return Err(categorized(
+                ErrorCategory::Usage,
+                "HEAD has no commits yet; commit something before branching",
+            ));
+        };
+        if self.branch_target(name)?.is_some() {
+            return Err(categorized(
+                ErrorCategory::Conflict,
+                format!("branch `{name}` already exists; switch with `git switch {name}`"),
+            ));
+        }
+        let path = self.common_dir.join("refs/heads").join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_locked(&path, &format!("{}\n", target.to_hex()))?;
+        if switch {
+            write_locked(
+                &self.git_dir.join("HEAD"),
+                &format!("ref: refs/heads/{name}\n"),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Commits reachable from HEAD whose message mentions any of `keys`,
+    /// newest first. At most `scan` commits are read.
+    pub fn commits_mentioning(&self, keys: &[String], scan: usize) -> Result<Vec<Commit>> {
+        let Some(head) = self.head_commit()? else {
+            return Ok(Vec::new());
+        };
+        let mut store = ObjectStore::open(&self.common_dir.join("objects"))?;
+        let mut found = Vec::new();
+        let mut seen = HashSet::from([head]);
+        // Newest first across merges, like `git log`. Commits are read when
+        // discovered so the queue can order them by their own time; missing
+        // parents (shallow clones) end the walk on that side.
+        let mut queue = BinaryHeap::new();
+        if let Some(commit) = store.commit(head)? {
+            queue.push(Queued(commit));
+        }
+        let mut scanned = 0;
+        while let Some(Queued(commit)) = queue.pop() {
+            if scanned == scan {
+                break;
+            }
+            scanned += 1;
+            for parent in &commit.parents {
+                if seen.insert(*parent) {
+                    if let Some(parent) = store.commit(*parent)? {
+                        queue.push(Queued(parent));
+                    }
+                }
+            }
+            if keys.iter().any(|key| mentions(&commit.message, key)) {
+                found.push(commit);
+            }
+        }
+        Ok(found)
+    }
+}
+
+/// Commit in the walk queue, ordered by time (ties by id for determinism).
+struct Queued(Commit);
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.0.time, &self.0.id).cmp(&(other.0.time, &other.0.id))
+    }
+}
+
+/// Write through `<path>.lock` and rename, as git does.
+fn write_locked(path: &Path, contents: &str) -> Result<()> {
+    let mut lock_name = path.as_os_str().to_owned();
+    lock_name.push(".lock");
+    let lock = PathBuf::from(lock_name);
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&lock)
+        .map_err(|e| eyre!("cannot lock {}: {e}", path.display()))?;
+    let written = file
+        .write_all(contents.as_bytes())
+        .and_then(|_| file.sync_all());
+    if let Err(err) = written.and_then(|_| fs::rename(&lock, path)) {
+        let _ = fs::remove_file(&lock);
+        return Err(err.into());
+    }
+    Ok(())
+}
+
+/// The subset of `git check-ref-format` rules that slugs or user input can break.
+fn valid_branch_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains("..")
+        && !name.contains("//")
+        && !name.contains("@{")
+        && !name.ends_with('/')
+        && !name.ends_with('.')
+        && !name
+            .chars()
+            .any(|c| c.is_control() || c.is_whitespace() || "~^:?*[\\".contains(c))
+        && name
+            .split('/')
+            .all(|part| !part.starts_with('.') && !part.ends_with(".lock"))
+}
+
+/// Whether `text` mentions `key` as a whole word, ignoring case
+/// (`PROJ-12` does not match `PROJ-123`).
+pub fn mentions(text: &str, key: &str) -> bool {
+    if key.is_empty() {
+        return false;
+    }
+    let text = text.to_ascii_lowercase();
+    let key = key.to_ascii_lowercase();
+    let word = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric());
+    text.match_indices(&key).any(|(start, _)| {
+        !word(text[..start].chars().next_back()) && !word(text[start + key.len()..].chars().next())
+    })
+}
+
+/// Keys that refer to `task` in branch names and commit messages: its remote
+/// issue key and its short id.
+pub fn task_keys(task: &Task) -> Vec<String> {
+    let mut keys: Vec<String> = task.remote_key.iter().cloned().collect();
+    keys.push(short_id(task.id));
+    keys
+}
+
+/// Branch name for a task: the issue key (or short id) followed by a slug of
+/// the title, e.g. `PROJ-123-fix-login-timeout` or `gh-42-...`.
+pub fn branch_name(task: &Task) -> String {
+    let prefix = match task.remote_key.as_deref() {
+        Some(key) => match key.strip_prefix('#') {
+            Some(number) => format!("gh-{number}"),
+            None => key.to_string(),
+        },
+        None => short_id(task.id),
+    };
+    let prefix: String = prefix
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let mut slug = String::new();
+    for word in task
+        .title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        if slug.len() + word.len() + 1 > MAX_SLUG_LEN {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_ascii_lowercase());
+    }
+    if slug.is_empty() {
+        prefix
+    } else {
+        format!("{prefix}-{slug}")
+    }
+}
+
+/// The task a branch belongs to: the one it was created for, else one whose
+/// issue key or short id starts the last path segment (`feature/PROJ-123-x`).
+pub fn task_for_branch<'a>(branch: &str, tasks: &'a [Task]) -> Option<&'a Task> {
+    if let Some(task) = tasks.iter().find(|t| t.branch.as_deref() == Some(branch)) {
+        return Some(task);
+    }
+    let segment = branch.rsplit('/').next().unwrap_or(branch);
+    let starts_with_key = |key: &str| {
+        let key = key
+            .strip_prefix('#')
+            .map_or_else(|| key.to_string(), |n| format!("gh-{n}"));
+        // `get` rather than slicing: branch names may be non-ASCII.
+        segment
+            .get(..key.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(&key))
+            && segment
+                .get(key.len()..)
+                .and_then(|rest| rest.chars().next())
+                .is_none_or(|c| !c.is_ascii_alphanumeric())
+    };
+    tasks
+        .iter()
+        .find(|t| task_keys(t).iter().any(|k| starts_with_key(k)))
+}
+
+/// Loose objects plus pack indexes, opened lazily per lookup.
+struct ObjectStore {
+    dir: PathBuf,
+    packs: Vec<Pack>,
+}
+
+struct Pack {
+    index: Vec<u8>,
+    data: BufReader<File>,
+}
+
+const OBJ_COMMIT: u8 = 1;
+const OBJ_OFS_DELTA: u8 = 6;
+const OBJ_REF_DELTA: u8 = 7;
+
+impl ObjectStore {
+    fn open(dir: &Path) -> Result<Self> {
+        let mut packs = Vec::new();
+        if let Ok(entries) = fs::read_dir(dir.join("pack")) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().is_some_and(|e| e == "idx") {
+                    let index = fs::read(&path)?;
+                    // Only v2 indexes (git >= 1.5.2) are read.
+                    if index.get(..8) != Some(&[0xff, b't', b'O', b'c', 0, 0, 0, 2]) {
+                        continue;
+                    }
+                    // Truncated indexes are skipped like foreign ones.
+                    if !Pack::index_is_complete(&index) {
+                        continue;
+                    }
+                    let data = BufReader::new(File::open(path.with_extension("pack"))?);
+                    packs.push(Pack { index, data });
+                }
+            }
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            packs,
+        })
+    }
+
+    fn commit(&mut self, id: ObjectId) -> Result<Option<Commit>> {
+        match self.read(id, 0)? {
+            Some((OBJ_COMMIT, body)) => Ok(Some(parse_commit(id, &body)?)),
+            Some((kind, _)) => bail!("object {} is not a commit (type {kind})", id.to_hex()),
+            None => Ok(None),
+        }
+    }
+
+    /// Object type and body.
+    fn read(&mut self, id: ObjectId, depth: usize) -> Result<Option<(u8, Vec<u8>)>> {
+        let hex = id.to_hex();
+        match File::open(self.dir.join(&hex[..2]).join(&hex[2..])) {
+            Ok(file) => {
+                let mut raw = Vec::new();
+                ZlibDecoder::new(file).read_to_end(&mut raw)?;
+                let nul = raw
+                    .iter()
+                    .position(|b| *b == 0)
+                    .ok_or_else(|| eyre!("corrupt loose object {hex}"))?;
+                let kind = match raw[..nul].split(|b| *b == b' ').next() {
+                    Some(b"commit") => OBJ_COMMIT,
+                    Some(b"tree") => 2,
+                    Some(b"blob") => 3,
+                    Some(b"tag") => 4,
+                    _ => bail!("corrupt loose object {hex}"),
+                };
+                return Ok(Some((kind, raw.split_off(nul + 1))));
+            }
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+            Err(_) => {}
+        }
+        for pack in 0..self.packs.len() {
+            if let Some(offset) = self.packs[pack].offset_of(id) {
+                return self.read_packed(pack, offset, depth).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_packed(&mut self, pack: usize, offset: u64, depth: usize) -> Result<(u8, Vec<u8>)> {
+        if depth > MAX_DELTA_DEPTH {
+            bail!("delta chain too long in pack");
+        }
+        let data = &mut self.packs[pack].data;
+        data.seek(SeekFrom::Start(offset))?;
+        let mut byte = read_byte(data)?;
+        let kind = (byte >> 4) & 7;
+        let mut size = (byte & 0x0f) as u64;
+        let mut shift = 4;
+        while byte & 0x80 != 0 {
+            byte = read_byte(data)?;
+            size |= ((byte & 0x7f) as u64) << shift;
+            shift += 7;
+        }
+        enum Base {
+            Offset(u64),
+            Id(ObjectId),
+        }
+        let base = match kind {
+            OBJ_OFS_DELTA => {
+                let mut byte = read_byte(data)?;
+                let mut back = (byte & 0x7f) as u64;
+                while byte & 0x80 != 0 {
+                    byte = read_byte(data)?;
+                    back = ((back + 1) << 7) | (byte & 0x7f) as u64;
+                }
+                let base = offset
+                    .checked_sub(back)
+                    .ok_or_else(|| eyre!("corrupt delta offset in pack"))?;
+                Some(Base::Offset(base))
+            }
+            OBJ_REF_DELTA => {
+                let mut id = [0u8; 20];
+                data.read_exact(&mut id)?;
+                Some(Base::Id(ObjectId(id)))
+            }
+            _ => None,
+        };
+        // `size` comes from the pack, so it is not trusted for preallocation.
+        let mut body = Vec::new();
+        ZlibDecoder::new(&mut *data)
+            .take(size)
+            .read_to_end(&mut body)?;
+        let (base_kind, base_body) = match base {
+            None => return Ok((kind, body)),
+            Some(Base::Offset(base)) => self.read_packed(pack, base, depth + 1)?,
+            Some(Base::Id(id)) => self
+                .read(id, depth + 1)?
+                .ok_or_else(|| eyre!("missing delta base {}", id.to_hex()))?,
+        };
+        Ok((base_kind, apply_delta(&base_body, &body)?))
+    }
+}
+
+impl Pack {
+    /// Whether a v2 index holds the fan-out table plus the names, CRCs and
+    /// offsets of every object it counts.
+    fn index_is_complete(idx: &[u8]) -> bool {
+        let Some(total) = idx.get(8 + 255 * 4..8 + 256 * 4) else {
+            return false;
+        };
+        let total = u32::from_be_bytes(total.try_into().expect("four bytes")) as usize;
+        idx.len() >= 8 + 256 * 4 + total * (20 + 4 + 4)
+    }
+
+    /// Offset of `id` in the pack, via the fan-out table and a binary search.
+    fn offset_of(&self, id: ObjectId) -> Option<u64> {
+        let idx = &self.index;
+        let fanout = |i: usize| -> Option<usize> {
+            let at = 8 + i * 4;
+            Some(u32::from_be_bytes(idx.get(at..at + 4)?.try_into().ok()?) as usize)
+        };
+        let total = fanout(255)?;
+        let first = id.0[0] as usize;
+        let (mut lo, mut hi) = (
+            if first == 0 { 0 } else { fanout(first - 1)? },
+            fanout(first)?,
+        );
+        let names = 8 + 256 * 4;
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let name = idx.get(names + mid * 20..names + mid * 20 + 20)?;
+            match name.cmp(&id.0[..]) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => {
+                    let offsets = names + total * 20 + total * 4;
+                    let at = offsets + mid * 4;
+                    let small = u32::from_be_bytes(idx.get(at..at + 4)?.try_into().ok()?);
+                    if small & 0x8000_0000 == 0 {
+                        return Some(small as u64);
+                    }
+                    let large = offsets + total * 4 + (small & 0x7fff_ffff) as usize * 8;
+                    return Some(u64::from_be_bytes(
+                        idx.get(large..large + 8)?.try_into().ok()?,
+                    ));
+                }
+            }
+        }
+        None
+    }
+}
+
+fn read_byte(reader: &mut impl Read) -> Result<u8> {
+    let mut byte = [0u8];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+/// Rebuild an object from its base and a git delta (copy/insert opcodes).
+fn apply_delta(base: &[u8], delta: &[u8]) -> Result<Vec<u8>> {
+    let corrupt = || eyre!("corrupt delta in pack");
+    let mut pos = 0;
+    let varint = |pos: &mut usize| -> Result<usize> {
+        let (mut value, mut shift) = (0usize, 0);
+        loop {
+            let byte = *delta.get(*pos).ok_or_else(corrupt)?;
+            *pos += 1;
+            value |= ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    };
+    let base_len = varint(&mut pos)?;
+    let target_len = varint(&mut pos)?;
+    if base_len != base.len() {
+        return Err(corrupt());
+    }
+    let mut out = Vec::with_capacity(target_len);
+    while pos < delta.len() {
+        let op = delta[pos];
+        pos += 1;
+        if op & 0x80 != 0 {
+            let (mut offset, mut size) = (0usize, 0usize);
+            for bit in 0..4 {
+                if op & (1 << bit) != 0 {
+                    offset |= (*delta.get(pos).ok_or_else(corrupt)? as usize) << (8 * bit);
+                    pos += 1;
+                }
+            }
+            for bit in 0..3 {
+                if op & (0x10 << bit) != 0 {
+                    size |= (*delta.get(pos).ok_or_else(corrupt)? as usize) << (8 * bit);
+                    pos += 1;
+                }
+            }
+            if size == 0 {
+                size = 0x10000;
+            }
+            out.extend_from_slice(base.get(offset..offset + size).ok_or_else(corrupt)?);
+        } else if op > 0 {
+            let len = op as usize;
+            out.extend_from_slice(delta.get(pos..pos + len).ok_or_else(corrupt)?);
+            pos += len;
+        } else {
+            return Err(corrupt());
+        }
+    }
+    if out.len() != target_len {
+        return Err(corrupt());
+    }
+    Ok(out)
+}
+
+fn parse_commit(id: ObjectId, body: &[u8]) -> Result<Commit> {
+    let text = String::from_utf8_lossy(body);
+    let (headers, message) = text.split_once("\n\n").unwrap_or((&text, ""));
+    let mut parents = Vec::new();
+    let mut author = String::new();
+    let mut time = DateTime::<Utc>::UNIX_EPOCH;
+    for line in headers.lines() {
+        if let Some(parent) = line.strip_prefix("parent ") {
+            parents.push(ObjectId::from_hex(parent).ok_or_else(|| eyre!("bad parent {parent}"))?);
+        } else if let Some(rest) = line.strip_prefix("author ") {
+            // `Name <email> 1700000000 +0100`
+            if let Some((name, stamp)) = rest.rsplit_once('>') {
+                author = name.split('<').next().unwrap_or(name).trim().to_string();
+                let seconds = stamp.split_whitespace().next().and_then(|s| s.parse().ok());
+                if let Some(at) = seconds.and_then(|s| DateTime::from_timestamp(s, 0)) {
+                    time = at;
+                }
+            }
+        }
+    }
+    Ok(Commit {
+        id: id.to_hex(),
+        author,
+        time,
+        summary: message.lines().next().unwrap_or_default().to_string(),
+        message: message.to_string(),
+        parents,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::{write::ZlibEncoder, Compression};
+
+    use super::*;
+
+    fn zlib(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn id(n: u8) -> ObjectId {
+        ObjectId([n; 20])
+    }
+
+    fn commit_body(parent: Option<ObjectId>, time: i64, message: &str) -> String {
+        let mut body = format!("tree {}\n", id(0xee).to_hex());
+        if let Some(parent) = parent {
+            body.push_str(&format!("parent {}\n", parent.to_hex()));
+        }
+        body.push_str(&format!(
+            "author Ada <ada@example.com> {time} +0000\ncommitter Ada <ada@example.com> {time} +0000\n\n{message}\n"
+        ));
+        body
+    }
+
+    /// A repo with `main` at commit 3 -> 2 -> 1: commit 1 is loose, 2 is a
+    /// full packed object and 3 a delta against it.
+    fn fixture() -> (tempfile::TempDir, Repo) {
+        let dir = tempfile::tempdir().unwrap();
+        let git = dir.path().join(".git");
+        fs::create_dir_all(git.join("objects/pack")).unwrap();
+        fs::create_dir_all(git.join("refs/heads")).unwrap();
+        fs::write(git.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(
+            git.join("packed-refs"),
+            format!(
+                "# pack-refs with: peeled\n{} refs/heads/main\n",
+                id(3).to_hex()
+            ),
+        )
+        .unwrap();
+
+        let first = commit_body(None, 1_700_000_000, "Initial commit");
+        let hex = id(1).to_hex();
+        fs::create_dir_all(git.join("objects").join(&hex[..2])).unwrap();
+        let loose = format!("commit {}\0{first}", first.len());
+        fs::write(
+            git.join("objects").join(&hex[..2]).join(&hex[2..]),
+            zlib(loose.as_bytes()),
+        )
+        .unwrap();
+
+        let second = commit_body(Some(id(1)), 1_700_000_100, "Add login form (PROJ-12)");
+        let third = commit_body(
+            Some(id(2)),
+            1_700_000_200,
+            "Fix PROJ-123 timeout\n\nDetails",
+        );
+        // Delta: copy the shared tree line from the base, insert the rest.
+        let shared = second.find("parent").unwrap();
+        let rest = &third.as_bytes()[shared..];
+        let mut delta = vec![second.len() as u8 | 0x80, 1, third.len() as u8 | 0x80, 1];
+        delta.extend([0x80 | 0x10, shared as u8]);
+        for chunk in rest.chunks(127) {
+            delta.push(chunk.len() as u8);
+            delta.extend_from_slice(chunk);
+        }
+        assert_eq!(
+            apply_delta(second.as_bytes(), &delta).unwrap(),
+            third.as_bytes()
+        );
+
+        let mut pack = b"PACK\0\0\0\x02\0\0\0\x02".to_vec();
+        let second_offset = pack.len() as u64;
+        let size = second.len();
+        pack.extend([
+            0x80 | (OBJ_COMMIT << 4) | (size & 0x0f) as u8,
+            (size >> 4) as u8,
+        ]);
+        pack.extend(zlib(second.as_bytes()));
+        let third_offset = pack.len() as u64;
+        let size = delta.len();
+        pack.extend([
+            0x80 | (OBJ_OFS_DELTA << 4) | (size & 0x0f) as u8,
+            (size >> 4) as u8,
+        ]);
+        let back = third_offset - second_offset;
+        assert!(back < 0x80);
+        pack.push(back as u8);
+        pack.extend(zlib(&delta));
+        fs::write(git.join("objects/pack/pack-test.pack"), pack).unwrap();
+
+        let mut idx = vec![0xff, b't', b'O', b'c', 0, 0, 0, 2];
+        for first_byte in 0..256u32 {
+            let count = [2u8, 3]
+                .iter()
+                .filter(|n| u32::from(**n) <= first_byte)
+                .count();
+            idx.extend((count as u32).to_be_bytes());
+        }
+        idx.extend(id(2).0);
+        idx.extend(id(3).0);
+        idx.extend([0u8; 8]);
+        idx.extend((second_offset as u32).to_be_bytes());
+        idx.extend((third_offset as u32).to_be_bytes());
+        fs::write(git.join("objects/pack/pack-test.idx"), idx).unwrap();
+
+        let repo = Repo::discover(dir.path()).unwrap();
+        (dir, repo)
+    }
+
+    #[test]
+    fn reads_loose_and_packed_commits() {
+        let (_dir, repo) = fixture();
+        assert_eq!(repo.head().unwrap(), Head::Branch("main".into()));
+        assert_eq!(repo.head_commit().unwrap(), Some(id(3)));
+
+        let found = repo.commits_mentioning(&["proj-123".into()], 100).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].summary, "Fix PROJ-123 timeout");
+        assert_eq!(found[0].author, "Ada");
+
+        let all = repo
+            .commits_mentioning(&["PROJ-12".into(), "initial".into()], 100)
+            .unwrap();
+        let summaries: Vec<&str> = all.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries, ["Add login form (PROJ-12)", "Initial commit"]);
+        assert!(repo
+            .commits_mentioning(&["initial".into()], 2)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn skips_truncated_pack_indexes() {
+        let (dir, _) = fixture();
+        let path = dir.path().join(".git/objects/pack/pack-test.idx");
+        let idx = fs::read(&path).unwrap();
+        assert!(Pack::index_is_complete(&idx));
+        for len in [8, 100, idx.len() - 1] {
+            assert!(!Pack::index_is_complete(&idx[..len]));
+            fs::write(&path, &idx[..len]).unwrap();
+            let repo = Repo::discover(dir.path()).unwrap();
+            // The packed head commit is simply not found.
+            assert!(repo
+                .commits_mentioning(&["proj-123".into()], 100)
+                .map_or(true, |found| found.is_empty()));
+        }
+    }
+
+    #[test]
+    fn creates_and_switches_branches() {
+        let (dir, repo) = fixture();
+        repo.create_branch("PROJ-123-fix-timeout", true).unwrap();
+        assert_eq!(
+            repo.head().unwrap(),
+            Head::Branch("PROJ-123-fix-timeout".into())
+        );
+        assert_eq!(repo.head_commit().unwrap(), Some(id(3)));
+        assert!(repo.create_branch("main", false).is_err());
+        assert!(repo.create_branch("bad..name", false).is_err());
+        assert!(!dir.path().join(".git/HEAD.lock").exists());
+    }
+
+    #[test]
+    fn names_branches_and_maps_them_back() {
+        let mut jira = Task::new("Fix login timeout!".into(), None, vec![]);
+        jira.remote_key = Some("PROJ-123".into());
+        let mut github = Task::new("Docs".into(), None, vec![]);
+        github.remote_key = Some("#42".into());
+        let local = Task::new("Refactor the store".into(), None, vec![]);
+
+        assert_eq!(branch_name(&jira), "PROJ-123-fix-login-timeout");
+        assert_eq!(branch_name(&github), "gh-42-docs");
+        assert_eq!(
+            branch_name(&local),
+            format!("{}-refactor-the-store", short_id(local.id))
+        );
+
+        let tasks = vec![jira.clone(), github.clone(), local.clone()];
+        let found = |branch: &str| task_for_branch(branch, &tasks).map(|t| t.id);
+        assert_eq!(found("feature/proj-123-other-words"), Some(jira.id));
+        assert_eq!(found("PROJ-1234-other"), None);
+        assert_eq!(found("gh-42"), Some(github.id));
+        assert_eq!(found(&branch_name(&local)), Some(local.id));
+        // A multi-byte character straddling the key length is no match.
+        assert_eq!(found("cafééé-x"), None);
+        assert_eq!(found("feature/ünïcödé"), None);
+        assert!(mentions("Fixes #42.", "#42"));
+        assert!(!mentions("see #421", "#42"));
+    }
+}
@@ -0,0 +1,725 @@
+//! `frodo import`: bring existing lists (Markdown checklists, todo.txt, CSV,
+//! JSON) into the encrypted store, skipping tasks that already exist.
+
+use std::{collections::HashMap, path::Path, str::FromStr};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use color_eyre::{eyre::eyre, Result};
+use frodo_core::{
+    recurrence::Recurrence,
+    tasks::{Priority, Task, TaskRepository, TaskStatus},
+    workspace::Scope,
+};
+use frodo_task::SecureStoreTaskRepo;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config,
+    export::EXPORT_VERSION,
+    output::{categorized, lib_err, ErrorCategory, Output},
+    storage,
+    tasks::parse_due,
+};
+
+/// Supported input formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Markdown,
+    Todotxt,
+    Csv,
+    Json,
+}
+
+impl FromStr for ImportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Ok(ImportFormat::Markdown),
+            "todotxt" | "todo.txt" | "txt" => Ok(ImportFormat::Todotxt),
+            "csv" => Ok(ImportFormat::Csv),
+            "json" => Ok(ImportFormat::Json),
+            other => Err(format!(
+                "unknown import format `{other}` (expected markdown, todotxt, csv or json)"
+            )),
+        }
+    }
+}
+
+impl ImportFormat {
+    /// Guess the format from a file extension.
+    fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?;
+        ext.parse().ok()
+    }
+}
+
+/// A task read from an import file, before it is stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Draft {
+    pub title: String,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub status: TaskStatus,
+    pub priority: Priority,
+    pub due_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub recurrence: Option<Recurrence>,
+    pub remote_key: Option<String>,
+    /// Index of the parent draft (nested Markdown items, or `parent_id`
+    /// pointing at another task in a CSV/JSON export).
+    pub parent: Option<usize>,
+}
+
+impl Draft {
+    fn new(title: String) -> Self {
+        Self {
+            title,
+            description: None,
+            tags: Vec::new(),
+            status: TaskStatus::Todo,
+            priority: Priority::default(),
+            due_at: None,
+            created_at: None,
+            recurrence: None,
+            remote_key: None,
+            parent: None,
+        }
+    }
+}
+
+/// Import `file` into the workspace of `scope`, printing what was (or with
+/// `dry_run`, would be) added. Titles already present in the scope are skipped.
+pub async fn run(
+    cfg: &config::Config,
+    scope: &Scope,
+    file: &Path,
+    format: Option<ImportFormat>,
+    dry_run: bool,
+    out: &Output,
+) -> Result<()> {
+    let format = format
+        .or_else(|| ImportFormat::from_path(file))
+        .ok_or_else(|| {
+            categorized(
+                ErrorCategory::Usage,
+                format!(
+                    "cannot tell the format of {}; pass --as markdown|todotxt|csv|json",
+                    file.display()
+                ),
+            )
+        })?;
+    let input = std::fs::read_to_string(file)
+        .map_err(|e| eyre!("failed to read {}: {e}", file.display()))?;
+    let drafts = parse(format, &input, Utc::now())
+        .map_err(|e| categorized(ErrorCategory::Usage, format!("{}: {e}", file.display())))?;
+
+    let repo = SecureStoreTaskRepo::new(storage::store_from_config(cfg)?);
+    let existing = scope.filter(repo.list().await.map_err(lib_err)?);
+    let mut known: HashMap<String, Uuid> = existing
+        .iter()
+        .map(|t| (dedupe_key(&t.title), t.id))
+        .collect();
+
+    // Ids per draft, so nested items can point at an imported or existing parent.
+    let mut ids: Vec<Option<Uuid>> = Vec::with_capacity(drafts.len());
+    let (mut added, mut skipped) = (0, 0);
+    let mut results = Vec::new();
+    for draft in drafts {
+        let key = dedupe_key(&draft.title);
+        if let Some(id) = known.get(&key) {
+            if out.is_text() {
+                println!("skip (exists): {}", draft.title);
+            }
+            // A nil id is a duplicate of an earlier line in the same dry run.
+            let existing = Some(*id).filter(|id| !id.is_nil());
+            results.push(json!({ "action": "skipped", "title": draft.title, "id": existing }));
+            skipped += 1;
+            ids.push(Some(*id));
+            continue;
+        }
+        if dry_run {
+            if out.is_text() {
+                println!("would add: {}", describe(&draft));
+            }
+            results.push(json!({ "action": "would_add", "title": draft.title, "id": null }));
+            added += 1;
+            known.insert(key, Uuid::nil());
+            ids.push(None);
+            continue;
+        }
+
+        // Built in full and saved once, so each task is one undoable operation.
+        let mut task = Task::new(draft.title.clone(), draft.description, draft.tags);
+        task.status = draft.status;
+        task.priority = draft.priority;
+        task.due_at = draft.due_at;
+        task.recurrence = draft.recurrence;
+        task.remote_key = draft.remote_key;
+        task.workspace = scope.name().map(str::to_string);
+        task.parent_id = draft.parent.and_then(|i| ids.get(i).copied().flatten());
+        if let Some(created_at) = draft.created_at {
+            task.created_at = created_at;
+        }
+        let task = repo.add(task).await.map_err(lib_err)?;
+        if out.is_text() {
+            println!("added: {}", task.title);
+        }
+        results.push(json!({ "action": "added", "title": task.title, "id": task.id }));
+        known.insert(key, task.id);
+        ids.push(Some(task.id));
+        added += 1;
+    }
+
+    if !out.is_text() {
+        return out.object(
+            "import",
+            &json!({
+                "dry_run": dry_run,
+                "added": added,
+                "skipped": skipped,
+                "tasks": results,
+            }),
+        );
+    }
+    let verb = if dry_run { "Would import" } else { "Imported" };
+    println!("{verb} {added} task(s); skipped {skipped} duplicate(s).");
+    Ok(())
+}
+
+fn describe(draft: &Draft) -> String {
+    let mut out = draft.title.clone();
+    if draft.status != TaskStatus::Todo {
+        out.push_str(&format!(" [{:?}]", draft.status));
+    }
+    if draft.priority != Priority::default() {
+        out.push_str(&format!(" priority:{}", draft.priority.label()));
+    }
+    if let Some(due) = draft.due_at {
+        out.push_str(&format!(" due:{}", due.format("%Y-%m-%d")));
+    }
+    if !draft.tags.is_empty() {
+        out.push_str(&format!(" tags:{}", draft.tags.join(",")));
+    }
+    out
+}
+
+/// Titles compare case-insensitively with whitespace collapsed.
+pub fn dedupe_key(title: &str) -> String {
+    title
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parse an import file into drafts, in file order.
+pub fn parse(format: ImportFormat, input: &str, now: DateTime<Utc>) -> Result<Vec<Draft>> {
+    match format {
+        ImportFormat::Markdown => parse_markdown(input, now),
+        ImportFormat::Todotxt => parse_todotxt(input, now),
+        ImportFormat::Csv => parse_csv(input, now),
+        ImportFormat::Json => parse_json(input, now),
+    }
+}
+
+/// `- [ ] task` / `- [x] done` items (also `*`, `+`, `1.`). Deeper items
+/// become subtasks; plain bullets or text under an item become its
+/// description. Titles may carry todo.txt tokens (`(A)`, `+tag`, `due:`).
+fn parse_markdown(input: &str, now: DateTime<Utc>) -> Result<Vec<Draft>> {
+    let mut drafts: Vec<Draft> = Vec::new();
+    // (indent, draft index) of the enclosing items.
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    for (number, line) in input.lines().enumerate() {
+        let indent = line
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum::<usize>();
+        let text = line.trim();
+        if text.is_empty() || text.starts_with('#') {
+            if text.starts_with('#') {
+                stack.clear();
+            }
+            continue;
+        }
+        let item = strip_bullet(text);
+        while stack.last().is_some_and(|(depth, _)| *depth >= indent) {
+            stack.pop();
+        }
+        match item.and_then(checkbox) {
+            Some((done, rest)) => {
+                let mut draft = inline(rest, now).map_err(|e| eyre!("line {}: {e}", number + 1))?;
+                if done {
+                    draft.status = TaskStatus::Done;
+                }
+                draft.parent = stack.last().map(|(_, index)| *index);
+                stack.push((indent, drafts.len()));
+                drafts.push(draft);
+            }
+            None => {
+                if let Some((_, index)) = stack.last() {
+                    let note = item.unwrap_or(text);
+                    let description = drafts[*index].description.get_or_insert_with(String::new);
+                    if !description.is_empty() {
+                        description.push('\n');
+                    }
+                    description.push_str(note);
+                }
+            }
+        }
+    }
+    Ok(drafts)
+}
+
+fn strip_bullet(text: &str) -> Option<&str> {
+    if let Some(rest) = ["- ", "* ", "+ "].iter().find_map(|b| text.strip_prefix(b)) {
+        return Some(rest.trim_start());
+    }
+    let digits = text.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let rest = &text[digits..];
+        return rest
+            .strip_prefix(". ")
+            .or_else(|| rest.strip_prefix(") "))
+            .map(str::trim_start);
+    }
+    None
+}
+
+fn checkbox(item: &str) -> Option<(bool, &str)> {
+    let rest = item.strip_prefix('[')?;
+    let mut chars = rest.chars();
+    let mark = chars.next()?;
+    let rest = chars.as_str().strip_prefix(']')?;
+    match mark {
+        ' ' => Some((false, rest.trim())),
+        'x' | 'X' => Some((true, rest.trim())),
+        _ => None,
+    }
+}
+
+/// todo.txt lines: `x 2026-10-02 2026-09-30 (A) Call Mom +family @phone due:2026-10-05`.
+fn parse_todotxt(input: &str, now: DateTime<Utc>) -> Result<Vec<Draft>> {
+    let mut drafts = Vec::new();
+    for (number, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (done, rest) = match line.strip_prefix("x ") {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, line),
+        };
+        let mut rest = rest;
+        if done {
+            // Completion date, then the optional creation date.
+            if let Some((_, after)) = leading_date(rest) {
+                rest = after;
+            }
+        }
+        let (priority, after) = leading_priority(rest);
+        rest = after;
+        let created = leading_date(rest);
+        if let Some((_, after)) = created {
+            rest = after;
+        }
+
+        let mut draft = inline(rest, now).map_err(|e| eyre!("line {}: {e}", number + 1))?;
+        if let Some(priority) = priority {
+            draft.priority = priority;
+        }
+        if done {
+            draft.status = TaskStatus::Done;
+        }
+        draft.created_at = created.map(|(date, _)| {
+            date.and_hms_opt(0, 0, 0)
+                .expect("midnight is valid")
+                .and_utc()
+        });
+        drafts.push(draft);
+    }
+    Ok(drafts)
+}
+
+fn leading_date(text: &str) -> Option<(NaiveDate, &str)> {
+    let (word, rest) = text.split_once(' ').unwrap_or((text, ""));
+    let date = NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()?;
+    Some((date, rest.trim_start()))
+}
+
+fn leading_priority(text: &str) -> (Option<Priority>, &str) {
+    let bytes = text.as_bytes();
+    if bytes.len() >= 3 && bytes[0] == b'(' && bytes[2] == b')' {
+        if let Some(priority) = letter_priority(bytes[1] as char) {
+            return (Some(priority), text[3..].trim_start());
+        }
+    }
+    (None, text)
+}
+
+/// todo.txt priorities: A is urgent, B high, C medium, anything later low.
+fn letter_priority(letter: char) -> Option<Priority> {
+    match letter {
+        'A' => Some(Priority::Urgent),
+        'B' => Some(Priority::High),
+        'C' => Some(Priority::Medium),
+        'D'..='Z' => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// Title text with todo.txt tokens pulled out: a leading `(A)` priority,
+/// `+project` and `@context` tags, `due:` and `pri:` values.
+fn inline(text: &str, now: DateTime<Utc>) -> Result<Draft> {
+    let (priority, text) = leading_priority(text.trim());
+    let mut draft = Draft::new(String::new());
+    if let Some(priority) = priority {
+        draft.priority = priority;
+    }
+    let mut words = Vec::new();
+    for word in text.split_whitespace() {
+        if let Some(tag) = word
+            .strip_prefix('+')
+            .or_else(|| word.strip_prefix('@'))
+            .filter(|t| !t.is_empty())
+        {
+            if !draft.tags.iter().any(|t| t == tag) {
+                draft.tags.push(tag.to_string());
+            }
+        } else if let Some(due) = word.strip_prefix("due:") {
+            draft.due_at = Some(parse_due(due, now)?);
+        } else if let Some(priority) = word
+            .strip_prefix("pri:")
+            .and_then(|p| p.chars().next())
+            .and_then(letter_priority)
+        {
+            draft.priority = priority;
+        } else {
+            words.push(word);
+        }
+    }
+    draft.title = words.join(" ");
+    if draft.title.is_empty() {
+        return Err(eyre!("task has no title"));
+    }
+    Ok(draft)
+}
+
+fn parse_status(value: &str) -> Result<TaskStatus> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "" | "todo" | "open" | "new" => Ok(TaskStatus::Todo),
+        "in-progress" | "in_progress" | "inprogress" | "in progress" | "doing" => {
+            Ok(TaskStatus::InProgress)
+        }
+        "done" | "x" | "closed" | "completed" | "true" | "yes" => Ok(TaskStatus::Done),
+        other => Err(eyre!("unknown status `{other}`")),
+    }
+}
+
+fn parse_priority(value: &str) -> Result<Priority> {
+    let value = value.trim();
+    // Single capital letters are todo.txt priorities; `h`, `high` etc. are ours.
+    let mut chars = value.chars();
+    if let (Some(letter), None) = (chars.next(), chars.next()) {
+        if let Some(priority) = letter_priority(letter) {
+            return Ok(priority);
+        }
+    }
+    value.parse().map_err(|e: String| eyre!(e))
+}
+
+fn split_tags(value: &str) -> Vec<String> {
+    value
+        .split([',', ';', ' '])
+        .map(|t| t.trim().trim_start_matches(['+', '@', '#']))
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// CSV with a header row. Recognised columns (case-insensitive): `title`
+/// (or `name`/`summary`), `description`/`notes`, `tags`, `status`,
+/// `priority`, `due`; others are ignored.
+fn parse_csv(input: &str, now: DateTime<Utc>) -> Result<Vec<Draft>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(input.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()?
+        .iter()
+        .map(|h| h.to_ascii_lowercase())
+        .collect();
+    let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+    let title = column(&["title", "name", "summary", "task"])
+        .ok_or_else(|| eyre!("CSV needs a `title` column"))?;
+    let description = column(&["description", "notes", "body"]);
+    let tags = column(&["tags", "labels"]);
+    let status = column(&["status", "state", "done"]);
+    let priority = column(&["priority", "pri"]);
+    let due = column(&["due", "due_at", "deadline"]);
+    let repeat = column(&["repeat", "recurrence"]);
+    let remote_key = column(&["remote_key", "key"]);
+    let id = column(&["id"]);
+    let parent_id = column(&["parent_id", "parent"]);
+
+    let mut drafts = Vec::new();
+    let mut links = Vec::new();
+    for (row, record) in reader.records().enumerate() {
+        let record = record?;
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|i| record.get(i))
+                .filter(|value| !value.is_empty())
+        };
+        // Header is line 1.
+        let line = row + 2;
+        let Some(text) = field(Some(title)) else {
+            continue;
+        };
+        let mut draft = Draft::new(text.to_string());
+        draft.description = field(description).map(str::to_string);
+        draft.tags = field(tags).map(split_tags).unwrap_or_default();
+        if let Some(value) = field(status) {
+            draft.status = parse_status(value).map_err(|e| eyre!("line {line}: {e}"))?;
+        }
+        if let Some(value) = field(priority) {
+            draft.priority = parse_priority(value).map_err(|e| eyre!("line {line}: {e}"))?;
+        }
+        if let Some(value) = field(due) {
+            draft.due_at = Some(parse_due(value, now).map_err(|e| eyre!("line {line}: {e}"))?);
+        }
+        if let Some(value) = field(repeat) {
+            draft.recurrence = Some(value.parse().map_err(|e| eyre!("line {line}: {e}"))?);
+        }
+        draft.remote_key = field(remote_key).map(str::to_string);
+        let uuid = |index| field(index).and_then(|v| Uuid::parse_str(v).ok());
+        links.push((uuid(id), uuid(parent_id)));
+        drafts.push(draft);
+    }
+    link_parents(&mut drafts, &links);
+    Ok(drafts)
+}
+
+/// Turn exported `(id, parent_id)` pairs into parent indexes; parents that
+/// are not part of the file are dropped.
+fn link_parents(drafts: &mut [Draft], links: &[(Option<Uuid>, Option<Uuid>)]) {
+    let index: HashMap<Uuid, usize> = links
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (id, _))| id.map(|id| (id, i)))
+        .collect();
+    for (draft, (_, parent)) in drafts.iter_mut().zip(links) {
+        if let Some(parent) = parent.and_then(|p| index.get(&p)) {
+            draft.parent = Some(*parent);
+        }
+    }
+}
+
+/// One task in a JSON import: frodo's own task objects, or simpler records
+/// with string fields.
+#[derive(Debug, Deserialize)]
+struct JsonTask {
+    #[serde(default)]
+    id: Option<Uuid>,
+    title: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    priority: Option<String>,
+    #[serde(default, alias = "due_at")]
+    due: Option<String>,
+    #[serde(default)]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    recurrence: Option<Recurrence>,
+    #[serde(default)]
+    remote_key: Option<String>,
+    #[serde(default)]
+    parent_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum JsonFile {
+    Tasks(Vec<JsonTask>),
+    Wrapped {
+        #[serde(default)]
+        version: Option<u32>,
+        tasks: Vec<JsonTask>,
+    },
+}
+
+/// A JSON array of tasks, or an object with a `tasks` array such as the
+/// `frodo export --as json` document.
+fn parse_json(input: &str, now: DateTime<Utc>) -> Result<Vec<Draft>> {
+    let file: JsonFile =
+        serde_json::from_str(input).map_err(|e| eyre!("invalid task JSON: {e}"))?;
+    let tasks = match file {
+        JsonFile::Wrapped {
+            version: Some(version),
+            ..
+        } if version > EXPORT_VERSION => {
+            return Err(eyre!(
+                "export version {version} is newer than this frodo supports ({EXPORT_VERSION})"
+            ))
+        }
+        JsonFile::Tasks(tasks) | JsonFile::Wrapped { tasks, .. } => tasks,
+    };
+    let links: Vec<(Option<Uuid>, Option<Uuid>)> =
+        tasks.iter().map(|t| (t.id, t.parent_id)).collect();
+    let mut drafts = tasks
+        .into_iter()
+        .enumerate()
+        .map(|(index, task)| {
+            let context = |e: color_eyre::Report| eyre!("task {}: {e}", index + 1);
+            let mut draft = Draft::new(task.title);
+            draft.description = task.description;
+            draft.tags = task.tags;
+            draft.created_at = task.created_at;
+            draft.recurrence = task.recurrence;
+            draft.remote_key = task.remote_key;
+            if let Some(status) = task.status {
+                draft.status = parse_status(&status).map_err(context)?;
+            }
+            if let Some(priority) = task.priority {
+                draft.priority = parse_priority(&priority).map_err(context)?;
+            }
+            if let Some(due) = task.due {
+                draft.due_at = Some(parse_due(&due, now).map_err(context)?);
+            }
+            Ok(draft)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    link_parents(&mut drafts, &links);
+    Ok(drafts)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap()
+    }
+
+    fn end_of(day: u32) -> Option<DateTime<Utc>> {
+        Some(Utc.with_ymd_and_hms(2026, 10, day, 23, 59, 59).unwrap())
+    }
+
+    #[test]
+    fn markdown_checklists_nest_and_collect_notes() {
+        let input = "\
+# Release
+- [ ] (B) Ship 1.0 +release due:2026-10-20
+  - [x] Write changelog
+    - mention the new importer
+  - [ ] Tag the build @ci
+* [X] Old chore
+";
+        let drafts = parse(ImportFormat::Markdown, input, now()).unwrap();
+        let titles: Vec<&str> = drafts.iter().map(|d| d.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec!["Ship 1.0", "Write changelog", "Tag the build", "Old chore"]
+        );
+        assert_eq!(drafts[0].priority, Priority::High);
+        assert_eq!(drafts[0].tags, vec!["release"]);
+        assert_eq!(drafts[0].due_at, end_of(20));
+        assert_eq!(drafts[1].status, TaskStatus::Done);
+        assert_eq!(drafts[1].parent, Some(0));
+        assert_eq!(
+            drafts[1].description.as_deref(),
+            Some("mention the new importer")
+        );
+        assert_eq!(drafts[2].parent, Some(0));
+        assert_eq!(drafts[2].tags, vec!["ci"]);
+        assert_eq!(drafts[3].parent, None);
+        assert_eq!(drafts[3].status, TaskStatus::Done);
+    }
+
+    #[test]
+    fn todotxt_lines_map_priority_dates_and_tags() {
+        let input = "\
+(A) 2026-10-01 Call Mom +family @phone due:2026-10-19
+x 2026-10-02 2026-09-30 Pay rent pri:C
+Plain task
+";
+        let drafts = parse(ImportFormat::Todotxt, input, now()).unwrap();
+        assert_eq!(drafts.len(), 3);
+        assert_eq!(drafts[0].title, "Call Mom");
+        assert_eq!(drafts[0].priority, Priority::Urgent);
+        assert_eq!(drafts[0].tags, vec!["family", "phone"]);
+        assert_eq!(drafts[0].due_at, end_of(19));
+        assert_eq!(
+            drafts[0].created_at,
+            Some(Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(drafts[1].title, "Pay rent");
+        assert_eq!(drafts[1].status, TaskStatus::Done);
+        assert_eq!(drafts[1].priority, Priority::Medium);
+        assert_eq!(
+            drafts[1].created_at,
+            Some(Utc.with_ymd_and_hms(2026, 9, 30, 0, 0, 0).unwrap())
+        );
+        assert_eq!(drafts[2].priority, Priority::default());
+    }
+
+    #[test]
+    fn csv_and_json_use_named_fields() {
+        let csv = "\
+Title,Notes,Tags,Status,Priority,Due,Owner
+Fix login,\"Users get 500, sometimes\",\"backend, auth\",in progress,high,2026-10-21,sam
+,skipped without a title,,,,,
+";
+        let drafts = parse(ImportFormat::Csv, csv, now()).unwrap();
+        assert_eq!(drafts.len(), 1);
+        assert_eq!(
+            drafts[0].description.as_deref(),
+            Some("Users get 500, sometimes")
+        );
+        assert_eq!(drafts[0].tags, vec!["backend", "auth"]);
+        assert_eq!(drafts[0].status, TaskStatus::InProgress);
+        assert_eq!(drafts[0].priority, Priority::High);
+        assert_eq!(drafts[0].due_at, end_of(21));
+
+        let err = parse(ImportFormat::Csv, "title,status\nx,someday\n", now()).unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+
+        let json = r#"{"tasks": [
+            {"title": "Ship", "status": "Done", "priority": "Urgent", "due_at": "2026-10-22T10:00:00Z"},
+            {"title": "Plan", "tags": ["q4"]}
+        ]}"#;
+        let drafts = parse(ImportFormat::Json, json, now()).unwrap();
+        assert_eq!(drafts[0].status, TaskStatus::Done);
+        assert_eq!(drafts[0].priority, Priority::Urgent);
+        assert_eq!(
+            drafts[0].due_at,
+            Some(Utc.with_ymd_and_hms(2026, 10, 22, 10, 0, 0).unwrap())
+        );
+        assert_eq!(drafts[1].tags, vec!["q4"]);
+    }
+
+    #[test]
+    fn formats_and_dedupe_keys() {
+        assert_eq!(
+            ImportFormat::from_path(Path::new("todo.txt")),
+            Some(ImportFormat::Todotxt)
+        );
+        assert_eq!(
+            ImportFormat::from_path(Path::new("NOTES.md")),
+            Some(ImportFormat::Markdown)
+        );
+        assert!("xml".parse::<ImportFormat>().is_err());
+        assert_eq!(dedupe_key("  Fix   Login "), dedupe_key("fix login"));
+    }
+}
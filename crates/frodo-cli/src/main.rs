@@ -1,12 +1,25 @@
 mod cli;
 mod config;
+mod export;
+mod git;
+mod import;
+mod next;
+mod output;
+mod report;
+mod search;
+mod select;
 mod storage;
+mod store;
 mod sync;
 mod tasks;
 mod tui;
 mod update;
+mod workspace;
 
-use crate::cli::ConfigCommand;
+use crate::{
+    cli::ConfigCommand,
+    output::{categorized, lib_err, ErrorCategory, Output, OutputFormat},
+};
 use clap::Parser;
 use color_eyre::Result;
 use frodo_agent::openai::{OpenAiAgent, OpenAiSettings};
@@ -14,41 +27,108 @@ use frodo_core::{
     agent::{Agent, AgentContext, AgentRequest, AgentResponse, EchoAgent},
     storage::SecureStore,
     tasks::{Task, TaskRepository},
+    workspace::Scope,
 };
-use frodo_storage::secure_file_store::EncryptedFileStore;
+use frodo_storage::secure_file_store::BLOB_VERSION;
 use frodo_task::SecureStoreTaskRepo;
-use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::{collections::BTreeMap, ffi::OsString};
 use tokio::runtime::Handle;
 use tracing::warn;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
-/// Entry point wiring the CLI to the (placeholder) TUI.
+/// Entry point: parse arguments, run the command, and map failures to
+/// exit codes (see `output::ErrorCategory`).
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
     init_tracing();
 
-    let cli = cli::Cli::parse();
-    let config = config::load()?;
-    match cli.command.unwrap_or(cli::Command::Tui) {
+    let args: Vec<OsString> = std::env::args_os().collect();
+    let cli = match cli::Cli::try_parse_from(&args) {
+        Ok(cli) => cli,
+        Err(err) => {
+            let format = requested_format(&args);
+            if !err.use_stderr() || format == OutputFormat::Text {
+                err.exit();
+            }
+            let message = err.render().to_string();
+            let value = output::error_json("frodo", ErrorCategory::Usage, message.trim());
+            Output::new(format).print(&value)?;
+            std::process::exit(ErrorCategory::Usage.exit_code());
+        }
+    };
+    let out = Output::new(cli.format);
+    let command = cli.command.unwrap_or(cli::Command::Tui);
+    let name = command.name();
+    if let Err(report) = run(command, cli.all, &out).await {
+        std::process::exit(out.error(name, &report));
+    }
+    Ok(())
+}
+
+/// `--format` given anywhere before `--`, for reporting argument errors in
+/// the requested format.
+fn requested_format(args: &[OsString]) -> OutputFormat {
+    let mut args = args.iter().skip(1).filter_map(|a| a.to_str());
+    while let Some(arg) = args.next() {
+        let value = match arg.strip_prefix("--format") {
+            Some("") => args.next(),
+            Some(rest) => match rest.strip_prefix('=') {
+                Some(value) => Some(value),
+                None => continue,
+            },
+            None if arg == "--" => break,
+            None => continue,
+        };
+        return value.and_then(|v| v.parse().ok()).unwrap_or_default();
+    }
+    OutputFormat::Text
+}
+
+/// Run one command. Task commands only see the workspace detected from the
+/// current directory unless `all` is set.
+async fn run(command: cli::Command, all: bool, out: &Output) -> Result<()> {
+    let config =
+        config::load().map_err(|e| categorized(ErrorCategory::Config, format!("{e:#}")))?;
+    let scope = config.scope(all);
+    match command {
         cli::Command::Tui => {
             let store = storage::store_from_config(&config)?;
             let repo: Arc<SecureStoreTaskRepo<_>> = Arc::new(SecureStoreTaskRepo::new(store));
-            let tasks = repo
-                .list()
-                .await
-                .map_err(|e| color_eyre::eyre::eyre!(e.to_string()))?;
+            let tasks = scope.filter(repo.list().await.map_err(lib_err)?);
             let handle = Handle::current();
-            tui::launch(&tasks, repo, config.clone(), handle)?
+            tui::launch(&tasks, repo, config.clone(), scope, handle)?
         }
-        cli::Command::Version => print_version(),
-        cli::Command::Health => run_health_check(&config).await?,
-        cli::Command::Config(ConfigCommand::Init) => init_config(&config)?,
-        cli::Command::Ask { prompt } => run_ask(prompt, &config).await?,
-        cli::Command::Task(cmd) => tasks::handle(cmd, &config).await?,
-        cli::Command::Sync { apply } => sync::run(&config, apply).await?,
-        cli::Command::SelfUpdate { check } => update::run(check).await?,
+        cli::Command::Version => print_version(out)?,
+        cli::Command::Health => run_health_check(&config, out).await?,
+        cli::Command::Config(ConfigCommand::Init) => init_config(&config, out)?,
+        cli::Command::Ask { prompt } => run_ask(prompt, &config, &scope, out).await?,
+        cli::Command::Task(cmd) => tasks::handle(cmd, &config, &scope, out).await?,
+        cli::Command::Workspace(cmd) => {
+            workspace::handle(cmd, &config, &config.scope(false), out).await?
+        }
+        cli::Command::Store(cmd) => store::handle(cmd, &config, out).await?,
+        cli::Command::Report(cmd) => report::handle(cmd, &config, &scope, out).await?,
+        cli::Command::Undo { count } => tasks::undo(&config, count, out).await?,
+        cli::Command::Next { limit } => next::run(&config, &scope, limit, out).await?,
+        cli::Command::Search {
+            terms,
+            limit,
+            reindex,
+        } => search::run(&config, &scope, &terms.join(" "), limit, reindex, out).await?,
+        cli::Command::Export {
+            format,
+            output,
+            query,
+        } => export::run(&config, &scope, format, &query, output.as_deref(), out).await?,
+        cli::Command::Import {
+            file,
+            format,
+            dry_run,
+        } => import::run(&config, &scope, &file, format, dry_run, out).await?,
+        cli::Command::Sync { apply } => sync::run(&config, &scope, apply, out).await?,
+        cli::Command::SelfUpdate { check } => update::run(check, out).await?,
     }
 
     Ok(())
@@ -57,40 +137,72 @@ async fn main() -> Result<()> {
 fn init_tracing() {
     // Respect user-provided filters, default to info to avoid noisy stdout.
     let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
-    let fmt_layer = tracing_subscriber::fmt::layer().with_target(false);
+    // Logs go to stderr so stdout stays parseable with `--format json`.
+    let fmt_layer = tracing_subscriber::fmt::layer()
+        .with_target(false)
+        .with_writer(std::io::stderr);
     tracing_subscriber::registry()
         .with(env_filter)
         .with(fmt_layer)
         .init();
 }
 
-fn print_version() {
-    println!("frodo-cli {}", env!("CARGO_PKG_VERSION"));
+fn print_version(out: &Output) -> Result<()> {
+    let version = env!("CARGO_PKG_VERSION");
+    if out.is_text() {
+        println!("frodo-cli {version}");
+        return Ok(());
+    }
+    out.object("version", &serde_json::json!({ "version": version }))
 }
 
 /// Runs a quick health check of the encrypted storage path.
-async fn run_health_check(config: &config::Config) -> Result<()> {
-    let store: EncryptedFileStore<_> = storage::store_from_config(config)?;
+async fn run_health_check(config: &config::Config, out: &Output) -> Result<()> {
+    let store: storage::Store = storage::store_from_config(config)?;
     run_store_health(&store).await?;
-    println!("Storage: ok");
-    Ok(())
+    let keys = store.key_provider().name();
+    let backend = store.backend().kind().name();
+    let recovery = store.recovery_records().map_err(lib_err)?.len();
+    let mut warnings = Vec::new();
+    if recovery == 0 {
+        warnings.push(
+            "no recovery code or key backup exists; losing the keychain would lose every task \
+             (run `frodo store backup-key`)",
+        );
+    }
+    if store.blob_format().await.map_err(lib_err)? < BLOB_VERSION {
+        warnings.push(
+            "stored items use an older encryption format that is not bound to their names \
+             (run `frodo store migrate`)",
+        );
+    }
+    if out.is_text() {
+        println!("Storage: ok");
+        println!("Keys: {keys}");
+        println!("Backend: {backend}");
+        for warning in &warnings {
+            println!("Warning: {warning}");
+        }
+        return Ok(());
+    }
+    out.object(
+        "health",
+        &serde_json::json!({
+            "storage": "ok",
+            "keys": keys,
+            "backend": backend,
+            "recovery": recovery,
+            "warnings": warnings,
+        }),
+    )
 }
 
 async fn run_store_health<S: SecureStore>(store: &S) -> Result<()> {
     let probe_key = "health/probe";
     let payload = b"ok";
-    store
-        .put(probe_key, payload)
-        .await
-        .map_err(|e| color_eyre::eyre::eyre!(e.to_string()))?;
-    let round_trip = store
-        .get(probe_key)
-        .await
-        .map_err(|e| color_eyre::eyre::eyre!(e.to_string()))?;
-    store
-        .delete(probe_key)
-        .await
-        .map_err(|e| color_eyre::eyre::eyre!(e.to_string()))?;
+    store.put(probe_key, payload).await.map_err(lib_err)?;
+    let round_trip = store.get(probe_key).await.map_err(lib_err)?;
+    store.delete(probe_key).await.map_err(lib_err)?;
 
     if round_trip != payload {
         color_eyre::eyre::bail!("storage round-trip failed");
@@ -98,17 +210,40 @@ async fn run_store_health<S: SecureStore>(store: &S) -> Result<()> {
     Ok(())
 }
 
-fn init_config(config: &config::Config) -> Result<()> {
-    let path = config::write_default_if_missing(config)?;
-    println!("Config initialized at {}", path.display());
-    Ok(())
+fn init_config(config: &config::Config, out: &Output) -> Result<()> {
+    let path = config::write_default_if_missing(config)
+        .map_err(|e| categorized(ErrorCategory::Config, format!("{e:#}")))?;
+    if out.is_text() {
+        println!("Config initialized at {}", path.display());
+        return Ok(());
+    }
+    out.object("config.init", &serde_json::json!({ "path": path }))
 }
 
-async fn run_ask(prompt: Vec<String>, config: &config::Config) -> Result<()> {
+async fn run_ask(
+    prompt: Vec<String>,
+    config: &config::Config,
+    scope: &Scope,
+    out: &Output,
+) -> Result<()> {
     let prompt_text = prompt.join(" ");
     let (agent_name, agent) = build_agent(config)?;
-    let tasks = load_tasks(config).await.unwrap_or_default();
-    let response = ask_with_agent(agent.as_ref(), prompt_text, &tasks).await?;
+    let tasks = context_tasks(config, scope, &prompt_text)
+        .await
+        .unwrap_or_default();
+    let response = ask_with_agent(agent.as_ref(), prompt_text, scope.name(), &tasks).await?;
+    if !out.is_text() {
+        return out.object(
+            "ask",
+            &serde_json::json!({
+                "agent": agent_name,
+                "workspace": scope.name(),
+                "content": response.message.content,
+                "summary": response.summary,
+                "context_tasks": tasks.iter().take(5).map(|t| t.id).collect::<Vec<_>>(),
+            }),
+        );
+    }
     println!("[{agent_name}] {}", response.message.content);
     if let Some(summary) = response.summary {
         println!("\nSummary: {summary}");
@@ -119,6 +254,7 @@ async fn run_ask(prompt: Vec<String>, config: &config::Config) -> Result<()> {
 async fn ask_with_agent(
     agent: &(dyn Agent + Send + Sync),
     prompt: String,
+    workspace: Option<&str>,
     tasks: &[Task],
 ) -> Result<AgentResponse> {
     let mut hints = BTreeMap::new();
@@ -130,14 +266,14 @@ async fn ask_with_agent(
         prompt,
         conversation_id: None,
         context: AgentContext {
-            workspace: None,
+            workspace: workspace.map(str::to_string),
             hints,
         },
     };
     agent
         .ask(request)
         .await
-        .map_err(|e| color_eyre::eyre::eyre!(e.to_string()))
+        .map_err(|e| categorized(ErrorCategory::Network, format!("{e:#}")))
 }
 
 fn build_agent(config: &config::Config) -> Result<(String, Arc<dyn Agent + Send + Sync>)> {
@@ -175,12 +311,19 @@ fn resolve_openai_settings(config: &config::Config) -> Option<OpenAiSettings> {
     })
 }
 
-async fn load_tasks(config: &config::Config) -> Result<Vec<Task>> {
+/// Tasks to share with the agent: the best full-text matches for the prompt,
+/// or the task list when nothing matches.
+async fn context_tasks(config: &config::Config, scope: &Scope, prompt: &str) -> Result<Vec<Task>> {
     let store = storage::store_from_config(config)?;
     let repo: SecureStoreTaskRepo<_> = SecureStoreTaskRepo::new(store);
-    repo.list()
+    let relevant = repo
+        .search_where(prompt, 5, |task| scope.includes(task))
         .await
-        .map_err(|e| color_eyre::eyre::eyre!(e.to_string()))
+        .map_err(lib_err)?;
+    if !relevant.is_empty() {
+        return Ok(relevant.into_iter().map(|(task, _)| task).collect());
+    }
+    Ok(scope.filter(repo.list().await.map_err(lib_err)?))
 }
 
 fn format_task_context(tasks: &[Task]) -> String {
@@ -214,6 +357,23 @@ mod tests {
     use super::*;
     use crate::storage;
 
+    #[test]
+    fn finds_the_requested_format_anywhere() {
+        let args = |list: &[&str]| list.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(
+            requested_format(&args(&["frodo", "--format", "json", "task", "list"])),
+            OutputFormat::Json
+        );
+        assert_eq!(
+            requested_format(&args(&["frodo", "task", "done", "--format=ndjson"])),
+            OutputFormat::Ndjson
+        );
+        assert_eq!(
+            requested_format(&args(&["frodo", "task", "add", "--", "--format", "json"])),
+            OutputFormat::Text
+        );
+    }
+
     #[tokio::test]
     async fn health_check_with_test_store_succeeds() {
         let dir = tempfile::tempdir().expect("tempdir");
@@ -226,7 +386,7 @@ mod tests {
     #[tokio::test]
     async fn ask_with_echo_agent_returns_echoed_content() {
         let agent = EchoAgent;
-        let response = ask_with_agent(&agent, "hello world".into(), &[])
+        let response = ask_with_agent(&agent, "hello world".into(), Some("frodo"), &[])
             .await
             .expect("ask should succeed");
         assert_eq!(response.message.content, "Echo: hello world");
@@ -0,0 +1,95 @@
+use chrono::Utc;
+use color_eyre::Result;
+use frodo_core::{
+    priority::{self, ScoredTask},
+    tasks::TaskRepository,
+    workspace::Scope,
+};
+use frodo_task::SecureStoreTaskRepo;
+
+use crate::{
+    config,
+    output::{lib_err, Output},
+    storage,
+};
+
+/// Print the top `limit` open tasks in `scope` by priority score, with the
+/// reasons behind each score.
+pub async fn run(cfg: &config::Config, scope: &Scope, limit: usize, out: &Output) -> Result<()> {
+    let repo = SecureStoreTaskRepo::new(storage::store_from_config(cfg)?);
+    let tasks = repo.list().await.map_err(lib_err)?;
+    let weights = cfg.priority.clone().unwrap_or_default();
+    // Rank against every task so blockers in other workspaces still count.
+    let mut ranked = priority::rank(&tasks, &weights, Utc::now());
+    ranked.retain(|scored| scope.includes(scored.task));
+    if !out.is_text() {
+        let items: Vec<serde_json::Value> = ranked
+            .iter()
+            .take(limit)
+            .enumerate()
+            .map(|(idx, scored)| {
+                serde_json::json!({
+                    "rank": idx + 1,
+                    "score": scored.score,
+                    "reasons": scored.reasons,
+                    "task": scored.task,
+                })
+            })
+            .collect();
+        return out.list("next", &items);
+    }
+
+    if ranked.is_empty() {
+        println!("Nothing to do. Add a task with `frodo task add <title>`.");
+        return Ok(());
+    }
+    for line in render(&ranked, limit) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+fn render(ranked: &[ScoredTask<'_>], limit: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (idx, scored) in ranked.iter().take(limit).enumerate() {
+        lines.push(format!(
+            "{}. {} (score {}) {}",
+            idx + 1,
+            scored.task.title,
+            scored.score,
+            scored.task.id
+        ));
+        for reason in &scored.reasons {
+            lines.push(format!("    {:+} {}", reason.points, reason.label));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use frodo_core::{
+        priority::PriorityConfig,
+        tasks::{Priority, Task},
+    };
+
+    use super::*;
+
+    #[test]
+    fn renders_top_tasks_with_reasons() {
+        let now = Utc::now();
+        let mut urgent = Task::new("Fix prod".into(), None, vec![]);
+        urgent.priority = Priority::Urgent;
+        urgent.created_at = now;
+        let mut low = Task::new("Tidy docs".into(), None, vec![]);
+        low.priority = Priority::Low;
+        low.created_at = now;
+        let tasks = vec![low, urgent];
+
+        let ranked = priority::rank(&tasks, &PriorityConfig::default(), now);
+        let lines = render(&ranked, 1);
+        assert!(lines[0].starts_with("1. Fix prod (score 60)"));
+        assert_eq!(lines[1], "    +60 priority urgent");
+        assert!(!lines.iter().any(|l| l.contains("Tidy docs")));
+    }
+}
@@ -0,0 +1,270 @@
+//! Result and error output for `--format text|json|ndjson`, and the exit
+//! codes errors map to.
+//!
+//! JSON output is one document per command:
+//! `{"command": "task.list", "ok": true, "data": ...}`; failures print
+//! `{"command": ..., "ok": false, "error": {"category", "message", "exit_code"}}`.
+//! NDJSON prints the same envelope compactly, one line per list item.
+
+use std::{fmt, str::FromStr};
+
+use color_eyre::Report;
+use frodo_core::{
+    graph::GraphError, select::SelectError, storage::SecureStoreError, tasks::TaskConflict,
+    timetrack::TimerError,
+};
+use serde::Serialize;
+use serde_json::json;
+use thiserror::Error;
+
+/// How command results are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Human-readable text (the default).
+    #[default]
+    Text,
+    /// One pretty-printed JSON document.
+    Json,
+    /// Newline-delimited JSON, one line per item.
+    Ndjson,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
+            other => Err(format!(
+                "unknown output format `{other}` (expected text, json or ndjson)"
+            )),
+        }
+    }
+}
+
+/// Error classes with stable exit codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// Anything not covered below (exit 1).
+    General,
+    /// Invalid arguments, queries or selectors (exit 2, like clap).
+    Usage,
+    /// A referenced task or entry does not exist (exit 3).
+    NotFound,
+    /// Concurrent edits, a running timer, or another state clash (exit 4).
+    Conflict,
+    /// Encrypted store or keychain failure (exit 5).
+    Storage,
+    /// Remote provider, agent or release server failure (exit 6).
+    Network,
+    /// Unreadable or invalid config file (exit 7).
+    Config,
+}
+
+impl ErrorCategory {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorCategory::General => 1,
+            ErrorCategory::Usage => 2,
+            ErrorCategory::NotFound => 3,
+            ErrorCategory::Conflict => 4,
+            ErrorCategory::Storage => 5,
+            ErrorCategory::Network => 6,
+            ErrorCategory::Config => 7,
+        }
+    }
+}
+
+/// An error message tagged with its category.
+#[derive(Debug, Error)]
+#[error("{message}")]
+pub struct CliError {
+    pub category: ErrorCategory,
+    pub message: String,
+}
+
+/// Wrap an error with an explicit category.
+pub fn categorized(category: ErrorCategory, err: impl fmt::Display) -> Report {
+    Report::new(CliError {
+        category,
+        message: err.to_string(),
+    })
+}
+
+/// Convert an error from the library crates, keeping its category.
+pub fn lib_err(err: impl Into<anyhow::Error>) -> Report {
+    let err = err.into();
+    categorized(classify_anyhow(&err), format!("{err:#}"))
+}
+
+fn classify_anyhow(err: &anyhow::Error) -> ErrorCategory {
+    for cause in err.chain() {
+        if cause.is::<TaskConflict>() || cause.is::<TimerError>() {
+            return ErrorCategory::Conflict;
+        }
+        if let Some(err) = cause.downcast_ref::<GraphError>() {
+            return match err {
+                GraphError::UnknownTask(_) => ErrorCategory::NotFound,
+                GraphError::SelfReference(_) | GraphError::Cycle { .. } => ErrorCategory::Usage,
+            };
+        }
+        if let Some(err) = cause.downcast_ref::<SecureStoreError>() {
+            return match err {
+                SecureStoreError::NotFound { .. } => ErrorCategory::NotFound,
+                SecureStoreError::Conflict { .. } => ErrorCategory::Conflict,
+                SecureStoreError::Storage { .. } => ErrorCategory::Storage,
+            };
+        }
+        if cause.is::<reqwest::Error>() {
+            return ErrorCategory::Network;
+        }
+    }
+    ErrorCategory::General
+}
+
+/// Category of a command's final error.
+pub fn classify(report: &Report) -> ErrorCategory {
+    if let Some(err) = report.downcast_ref::<CliError>() {
+        return err.category;
+    }
+    if report.downcast_ref::<SelectError>().is_some() {
+        return ErrorCategory::NotFound;
+    }
+    if report.chain().any(|cause| cause.is::<reqwest::Error>()) {
+        return ErrorCategory::Network;
+    }
+    ErrorCategory::General
+}
+
+/// Prints results in the selected format.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Output {
+    format: OutputFormat,
+}
+
+impl Output {
+    pub fn new(format: OutputFormat) -> Self {
+        Self { format }
+    }
+
+    /// Whether to print the human-readable text instead of JSON.
+    pub fn is_text(&self) -> bool {
+        self.format == OutputFormat::Text
+    }
+
+    /// Print a single result.
+    pub fn object<T: Serialize>(&self, command: &str, data: &T) -> color_eyre::Result<()> {
+        self.print(&json!({ "command": command, "ok": true, "data": data }))
+    }
+
+    /// Print a list result: one array in JSON, one line per item in NDJSON.
+    pub fn list<T: Serialize>(&self, command: &str, items: &[T]) -> color_eyre::Result<()> {
+        match self.format {
+            OutputFormat::Ndjson => {
+                for item in items {
+                    self.print(&json!({ "command": command, "ok": true, "data": item }))?;
+                }
+                Ok(())
+            }
+            _ => self.object(command, &items),
+        }
+    }
+
+    /// Report a failed command and return the process exit code.
+    pub fn error(&self, command: &str, report: &Report) -> i32 {
+        let category = classify(report);
+        if self.is_text() {
+            eprintln!("Error: {report:?}");
+        } else {
+            let value = error_json(command, category, &format!("{report:#}"));
+            // Nothing sensible is left to do if stdout is gone.
+            let _ = self.print(&value);
+        }
+        category.exit_code()
+    }
+
+    /// Print a prebuilt JSON value in the selected format.
+    pub fn print(&self, value: &serde_json::Value) -> color_eyre::Result<()> {
+        let text = match self.format {
+            OutputFormat::Json => serde_json::to_string_pretty(value)?,
+            _ => serde_json::to_string(value)?,
+        };
+        println!("{text}");
+        Ok(())
+    }
+}
+
+/// The error envelope shared by command failures and argument errors.
+pub fn error_json(command: &str, category: ErrorCategory, message: &str) -> serde_json::Value {
+    json!({
+        "command": command,
+        "ok": false,
+        "error": {
+            "category": category,
+            "message": message,
+            "exit_code": category.exit_code(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn library_errors_keep_their_category() {
+        let conflict = lib_err(TaskConflict { id: Uuid::nil() });
+        assert_eq!(classify(&conflict), ErrorCategory::Conflict);
+        assert_eq!(
+            conflict.to_string(),
+            TaskConflict { id: Uuid::nil() }.to_string()
+        );
+
+        let missing = lib_err(GraphError::UnknownTask(Uuid::nil()));
+        assert_eq!(classify(&missing), ErrorCategory::NotFound);
+
+        let storage = lib_err(SecureStoreError::Storage {
+            reason: "keyring locked".into(),
+        });
+        assert_eq!(classify(&storage), ErrorCategory::Storage);
+        assert_eq!(storage.to_string(), "storage failure: keyring locked");
+
+        let wrapped = lib_err(anyhow::Error::new(TimerError::AlreadyRunning {
+            task_id: Uuid::nil(),
+            title: "t".into(),
+        }));
+        assert_eq!(classify(&wrapped), ErrorCategory::Conflict);
+
+        assert_eq!(
+            classify(&color_eyre::eyre::eyre!("boom")),
+            ErrorCategory::General
+        );
+        assert_eq!(
+            classify(&Report::new(SelectError::NotFound("x".into()))),
+            ErrorCategory::NotFound
+        );
+    }
+
+    #[test]
+    fn error_envelope_is_stable() {
+        let value = error_json("task.done", ErrorCategory::NotFound, "no task matches `x`");
+        assert_eq!(
+            value,
+            json!({
+                "command": "task.done",
+                "ok": false,
+                "error": {
+                    "category": "not_found",
+                    "message": "no task matches `x`",
+                    "exit_code": 3
+                }
+            })
+        );
+        assert_eq!("NDJSON".parse(), Ok(OutputFormat::Ndjson));
+    }
+}
@@ -0,0 +1,154 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use color_eyre::Result;
+use frodo_core::{
+    tasks::TaskRepository,
+    timetrack::{self, format_duration, GroupBy, ReportRow},
+    workspace::Scope,
+};
+use frodo_task::SecureStoreTaskRepo;
+
+use crate::{
+    cli::ReportCommand,
+    config,
+    output::{lib_err, Output},
+    storage,
+};
+
+/// Execute a report subcommand.
+pub async fn handle(
+    cmd: ReportCommand,
+    cfg: &config::Config,
+    scope: &Scope,
+    out: &Output,
+) -> Result<()> {
+    match cmd {
+        ReportCommand::Time { since, by } => {
+            let repo = SecureStoreTaskRepo::new(storage::store_from_config(cfg)?);
+            let tasks = scope.filter(repo.list().await.map_err(lib_err)?);
+            let now = Utc::now();
+            let from = parse_since(&since, now)?;
+            let rows = timetrack::report(&tasks, from, now, by);
+            if !out.is_text() {
+                let rows: Vec<serde_json::Value> = rows
+                    .iter()
+                    .map(|row| {
+                        serde_json::json!({ "key": row.key, "seconds": row.duration.num_seconds() })
+                    })
+                    .collect();
+                return out.object(
+                    "report.time",
+                    &serde_json::json!({ "since": from, "until": now, "rows": rows }),
+                );
+            }
+            if rows.is_empty() {
+                println!("No time tracked since {}.", from.format("%Y-%m-%d %H:%M"));
+                return Ok(());
+            }
+            println!("Time tracked since {}:", from.format("%Y-%m-%d %H:%M"));
+            for line in render(&rows, by) {
+                println!("{line}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Aligned rows plus a total. Tag totals can exceed wall-clock time when
+/// tasks carry several tags, so the total is omitted there.
+fn render(rows: &[ReportRow], by: GroupBy) -> Vec<String> {
+    let width = rows
+        .iter()
+        .map(|r| r.key.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines: Vec<String> = rows
+        .iter()
+        .map(|row| format!("  {:<width$}  {}", row.key, format_duration(row.duration)))
+        .collect();
+    if by != GroupBy::Tag {
+        let total = rows
+            .iter()
+            .fold(Duration::zero(), |acc, row| acc + row.duration);
+        lines.push(format!("  {:<width$}  {}", "total", format_duration(total)));
+    }
+    lines
+}
+
+/// Start of a report window (UTC): a weekday name (its most recent
+/// occurrence, today included), `today`, `yesterday`, `YYYY-MM-DD`, or an
+/// offset such as `7d`, `2w` or `12h` before now.
+pub fn parse_since(input: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let input = input.trim().to_ascii_lowercase();
+    let midnight = |date: NaiveDate| date.and_hms_opt(0, 0, 0).expect("midnight").and_utc();
+    let today = now.date_naive();
+
+    match input.as_str() {
+        "today" => return Ok(midnight(today)),
+        "yesterday" => return Ok(midnight(today - Duration::days(1))),
+        _ => {}
+    }
+    if let Ok(weekday) = input.parse::<Weekday>() {
+        let back =
+            (7 + today.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+        return Ok(midnight(today - Duration::days(i64::from(back))));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&input, "%Y-%m-%d") {
+        return Ok(midnight(date));
+    }
+    if let Some(unit) = input.chars().last() {
+        if let Ok(amount) = input[..input.len() - unit.len_utf8()].parse::<i64>() {
+            match unit {
+                'd' => return Ok(now - Duration::days(amount)),
+                'w' => return Ok(now - Duration::weeks(amount)),
+                'h' => return Ok(now - Duration::hours(amount)),
+                _ => {}
+            }
+        }
+    }
+    color_eyre::eyre::bail!(
+        "invalid --since `{input}` (use a weekday, today, yesterday, YYYY-MM-DD, or e.g. 7d/2w)"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn parses_since_relative_to_now() {
+        // Sunday 2026-10-18.
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 15, 30, 0).unwrap();
+        let day = |d| Utc.with_ymd_and_hms(2026, 10, d, 0, 0, 0).unwrap();
+        assert_eq!(parse_since("monday", now).unwrap(), day(12));
+        assert_eq!(parse_since("sun", now).unwrap(), day(18));
+        assert_eq!(parse_since("yesterday", now).unwrap(), day(17));
+        assert_eq!(parse_since("2026-10-01", now).unwrap(), day(1));
+        assert_eq!(parse_since("2d", now).unwrap(), now - Duration::days(2));
+        assert!(parse_since("someday", now).is_err());
+    }
+
+    #[test]
+    fn renders_aligned_rows_with_total() {
+        let rows = vec![
+            ReportRow {
+                key: "api".into(),
+                duration: Duration::minutes(90),
+            },
+            ReportRow {
+                key: "frontend".into(),
+                duration: Duration::minutes(20),
+            },
+        ];
+        assert_eq!(
+            render(&rows, GroupBy::Task),
+            vec![
+                "  api       1h 30m",
+                "  frontend  20m",
+                "  total     1h 50m"
+            ]
+        );
+        assert_eq!(render(&rows, GroupBy::Tag).len(), 2);
+    }
+}
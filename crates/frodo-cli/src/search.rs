@@ -0,0 +1,123 @@
+use std::io::IsTerminal;
+
+use color_eyre::Result;
+use frodo_core::{
+    search::{self, SearchHit},
+    tasks::Task,
+    workspace::Scope,
+};
+use frodo_task::SecureStoreTaskRepo;
+
+use crate::{
+    config,
+    output::{lib_err, Output},
+    storage,
+};
+
+/// Width of the description excerpt shown under each result.
+const SNIPPET_WIDTH: usize = 80;
+
+/// Print ranked search results in `scope` with highlighted snippets, or
+/// rebuild the index.
+pub async fn run(
+    cfg: &config::Config,
+    scope: &Scope,
+    query: &str,
+    limit: usize,
+    reindex: bool,
+    out: &Output,
+) -> Result<()> {
+    let repo = SecureStoreTaskRepo::new(storage::store_from_config(cfg)?);
+    if reindex && !out.is_text() {
+        let count = repo.reindex().await.map_err(lib_err)?;
+        if query.trim().is_empty() {
+            return out.object("search", &serde_json::json!({ "indexed": count }));
+        }
+    } else if reindex {
+        let count = repo.reindex().await.map_err(lib_err)?;
+        println!("Indexed {count} tasks.");
+        if query.trim().is_empty() {
+            return Ok(());
+        }
+    }
+
+    let results = repo
+        .search_where(query, limit, |task| scope.includes(task))
+        .await
+        .map_err(lib_err)?;
+    if !out.is_text() {
+        let hits: Vec<serde_json::Value> = results
+            .iter()
+            .map(|(task, hit)| {
+                serde_json::json!({ "score": hit.score, "terms": hit.terms, "task": task })
+            })
+            .collect();
+        return out.list("search", &hits);
+    }
+    if results.is_empty() {
+        println!("No tasks match `{query}`.");
+        return Ok(());
+    }
+    let highlight: fn(&str) -> String = if std::io::stdout().is_terminal() {
+        |word| format!("\x1b[1;33m{word}\x1b[0m")
+    } else {
+        |word| format!("**{word}**")
+    };
+    for line in render(&results, highlight) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+fn render(results: &[(Task, SearchHit)], highlight: fn(&str) -> String) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (task, hit) in results {
+        let title = search::snippet(&task.title, &hit.terms, usize::MAX, highlight)
+            .unwrap_or_else(|| task.title.clone());
+        lines.push(format!("{title} ({:.2}) {}", hit.score, task.id));
+        if !task.tags.is_empty() {
+            let tags: Vec<String> = task
+                .tags
+                .iter()
+                .map(|tag| {
+                    search::snippet(tag, &hit.terms, usize::MAX, highlight)
+                        .unwrap_or_else(|| tag.clone())
+                })
+                .collect();
+            lines.push(format!("    tags: {}", tags.join(", ")));
+        }
+        if let Some(excerpt) = task
+            .description
+            .as_deref()
+            .and_then(|desc| search::snippet(desc, &hit.terms, SNIPPET_WIDTH, highlight))
+        {
+            lines.push(format!("    {excerpt}"));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use frodo_core::search::SearchIndex;
+
+    use super::*;
+
+    #[test]
+    fn renders_results_with_highlighted_snippets() {
+        let task = Task::new(
+            "Rotate signing keys".into(),
+            Some("Do this before the release so signing uses the new HSM".into()),
+            vec!["security".into()],
+        );
+        let hit = SearchIndex::build([&task]).search("signing", 1).remove(0);
+        let lines = render(&[(task.clone(), hit)], |w| format!("[{w}]"));
+        assert!(lines[0].starts_with("Rotate [signing] keys ("));
+        assert!(lines[0].ends_with(&task.id.to_string()));
+        assert_eq!(lines[1], "    tags: security");
+        assert_eq!(
+            lines[2],
+            "    Do this before the release so [signing] uses the new HSM"
+        );
+    }
+}
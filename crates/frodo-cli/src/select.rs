@@ -0,0 +1,168 @@
+//! Task selectors for command arguments: full or short ids, numeric indexes from
+//! the last `frodo task list` in this shell, remote keys and fuzzy titles.
+//! Ambiguous matches open a numbered picker when stdin is a terminal.
+
+use std::io::{BufRead, IsTerminal, Write};
+
+use color_eyre::Result;
+use frodo_core::{
+    select::{self, short_id, Selection},
+    storage::{SecureStore, SecureStoreError},
+    tasks::{Task, TaskRepository},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::output::{categorized, lib_err, ErrorCategory};
+
+/// Store key for the ids shown by the last listing.
+const LAST_LIST_KEY: &str = "cli/last-list";
+
+/// Ids of the last listing, tied to the shell that ran it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LastList {
+    session: String,
+    ids: Vec<Uuid>,
+}
+
+/// Remember the display order of a listing for numeric selectors.
+pub async fn remember_listing(store: &impl SecureStore, ids: &[Uuid]) -> Result<()> {
+    let last = LastList {
+        session: session_id(),
+        ids: ids.to_vec(),
+    };
+    store
+        .put(LAST_LIST_KEY, &serde_json::to_vec(&last)?)
+        .await
+        .map_err(lib_err)
+}
+
+async fn recent_ids(store: &impl SecureStore) -> Result<Vec<Uuid>> {
+    match store.get(LAST_LIST_KEY).await {
+        Ok(bytes) => {
+            let last: LastList = serde_json::from_slice(&bytes).unwrap_or_default();
+            Ok(if last.session == session_id() {
+                last.ids
+            } else {
+                Vec::new()
+            })
+        }
+        Err(SecureStoreError::NotFound { .. }) => Ok(Vec::new()),
+        Err(err) => Err(lib_err(err)),
+    }
+}
+
+/// The invoking shell, so indexes from one terminal don't leak into another.
+#[cfg(unix)]
+fn session_id() -> String {
+    std::os::unix::process::parent_id().to_string()
+}
+
+#[cfg(not(unix))]
+fn session_id() -> String {
+    "default".into()
+}
+
+/// Snapshot of tasks and the last listing used to resolve selectors.
+pub struct Selector {
+    tasks: Vec<Task>,
+    recent: Vec<Uuid>,
+}
+
+impl Selector {
+    pub async fn load(repo: &impl TaskRepository, store: &impl SecureStore) -> Result<Self> {
+        let tasks = repo.list().await.map_err(lib_err)?;
+        Ok(Self {
+            tasks,
+            recent: recent_ids(store).await?,
+        })
+    }
+
+    /// A loaded task by id.
+    pub fn task(&self, id: Uuid) -> Option<&Task> {
+        self.tasks.iter().find(|t| t.id == id)
+    }
+
+    /// Resolve one selector to a task id, asking the user when ambiguous.
+    pub fn resolve(&self, input: &str) -> Result<Uuid> {
+        match select::resolve(input, &self.tasks, &self.recent)? {
+            Selection::One(task) => Ok(task.id),
+            Selection::Ambiguous(candidates) => {
+                if std::io::stdin().is_terminal() && std::io::stderr().is_terminal() {
+                    let stdin = std::io::stdin();
+                    pick(input, &candidates, stdin.lock(), std::io::stderr())
+                } else {
+                    Err(categorized(
+                        ErrorCategory::Usage,
+                        format!(
+                            "`{input}` matches several tasks:\n{}\nuse an id to pick one",
+                            describe(&candidates).join("\n")
+                        ),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+fn describe(candidates: &[&Task]) -> Vec<String> {
+    candidates
+        .iter()
+        .enumerate()
+        .map(|(i, task)| {
+            format!(
+                "  {:>2}) {} [{:?}] {}",
+                i + 1,
+                short_id(task.id),
+                task.status,
+                task.title
+            )
+        })
+        .collect()
+}
+
+/// Numbered picker; an empty answer cancels.
+fn pick(
+    input: &str,
+    candidates: &[&Task],
+    mut answer: impl BufRead,
+    mut out: impl Write,
+) -> Result<Uuid> {
+    writeln!(out, "`{input}` matches several tasks:")?;
+    for line in describe(candidates) {
+        writeln!(out, "{line}")?;
+    }
+    loop {
+        write!(out, "Pick 1-{} (enter to cancel): ", candidates.len())?;
+        out.flush()?;
+        let mut line = String::new();
+        answer.read_line(&mut line)?;
+        let line = line.trim();
+        if line.is_empty() {
+            return Err(categorized(ErrorCategory::Usage, "cancelled"));
+        }
+        match line.parse::<usize>() {
+            Ok(n) if (1..=candidates.len()).contains(&n) => return Ok(candidates[n - 1].id),
+            _ => writeln!(out, "`{line}` is not one of the choices")?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picker_retries_until_a_valid_choice() {
+        let a = Task::new("Fix login".into(), None, vec![]);
+        let b = Task::new("Fix logout".into(), None, vec![]);
+        let mut out = Vec::new();
+        let id = pick("fix", &[&a, &b], "9\n2\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(id, b.id);
+        let shown = String::from_utf8(out).unwrap();
+        assert!(shown.contains("Fix logout"));
+        assert!(shown.contains("`9` is not one of the choices"));
+
+        assert!(pick("fix", &[&a, &b], "\n".as_bytes(), Vec::new()).is_err());
+    }
+}
@@ -1,41 +1,269 @@
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use crate::config::Config;
+use crate::{
+    config::{BackendKind, Config, KeyProviderKind},
+    output::{categorized, lib_err, ErrorCategory},
+};
+use async_trait::async_trait;
 use color_eyre::Result;
-use dirs::data_dir;
+use dirs::{data_dir, runtime_dir};
+use frodo_core::storage::SecureStoreError;
+#[cfg(test)]
+use frodo_storage::key_provider::InMemoryKeyProvider;
 use frodo_storage::{
-    key_provider::{InMemoryKeyProvider, KeyringProvider},
-    secure_file_store::EncryptedFileStore,
+    key_provider::{
+        KeyError, KeyMaterial, KeyProvider, KeyringProvider, PassphraseKeyProvider,
+        PassphraseSource, SessionCache,
+    },
+    secure_file_store::{BlobBackend, EncryptedFileStore, FileBlobs, StoredBlob, TransferReport},
+    sqlite_store::SqliteBlobs,
 };
 use tracing::debug;
 
+/// Key file of the passphrase provider, inside the data dir.
+const KEY_FILE: &str = ".keys";
+/// Passphrase env var used when none is configured.
+const PASSPHRASE_ENV: &str = "FRODO_PASSPHRASE";
+const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Key provider selected by `[storage] key_provider`.
+pub enum DataKeys {
+    Keyring(KeyringProvider),
+    Passphrase(Box<PassphraseKeyProvider>),
+}
+
+impl DataKeys {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataKeys::Keyring(_) => "keyring",
+            DataKeys::Passphrase(_) => "passphrase",
+        }
+    }
+
+    /// The provider of `kind`, configured from `[storage]`.
+    pub fn from_config(config: &Config, kind: KeyProviderKind) -> Result<Self> {
+        let root = data_root(config)?;
+        let settings = config.storage.clone().unwrap_or_default();
+        if kind == KeyProviderKind::Keyring {
+            return Ok(DataKeys::Keyring(KeyringProvider::new(
+                "frodo-cli",
+                "data-key",
+            )));
+        }
+        let source = match (settings.passphrase_fd, settings.passphrase_env) {
+            (Some(fd), _) => PassphraseSource::Fd(fd),
+            (None, Some(name)) => PassphraseSource::Env(name),
+            (None, None) if std::env::var_os(PASSPHRASE_ENV).is_some() => {
+                PassphraseSource::Env(PASSPHRASE_ENV.to_string())
+            }
+            (None, None) => PassphraseSource::Prompt,
+        };
+        let mut provider = PassphraseKeyProvider::new(root.join(KEY_FILE), source)
+            .with_params(settings.kdf.unwrap_or_default());
+        let ttl = settings
+            .session_ttl_secs
+            .map_or(DEFAULT_SESSION_TTL, Duration::from_secs);
+        // Only cache on a per-user tmpfs, never in a persistent directory.
+        if let Some(dir) = runtime_dir().filter(|_| !ttl.is_zero()) {
+            provider = provider.with_session(SessionCache::new(dir.join("frodo/session"), ttl));
+        }
+        Ok(DataKeys::Passphrase(Box::new(provider)))
+    }
+}
+
+#[async_trait]
+impl KeyProvider for DataKeys {
+    async fn get_or_create(&self) -> Result<KeyMaterial, KeyError> {
+        match self {
+            DataKeys::Keyring(p) => p.get_or_create().await,
+            DataKeys::Passphrase(p) => p.get_or_create().await,
+        }
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<KeyMaterial>, KeyError> {
+        match self {
+            DataKeys::Keyring(p) => p.get(id).await,
+            DataKeys::Passphrase(p) => p.get(id).await,
+        }
+    }
+
+    async fn rotate(&self) -> Result<KeyMaterial, KeyError> {
+        match self {
+            DataKeys::Keyring(p) => p.rotate().await,
+            DataKeys::Passphrase(p) => p.rotate().await,
+        }
+    }
+
+    async fn retire(&self, id: &str) -> Result<(), KeyError> {
+        match self {
+            DataKeys::Keyring(p) => p.retire(id).await,
+            DataKeys::Passphrase(p) => p.retire(id).await,
+        }
+    }
+}
+
+/// Blob backend of the data dir (see [`open_blobs`]).
+pub enum Blobs {
+    Files(FileBlobs),
+    Sqlite(SqliteBlobs),
+}
+
+impl Blobs {
+    pub fn kind(&self) -> BackendKind {
+        match self {
+            Blobs::Files(_) => BackendKind::File,
+            Blobs::Sqlite(_) => BackendKind::Sqlite,
+        }
+    }
+}
+
+impl BlobBackend for Blobs {
+    fn names(&self) -> Result<Vec<String>, SecureStoreError> {
+        match self {
+            Blobs::Files(b) => b.names(),
+            Blobs::Sqlite(b) => b.names(),
+        }
+    }
+
+    fn read(&self, name: &str) -> Result<Option<StoredBlob>, SecureStoreError> {
+        match self {
+            Blobs::Files(b) => b.read(name),
+            Blobs::Sqlite(b) => b.read(name),
+        }
+    }
+
+    fn apply(&self, changes: Vec<(String, Option<StoredBlob>)>) -> Result<(), SecureStoreError> {
+        match self {
+            Blobs::Files(b) => b.apply(changes),
+            Blobs::Sqlite(b) => b.apply(changes),
+        }
+    }
+
+    fn recover(&self) -> Result<(), SecureStoreError> {
+        match self {
+            Blobs::Files(b) => b.recover(),
+            Blobs::Sqlite(b) => b.recover(),
+        }
+    }
+
+    fn batch_pending(&self) -> bool {
+        match self {
+            Blobs::Files(b) => b.batch_pending(),
+            Blobs::Sqlite(b) => b.batch_pending(),
+        }
+    }
+
+    fn quarantine(&self, name: &str, target: &Path) -> Result<(), SecureStoreError> {
+        match self {
+            Blobs::Files(b) => b.quarantine(name, target),
+            Blobs::Sqlite(b) => b.quarantine(name, target),
+        }
+    }
+
+    fn contains(&self, name: &str) -> Result<bool, SecureStoreError> {
+        match self {
+            Blobs::Files(b) => b.contains(name),
+            Blobs::Sqlite(b) => b.contains(name),
+        }
+    }
+}
+
+/// The encrypted store as the CLI opens it.
+pub type Store = EncryptedFileStore<DataKeys, Blobs>;
+
 /// Resolve the default data directory for Frodo.
 pub fn default_data_dir() -> Result<PathBuf> {
     let base = data_dir().ok_or_else(|| color_eyre::eyre::eyre!("no data dir available"))?;
     Ok(base.join("frodo"))
 }
 
-/// Build a production-grade encrypted store using the OS keychain.
-pub fn production_store() -> Result<EncryptedFileStore<KeyringProvider>> {
-    let root = default_data_dir()?;
-    debug!(?root, "initializing encrypted store");
-    Ok(EncryptedFileStore::new(
-        root,
-        KeyringProvider::new("frodo-cli", "data-key"),
-    ))
+fn data_root(config: &Config) -> Result<PathBuf> {
+    match &config.data_dir {
+        Some(root) => Ok(root.clone()),
+        None => default_data_dir(),
+    }
 }
 
-/// Build a store using config overrides.
-pub fn store_from_config(config: &Config) -> Result<EncryptedFileStore<KeyringProvider>> {
-    if let Some(root) = &config.data_dir {
-        debug!(?root, "initializing encrypted store (config override)");
-        return Ok(EncryptedFileStore::new(
-            root.clone(),
-            KeyringProvider::new("frodo-cli", "data-key"),
-        ));
+/// Build the encrypted store, honouring the config's data dir, backend and
+/// key provider.
+pub fn store_from_config(config: &Config) -> Result<Store> {
+    let root = data_root(config)?;
+    let settings = config.storage.clone().unwrap_or_default();
+    let blobs = open_blobs(&root, settings.backend)?;
+    let keys = DataKeys::from_config(config, settings.key_provider)?;
+    debug!(
+        ?root,
+        keys = keys.name(),
+        backend = blobs.kind().name(),
+        "initializing encrypted store"
+    );
+    Ok(EncryptedFileStore::with_backend(root, keys, blobs))
+}
+
+/// The backend holding the data dir's items: a SQLite database once one
+/// exists, otherwise the configured backend. Configuring `sqlite` over a
+/// dir that already holds item files is refused rather than hiding them.
+fn open_blobs(root: &Path, configured: BackendKind) -> Result<Blobs> {
+    if SqliteBlobs::exists(root) {
+        return Ok(Blobs::Sqlite(SqliteBlobs::open(root).map_err(lib_err)?));
+    }
+    let files = FileBlobs::new(root);
+    match configured {
+        BackendKind::File => Ok(Blobs::Files(files)),
+        BackendKind::Sqlite if !files.names().map_err(lib_err)?.is_empty() => Err(categorized(
+            ErrorCategory::Storage,
+            format!(
+                "{} holds items in files; run `frodo store migrate --to sqlite` first \
+                 or set `backend = \"file\"` under [storage]",
+                root.display()
+            ),
+        )),
+        BackendKind::Sqlite => Ok(Blobs::Sqlite(SqliteBlobs::open(root).map_err(lib_err)?)),
     }
+}
 
-    production_store()
+/// Move every item of the data dir into the `to` backend. Re-running after
+/// an interruption finishes the move; moving back to files deletes the
+/// emptied database.
+pub async fn migrate_backend(config: &Config, to: BackendKind) -> Result<TransferReport> {
+    let root = data_root(config)?;
+    let source = match to {
+        BackendKind::Sqlite => Blobs::Files(FileBlobs::new(&root)),
+        BackendKind::File if SqliteBlobs::exists(&root) => {
+            Blobs::Sqlite(SqliteBlobs::open(&root).map_err(lib_err)?)
+        }
+        BackendKind::File => {
+            return Ok(TransferReport {
+                moved: 0,
+                skipped: 0,
+            })
+        }
+    };
+    let kind = config
+        .storage
+        .as_ref()
+        .map(|s| s.key_provider)
+        .unwrap_or_default();
+    let keys = DataKeys::from_config(config, kind)?;
+    let source = EncryptedFileStore::with_backend(&root, keys, source);
+    let report = match to {
+        BackendKind::Sqlite => {
+            let target = SqliteBlobs::open(&root).map_err(lib_err)?;
+            source.transfer_to(&target).await
+        }
+        // Delete the emptied database while still holding the store lock, so
+        // no other process picks it as the backend and writes into it.
+        BackendKind::File => {
+            source
+                .transfer_then(&FileBlobs::new(&root), || SqliteBlobs::remove(&root))
+                .await
+        }
+    }
+    .map_err(lib_err)?;
+    Ok(report)
 }
 
 /// Helper for tests to construct a store rooted at a temp dir with an in-memory key.
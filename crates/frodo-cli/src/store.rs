@@ -0,0 +1,445 @@
+use std::{
+    io::{BufRead, IsTerminal, Write},
+    path::Path,
+};
+
+use chrono::DateTime;
+use color_eyre::Result;
+use frodo_core::storage::SecureStore;
+use frodo_storage::{
+    backup::{self, Archive, Protection, Unlock},
+    key_provider::PassphraseSource,
+    recovery::{KeyBackup, RecoveryCode},
+    secure_file_store::{CheckReport, QUARANTINE_DIR},
+};
+use frodo_task::{validate_blob, SecureStoreTaskRepo};
+
+use crate::{
+    cli::StoreCommand,
+    config,
+    output::{categorized, lib_err, ErrorCategory, Output},
+    storage::{self, DataKeys},
+};
+
+/// Execute a store maintenance subcommand.
+pub async fn handle(cmd: StoreCommand, cfg: &config::Config, out: &Output) -> Result<()> {
+    // Moving backends opens both sides itself; the configured one may not
+    // open until the move is done.
+    if let StoreCommand::Migrate { to: Some(to) } = cmd {
+        let report = storage::migrate_backend(cfg, to).await?;
+        if !out.is_text() {
+            return out.object("store.migrate", &report);
+        }
+        println!(
+            "Moved {} item(s) into the {} backend.",
+            report.moved,
+            to.name()
+        );
+        if report.skipped > 0 {
+            println!(
+                "Kept {} item(s) already there from an earlier run.",
+                report.skipped
+            );
+        }
+        println!(
+            "Set `backend = \"{}\"` under [storage] so the config matches.",
+            to.name()
+        );
+        return Ok(());
+    }
+    let store = storage::store_from_config(cfg)?;
+    match cmd {
+        StoreCommand::RotateKey => {
+            let report = store.rotate_key().await.map_err(lib_err)?;
+            if !out.is_text() {
+                return out.object("store.rotate-key", &report);
+            }
+            if report.resumed {
+                println!("Resumed an interrupted key rotation.");
+            }
+            println!(
+                "Re-encrypted {} item(s) with data key {}.",
+                report.reencrypted, report.key_id
+            );
+            if !report.retired.is_empty() {
+                println!("Retired old data key(s): {}", report.retired.join(", "));
+            }
+        }
+        StoreCommand::RotateKek => {
+            let report = store.rotate_kek().await.map_err(lib_err)?;
+            if !out.is_text() {
+                return out.object("store.rotate-kek", &report);
+            }
+            println!(
+                "Store now unlocks with {} key {} ({} retired).",
+                store.key_provider().name(),
+                report.key_id,
+                report.retired
+            );
+            if !report.invalidated.is_empty() {
+                println!(
+                    "These unlocked the replaced master key and were dropped: {}",
+                    report.invalidated.join(", ")
+                );
+                println!(
+                    "Run `frodo store add-unlock` or `frodo store backup-key` again to replace them."
+                );
+            }
+        }
+        StoreCommand::AddUnlock { provider } => {
+            let other = DataKeys::from_config(cfg, provider)?;
+            let key_id = store.add_unlock(&other).await.map_err(lib_err)?;
+            if !out.is_text() {
+                return out.object(
+                    "store.add-unlock",
+                    &serde_json::json!({ "provider": other.name(), "key_id": key_id }),
+                );
+            }
+            println!(
+                "The {} key {key_id} can now unlock this store.",
+                other.name()
+            );
+        }
+        StoreCommand::Migrate { .. } => {
+            let report = store.migrate().await.map_err(lib_err)?;
+            if !out.is_text() {
+                return out.object("store.migrate", &report);
+            }
+            println!(
+                "Re-sealed {} item(s); the store now requires format v{}.",
+                report.resealed, report.format
+            );
+        }
+        StoreCommand::BackupKey {
+            file: None,
+            passphrase_env: _,
+        } => {
+            let code = store.add_recovery_code().await.map_err(lib_err)?;
+            if !out.is_text() {
+                return out.object(
+                    "store.backup-key",
+                    &serde_json::json!({ "kind": "code", "code": code.to_string() }),
+                );
+            }
+            println!("Recovery code (shown once; print it or write it down):");
+            println!();
+            println!("    {code}");
+            println!();
+            println!("With a copy of the data dir, `frodo store recover` restores access on any machine.");
+        }
+        StoreCommand::BackupKey {
+            file: Some(path),
+            passphrase_env,
+        } => {
+            let passphrase = secret_source(passphrase_env)
+                .read_as("Backup passphrase", true)
+                .map_err(lib_err)?;
+            let kdf = cfg.storage.as_ref().and_then(|s| s.kdf).unwrap_or_default();
+            let backup = store.export_key(&passphrase, kdf).await.map_err(lib_err)?;
+            write_backup(&path, &backup)?;
+            if !out.is_text() {
+                return out.object(
+                    "store.backup-key",
+                    &serde_json::json!({ "kind": "file", "path": path }),
+                );
+            }
+            println!("Wrote key backup to {}.", path.display());
+            println!("Keep it away from the data dir; it is only as strong as its passphrase.");
+        }
+        StoreCommand::Recover {
+            file: Some(path),
+            passphrase_env,
+            ..
+        } => {
+            let backup = read_backup(&path)?;
+            let passphrase = secret_source(passphrase_env)
+                .read_as("Backup passphrase", false)
+                .map_err(lib_err)?;
+            let key_id = store
+                .recover_with_backup(&backup, &passphrase)
+                .await
+                .map_err(lib_err)?;
+            report_recovered(out, store.key_provider().name(), &key_id)?;
+        }
+        StoreCommand::Recover {
+            code, file: None, ..
+        } => {
+            let input = match code {
+                Some(code) => code,
+                None => PassphraseSource::Prompt
+                    .read_as("Recovery code", false)
+                    .map_err(lib_err)?,
+            };
+            let code = RecoveryCode::parse(&input).ok_or_else(|| {
+                categorized(
+                    ErrorCategory::Usage,
+                    "not a recovery code (expected 8 groups of 4 characters)",
+                )
+            })?;
+            let key_id = store.recover_with_code(&code).await.map_err(lib_err)?;
+            report_recovered(out, store.key_provider().name(), &key_id)?;
+        }
+        StoreCommand::Backup {
+            file,
+            recipient,
+            passphrase_env,
+        } => {
+            let protection = if recipient.is_empty() {
+                let passphrase = secret_source(passphrase_env)
+                    .read_as("Archive passphrase", true)
+                    .map_err(lib_err)?;
+                Protection::Passphrase(passphrase)
+            } else {
+                Protection::recipients(&recipient)
+                    .map_err(|e| categorized(ErrorCategory::Usage, e.to_string()))?
+            };
+            let archive = Archive::new(store.snapshot().await.map_err(lib_err)?);
+            write_new(&file, &archive.seal(&protection).map_err(lib_err)?)?;
+            if !out.is_text() {
+                return out.object(
+                    "store.backup",
+                    &serde_json::json!({
+                        "path": file,
+                        "items": archive.len(),
+                        "created_at": archive.created_at,
+                    }),
+                );
+            }
+            println!("Wrote {} item(s) to {}.", archive.len(), file.display());
+            println!("`frodo store restore` reads it back into any store, with or without this machine's keys.");
+        }
+        StoreCommand::Restore {
+            file,
+            identity,
+            passphrase_env,
+            check,
+            force,
+        } => {
+            let bytes = read_file(&file)?;
+            let unlock = match identity {
+                Some(path) => Unlock::identity_file(&path)
+                    .map_err(|e| categorized(ErrorCategory::Usage, e.to_string()))?,
+                None => Unlock::Passphrase(
+                    secret_source(passphrase_env)
+                        .read_as("Archive passphrase", false)
+                        .map_err(lib_err)?,
+                ),
+            };
+            let archive = Archive::open(&bytes, &unlock).map_err(lib_err)?;
+            let created = DateTime::from_timestamp(archive.created_at as i64, 0).map_or_else(
+                || "an unknown time".to_string(),
+                |at| at.format("%Y-%m-%d %H:%M UTC").to_string(),
+            );
+            if check {
+                if !out.is_text() {
+                    return out.object(
+                        "store.restore",
+                        &serde_json::json!({
+                            "path": file,
+                            "items": archive.len(),
+                            "created_at": archive.created_at,
+                            "restored": false,
+                        }),
+                    );
+                }
+                println!(
+                    "{} holds {} item(s) from {created} and decrypts cleanly.",
+                    file.display(),
+                    archive.len()
+                );
+                return Ok(());
+            }
+            let held = store.list("").await.map_err(lib_err)?.len();
+            if held > 0 && !force {
+                return Err(categorized(
+                    ErrorCategory::Conflict,
+                    format!(
+                        "the store already holds {held} item(s); pass --force to replace them with the archive"
+                    ),
+                ));
+            }
+            let report = backup::restore(&store, &archive).await.map_err(lib_err)?;
+            if !out.is_text() {
+                return out.object(
+                    "store.restore",
+                    &serde_json::json!({
+                        "path": file,
+                        "items": archive.len(),
+                        "created_at": archive.created_at,
+                        "restored": true,
+                        "removed": report.removed,
+                    }),
+                );
+            }
+            println!(
+                "Restored and verified {} item(s) from {} (written {created}).",
+                report.restored,
+                file.display()
+            );
+            if report.removed > 0 {
+                println!("Removed {} item(s) not in the archive.", report.removed);
+            }
+        }
+        StoreCommand::Fsck { repair } => {
+            let mut report = store.check(validate_blob).await.map_err(lib_err)?;
+            if out.is_text() {
+                print_findings(&report);
+            }
+            let interactive =
+                out.is_text() && std::io::stdin().is_terminal() && std::io::stderr().is_terminal();
+            let repair = !report.is_clean()
+                && (repair
+                    || interactive
+                        && confirm(
+                            &format!(
+                                "Quarantine {} corrupt item(s) and delete {} temp file(s)?",
+                                report.corrupt.len(),
+                                report.orphans.len()
+                            ),
+                            std::io::stdin().lock(),
+                            std::io::stderr(),
+                        )?);
+            let mut rebuilt = None;
+            if repair {
+                report = store.repair(validate_blob).await.map_err(lib_err)?;
+                // Tasks outlive a lost index; list them again.
+                if report.corrupt.iter().any(|c| c.key == "tasks/index") {
+                    let repo = SecureStoreTaskRepo::new(&store);
+                    rebuilt = Some(repo.rebuild_index().await.map_err(lib_err)?);
+                }
+            }
+            let quarantine = store.root().join(QUARANTINE_DIR);
+            if !out.is_text() {
+                return out.object(
+                    "store.fsck",
+                    &serde_json::json!({
+                        "clean": report.is_clean(),
+                        "healthy": report.healthy,
+                        "corrupt": report.corrupt,
+                        "orphans": report.orphans,
+                        "repaired": report.repaired,
+                        "quarantine": quarantine,
+                        "rebuilt_index": rebuilt,
+                    }),
+                );
+            }
+            if report.repaired {
+                if !report.corrupt.is_empty() {
+                    println!(
+                        "Moved {} item(s) to {}.",
+                        report.corrupt.len(),
+                        quarantine.display()
+                    );
+                }
+                if !report.orphans.is_empty() {
+                    println!("Deleted {} temp file(s).", report.orphans.len());
+                }
+                if let Some(count) = rebuilt {
+                    println!("Rebuilt the task index ({count} task(s)).");
+                }
+            } else if !report.is_clean() {
+                println!("Nothing changed; `frodo store fsck --repair` quarantines these.");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_findings(report: &CheckReport) {
+    println!(
+        "Checked {} item(s): {} ok, {} corrupt.",
+        report.healthy + report.corrupt.len(),
+        report.healthy,
+        report.corrupt.len()
+    );
+    for blob in &report.corrupt {
+        println!("  corrupt    {}: {}", blob.key, blob.problem);
+    }
+    for name in &report.orphans {
+        println!("  temp file  {name} (left by an interrupted write)");
+    }
+}
+
+/// Ask a yes/no question; anything but `y`/`yes` means no.
+fn confirm(question: &str, mut answer: impl BufRead, mut out: impl Write) -> Result<bool> {
+    write!(out, "{question} [y/N] ")?;
+    out.flush()?;
+    let mut line = String::new();
+    answer.read_line(&mut line)?;
+    Ok(matches!(
+        line.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+fn secret_source(env: Option<String>) -> PassphraseSource {
+    env.map_or(PassphraseSource::Prompt, PassphraseSource::Env)
+}
+
+fn report_recovered(out: &Output, provider: &str, key_id: &str) -> Result<()> {
+    if !out.is_text() {
+        return out.object(
+            "store.recover",
+            &serde_json::json!({ "provider": provider, "key_id": key_id }),
+        );
+    }
+    println!("Access restored: the {provider} key {key_id} now unlocks this store.");
+    Ok(())
+}
+
+/// Write a backup without replacing an existing file; temp files are
+/// created owner-only, so the backup is too.
+fn write_backup(path: &Path, backup: &KeyBackup) -> Result<()> {
+    write_new(path, &serde_json::to_vec_pretty(backup)?)
+}
+
+/// Create `path` with `bytes`, owner-only, failing if it already exists.
+fn write_new(path: &Path, bytes: &[u8]) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    tmp.write_all(bytes)?;
+    tmp.persist_noclobber(path).map_err(|e| {
+        categorized(
+            ErrorCategory::Conflict,
+            format!("cannot write {}: {}", path.display(), e.error),
+        )
+    })?;
+    Ok(())
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| {
+        categorized(
+            ErrorCategory::NotFound,
+            format!("cannot read {}: {e}", path.display()),
+        )
+    })
+}
+
+fn read_backup(path: &Path) -> Result<KeyBackup> {
+    let bytes = read_file(path)?;
+    serde_json::from_slice(&bytes).map_err(|e| {
+        categorized(
+            ErrorCategory::Usage,
+            format!("{} is not a key backup: {e}", path.display()),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confirm_only_accepts_yes() {
+        let mut out = Vec::new();
+        assert!(confirm("Go?", "Y\n".as_bytes(), &mut out).unwrap());
+        assert!(confirm("Go?", "yes\n".as_bytes(), &mut out).unwrap());
+        assert!(!confirm("Go?", "\n".as_bytes(), &mut out).unwrap());
+        assert!(!confirm("Go?", "nope\n".as_bytes(), &mut out).unwrap());
+        assert!(String::from_utf8(out).unwrap().starts_with("Go? [y/N] "));
+    }
+}
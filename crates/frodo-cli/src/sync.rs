@@ -1,43 +1,71 @@
+use chrono::Utc;
 use color_eyre::Result;
-use frodo_core::tasks::TaskRepository;
-use frodo_storage::secure_file_store::EncryptedFileStore;
+use frodo_core::{
+    tasks::{Task, TaskRepository},
+    workspace::Scope,
+};
 use frodo_sync::{GitHubConfig, GitHubSync, JiraConfig, JiraSync, NoopSync, TaskSync};
 use frodo_task::SecureStoreTaskRepo;
 use tracing::info;
 
 use crate::config;
+use crate::output::{categorized, classify, lib_err, ErrorCategory, Output};
 use crate::storage;
 
-/// Placeholder sync handler. Uses a no-op sync provider for now.
-pub async fn run(cfg: &config::Config, apply: bool) -> Result<()> {
+/// Placeholder sync handler. Uses a no-op sync provider for now. Only tasks
+/// in `scope` are considered, against the workspace's linked targets.
+pub async fn run(cfg: &config::Config, scope: &Scope, apply: bool, out: &Output) -> Result<()> {
+    let cfg = &cfg.for_workspace(scope.workspace());
     let provider = select_provider(cfg);
     info!(
         "sync invoked (provider: {}, apply={})",
         provider.name(),
         apply
     );
-    println!("Sync is not yet implemented. Planned targets:");
-    println!("- Jira: configure project/site and token (todo)");
-    println!("- GitHub Issues: derive from git remotes and token (todo)");
+    if out.is_text() {
+        println!("Sync is not yet implemented. Planned targets:");
+        println!("- Jira: configure project/site and token (todo)");
+        println!("- GitHub Issues: derive from git remotes and token (todo)");
+    }
     // Stub pull/push
-    let remote = provider
-        .pull()
-        .await
-        .map_err(|e| color_eyre::eyre::eyre!(e.to_string()))?;
-    println!("Pulled {} remote tasks.", remote.len());
+    let remote = provider.pull().await.map_err(remote_err)?;
     // Load local tasks to show the counts we would push.
-    let store: EncryptedFileStore<_> = storage::store_from_config(cfg)?;
+    let store: storage::Store = storage::store_from_config(cfg)?;
     let repo: SecureStoreTaskRepo<_> = SecureStoreTaskRepo::new(store);
-    let local = repo
-        .list()
-        .await
-        .map_err(|e| color_eyre::eyre::eyre!(e.to_string()))?;
-    println!("Local tasks: {}", local.len());
+    let local = scope.filter(repo.list().await.map_err(lib_err)?);
+    let scoped = in_scope(cfg, &local);
+    if apply {
+        provider.push(&scoped).await.map_err(remote_err)?;
+    }
+    let query = cfg.sync.as_ref().and_then(|s| s.scope.as_ref());
+
+    if !out.is_text() {
+        return out.object(
+            "sync",
+            &serde_json::json!({
+                "provider": provider.name(),
+                "workspace": scope.name(),
+                "applied": apply,
+                "pulled": remote.len(),
+                "local": local.len(),
+                "scope": query,
+                "in_scope": scoped.len(),
+            }),
+        );
+    }
+    if let Some(workspace) = scope.name() {
+        println!("Workspace: {workspace}");
+    }
+    println!("Pulled {} remote tasks.", remote.len());
+    match query {
+        Some(query) => println!(
+            "Local tasks: {} ({} in sync scope `{query}`)",
+            local.len(),
+            scoped.len()
+        ),
+        None => println!("Local tasks: {}", local.len()),
+    }
     if apply {
-        provider
-            .push(&local)
-            .await
-            .map_err(|e| color_eyre::eyre::eyre!(e.to_string()))?;
         println!("Applied push (stub).");
     } else {
         println!("Dry run: not pushing changes.");
@@ -45,6 +73,27 @@ pub async fn run(cfg: &config::Config, apply: bool) -> Result<()> {
     Ok(())
 }
 
+/// Provider failures are reported as network errors unless the cause says otherwise.
+fn remote_err(err: anyhow::Error) -> color_eyre::Report {
+    let report = lib_err(err);
+    match classify(&report) {
+        ErrorCategory::General => categorized(ErrorCategory::Network, format!("{report}")),
+        _ => report,
+    }
+}
+
+/// Local tasks eligible for push under the configured `[sync] scope` query.
+fn in_scope(cfg: &config::Config, tasks: &[Task]) -> Vec<Task> {
+    match cfg.sync.as_ref().and_then(|s| s.scope.as_ref()) {
+        Some(scope) => scope
+            .filter(tasks, Utc::now())
+            .into_iter()
+            .cloned()
+            .collect(),
+        None => tasks.to_vec(),
+    }
+}
+
 fn select_provider(cfg: &config::Config) -> Box<dyn TaskSync> {
     if let Some(gh) = &cfg.github {
         let gh_cfg = GitHubConfig {
@@ -84,6 +133,11 @@ mod tests {
                 token: "t".into(),
                 api_base: None,
             }),
+            priority: None,
+            views: Default::default(),
+            sync: None,
+            workspaces: Vec::new(),
+            storage: None,
         };
         let provider = select_provider(&cfg);
         assert_eq!(provider.name(), "github");
@@ -102,11 +156,34 @@ mod tests {
                 base_url: None,
             }),
             github: None,
+            priority: None,
+            views: Default::default(),
+            sync: None,
+            workspaces: Vec::new(),
+            storage: None,
         };
         let provider = select_provider(&cfg);
         assert_eq!(provider.name(), "jira");
     }
 
+    #[test]
+    fn scope_limits_pushed_tasks() {
+        let public = Task::new("public".into(), None, vec!["work".into()]);
+        let private = Task::new("private".into(), None, vec!["private".into()]);
+        let tasks = vec![public, private];
+
+        assert_eq!(in_scope(&config::Config::default(), &tasks).len(), 2);
+        let cfg = config::Config {
+            sync: Some(config::SyncSettings {
+                scope: Some("-tag:private".parse().unwrap()),
+            }),
+            ..Default::default()
+        };
+        let scoped = in_scope(&cfg, &tasks);
+        assert_eq!(scoped.len(), 1);
+        assert_eq!(scoped[0].title, "public");
+    }
+
     #[test]
     fn defaults_to_noop() {
         let provider = select_provider(&config::Config::default());
@@ -1,65 +1,604 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use color_eyre::Result;
-use frodo_core::tasks::{TaskRepository, TaskStatus};
-use frodo_storage::secure_file_store::EncryptedFileStore;
+use frodo_core::{
+    graph::TaskGraph,
+    history::{EventKind, FieldChange, TaskEvent},
+    select::short_id,
+    tasks::{Priority, Task, TaskRepository, TaskStatus},
+    timetrack::format_duration,
+    workspace::Scope,
+};
 use frodo_task::SecureStoreTaskRepo;
+use serde::Serialize;
 use uuid::Uuid;
 
-use crate::{cli::TaskCommand, config, storage};
+use crate::{
+    cli::TaskCommand,
+    config, git,
+    output::{categorized, lib_err, ErrorCategory, Output},
+    select::{self, Selector},
+    storage,
+};
 
-/// Execute a task subcommand using the encrypted store.
-pub async fn handle(cmd: TaskCommand, config: &config::Config) -> Result<()> {
-    let repo: SecureStoreTaskRepo<EncryptedFileStore<_>> =
-        SecureStoreTaskRepo::new(storage::store_from_config(config)?);
+/// Execute a task subcommand using the encrypted store. Listings only show
+/// tasks in `scope`; new tasks join its workspace.
+pub async fn handle(
+    cmd: TaskCommand,
+    config: &config::Config,
+    scope: &Scope,
+    out: &Output,
+) -> Result<()> {
+    let name = cmd.name();
+    // One store for the repository and the listing cache, so the key is
+    // unlocked (and a passphrase asked for) once per command.
+    let store = storage::store_from_config(config)?;
+    let repo = SecureStoreTaskRepo::new(&store);
+    let selector = || Selector::load(&repo, &store);
 
     match cmd {
-        TaskCommand::List => {
-            let tasks = repo
-                .list()
-                .await
-                .map_err(|e| color_eyre::eyre::eyre!(e.to_string()))?;
-            if tasks.is_empty() {
-                println!("No tasks yet. Add one with `frodo task add <title>`.");
+        TaskCommand::List { query } => {
+            let query = config.query(&join_query_args(&query))?;
+            let tasks = repo.list().await.map_err(lib_err)?;
+            // Blockers may live in another workspace, so the graph spans all tasks.
+            let graph = TaskGraph::new(&tasks);
+            let now = Utc::now();
+            let matching: Vec<&Task> = tasks
+                .iter()
+                .filter(|task| scope.includes(task) && query.matches(task, &graph, now))
+                .collect();
+            let ids: Vec<Uuid> = matching.iter().map(|t| t.id).collect();
+            if !ids.is_empty() {
+                select::remember_listing(&store, &ids).await?;
+            }
+            if !out.is_text() {
+                let items: Vec<ListedTask> = matching
+                    .iter()
+                    .enumerate()
+                    .map(|(index, task)| ListedTask {
+                        index: index + 1,
+                        short_id: short_id(task.id),
+                        blocked: graph.is_blocked(task.id),
+                        tracked_seconds: task.tracked(now).num_seconds(),
+                        task,
+                    })
+                    .collect();
+                return out.list(name, &items);
+            }
+            if !tasks.iter().any(|task| scope.includes(task)) {
+                match scope.name() {
+                    Some(workspace) => println!(
+                        "No tasks in workspace `{workspace}` yet. Add one with `frodo task add <title>` or see all with `--all`."
+                    ),
+                    None => println!("No tasks yet. Add one with `frodo task add <title>`."),
+                }
+                return Ok(());
+            }
+            if matching.is_empty() {
+                println!("No tasks match `{query}`.");
                 return Ok(());
             }
-            for task in tasks {
+            for (index, task) in matching.into_iter().enumerate() {
                 println!(
-                    "{} [{}] {}",
-                    task.id,
-                    status_label(&task.status),
+                    "{:>3}  {} [{}] {}",
+                    index + 1,
+                    short_id(task.id),
+                    display_status(&graph, task),
                     task.title
                 );
-                if let Some(desc) = task.description {
+                if let Some(desc) = &task.description {
                     println!("    {desc}");
                 }
                 if !task.tags.is_empty() {
                     println!("    tags: {}", task.tags.join(", "));
                 }
+                if task.priority != Priority::default()
+                    || task.due_at.is_some()
+                    || task.recurrence.is_some()
+                    || !task.time_entries.is_empty()
+                {
+                    let mut meta = vec![format!("priority: {}", task.priority.label())];
+                    if let Some(due) = task.due_at {
+                        meta.push(format!("due: {}", due.format("%Y-%m-%d")));
+                    }
+                    if let Some(rule) = &task.recurrence {
+                        meta.push(format!("repeats: {rule}"));
+                    }
+                    if !task.time_entries.is_empty() {
+                        let running = if task.running_entry().is_some() {
+                            " (running)"
+                        } else {
+                            ""
+                        };
+                        meta.push(format!(
+                            "tracked: {}{running}",
+                            format_duration(task.tracked(now))
+                        ));
+                    }
+                    println!("    {}", meta.join(", "));
+                }
+                let blockers = graph.open_blockers(task.id);
+                if !blockers.is_empty() {
+                    let titles: Vec<&str> = blockers.iter().map(|b| b.title.as_str()).collect();
+                    println!("    blocked by: {}", titles.join(", "));
+                }
+            }
+        }
+        TaskCommand::Start { id } => {
+            let id = selector().await?.resolve(&id)?;
+            let task = repo.start_timer(id).await.map_err(lib_err)?;
+            if !out.is_text() {
+                return out.object(name, &task);
+            }
+            println!("Started timer on {} ({})", task.title, task.id);
+        }
+        TaskCommand::Stop => {
+            let stopped = repo.stop_timer().await.map_err(lib_err)?;
+            if !out.is_text() {
+                return out.object(name, &stopped);
+            }
+            match stopped {
+                Some(task) => {
+                    let last = task
+                        .time_entries
+                        .last()
+                        .map(|e| e.overlap(e.started_at, Utc::now()))
+                        .unwrap_or_default();
+                    println!(
+                        "Stopped timer on {} after {} (total {})",
+                        task.title,
+                        format_duration(last),
+                        format_duration(task.tracked(Utc::now()))
+                    );
+                }
+                None => println!("No timer is running."),
+            }
+        }
+        TaskCommand::History { id } => {
+            let id = selector().await?.resolve(&id)?;
+            let events = repo.history(id).await.map_err(lib_err)?;
+            if !out.is_text() {
+                return out.list(name, &events);
+            }
+            if events.is_empty() {
+                println!("No recorded history for {id}.");
+                return Ok(());
+            }
+            for line in render_history(&events) {
+                println!("{line}");
+            }
+        }
+        TaskCommand::Views => {
+            if !out.is_text() {
+                let views: Vec<serde_json::Value> = config
+                    .views
+                    .iter()
+                    .map(|(view, query)| serde_json::json!({ "name": view, "query": query }))
+                    .collect();
+                return out.list(name, &views);
+            }
+            if config.views.is_empty() {
+                println!("No saved views. Add them under [views] in the config file, e.g.");
+                println!("    backend = \"status:todo tag:backend -tag:someday\"");
+                return Ok(());
+            }
+            for (name, query) in &config.views {
+                println!("@{name}  {query}");
+            }
+        }
+        TaskCommand::Tree => {
+            let tasks = scope.filter(repo.list().await.map_err(lib_err)?);
+            if !out.is_text() {
+                let graph = TaskGraph::new(&tasks);
+                let entries: Vec<serde_json::Value> = graph
+                    .tree()
+                    .into_iter()
+                    .map(|entry| {
+                        serde_json::json!({
+                            "depth": entry.depth,
+                            "blocked": graph.is_blocked(entry.task.id),
+                            "task": entry.task,
+                        })
+                    })
+                    .collect();
+                return out.list(name, &entries);
+            }
+            if tasks.is_empty() {
+                println!("No tasks yet. Add one with `frodo task add <title>`.");
+                return Ok(());
+            }
+            for line in render_tree(&tasks) {
+                println!("{line}");
             }
         }
         TaskCommand::Add {
             title,
             description,
             tag,
+            priority,
+            due,
+            parent,
+            blocked_by,
+            repeat,
         } => {
-            let task = repo
-                .create(title, description, tag)
-                .await
-                .map_err(|e| color_eyre::eyre::eyre!(e.to_string()))?;
+            let due_at = due.map(|d| parse_due(&d, Utc::now())).transpose()?;
+            let (parent, blocked_by) = if parent.is_some() || !blocked_by.is_empty() {
+                let selector = selector().await?;
+                (
+                    parent.map(|p| selector.resolve(&p)).transpose()?,
+                    blocked_by
+                        .iter()
+                        .map(|id| selector.resolve(id))
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            } else {
+                (None, Vec::new())
+            };
+            // Built in full and stored in one call, so `frodo undo` removes
+            // the whole task and a failure leaves nothing half set up.
+            let mut task = Task::new(title, description, tag);
+            task.priority = priority.unwrap_or_default();
+            task.due_at = due_at;
+            task.recurrence = repeat;
+            task.workspace = scope.name().map(str::to_string);
+            task.parent_id = parent;
+            for blocker in blocked_by {
+                if !task.blocked_by.contains(&blocker) {
+                    task.blocked_by.push(blocker);
+                }
+            }
+            let task = repo.add(task).await.map_err(lib_err)?;
+            if !out.is_text() {
+                return out.object(name, &task);
+            }
             println!("Created task {}: {}", task.id, task.title);
         }
         TaskCommand::Done { id } => {
-            let uuid = Uuid::parse_str(&id).map_err(|e| color_eyre::eyre::eyre!(e.to_string()))?;
+            let uuid = selector().await?.resolve(&id)?;
             let task = repo
                 .set_status(uuid, TaskStatus::Done)
                 .await
-                .map_err(|e| color_eyre::eyre::eyre!(e.to_string()))?;
+                .map_err(lib_err)?;
+            if !out.is_text() {
+                return out.object(name, &task);
+            }
             println!("Marked done: {}", task.title);
+            if let Some(rule) = &task.recurrence {
+                let next = rule.next_due(task.due_at, task.updated_at);
+                println!(
+                    "Next occurrence ({rule}) due {}",
+                    next.format("%Y-%m-%d %H:%M")
+                );
+            }
+        }
+        TaskCommand::Block { id, on } => {
+            let selector = selector().await?;
+            let task = repo
+                .add_dependency(selector.resolve(&id)?, selector.resolve(&on)?)
+                .await
+                .map_err(lib_err)?;
+            if !out.is_text() {
+                return out.object(name, &task);
+            }
+            println!("{} is now blocked by {on}", task.title);
+        }
+        TaskCommand::Unblock { id, on } => {
+            let selector = selector().await?;
+            let task = repo
+                .remove_dependency(selector.resolve(&id)?, selector.resolve(&on)?)
+                .await
+                .map_err(lib_err)?;
+            if !out.is_text() {
+                return out.object(name, &task);
+            }
+            println!("{} no longer waits on {on}", task.title);
+        }
+        TaskCommand::Branch { id, no_switch } => {
+            let git = git::Repo::current()?;
+            let selector = selector().await?;
+            let mut task = selected_task(&selector, &id)?;
+            let branch = git::branch_name(&task);
+            git.create_branch(&branch, !no_switch)?;
+            task.branch = Some(branch.clone());
+            let task = repo.update(task).await.map_err(lib_err)?;
+            if !out.is_text() {
+                return out.object(
+                    name,
+                    &serde_json::json!({ "branch": branch, "switched": !no_switch, "task": task }),
+                );
+            }
+            if no_switch {
+                println!("Created branch {branch} for {}", task.title);
+            } else {
+                println!("Switched to new branch {branch} for {}", task.title);
+            }
+        }
+        TaskCommand::Current => {
+            let git = git::Repo::current()?;
+            let branch = match git.head()? {
+                git::Head::Branch(branch) => branch,
+                git::Head::Detached(_) => {
+                    return Err(categorized(
+                        ErrorCategory::Usage,
+                        "HEAD is detached; check out a task branch first",
+                    ))
+                }
+            };
+            let tasks = repo.list().await.map_err(lib_err)?;
+            let task = git::task_for_branch(&branch, &tasks).ok_or_else(|| {
+                categorized(
+                    ErrorCategory::NotFound,
+                    format!("no task matches branch `{branch}`; create one with `frodo task branch <id>`"),
+                )
+            })?;
+            let commits = git.commits_mentioning(&git::task_keys(task), git::DEFAULT_SCAN)?;
+            if !out.is_text() {
+                return out.object(
+                    name,
+                    &serde_json::json!({ "branch": branch, "task": task, "commits": commits }),
+                );
+            }
+            println!(
+                "On {branch}: {} [{}] {}",
+                short_id(task.id),
+                status_label(&task.status),
+                task.title
+            );
+            for line in render_commits(&commits) {
+                println!("{line}");
+            }
         }
+        TaskCommand::Commits { id, scan } => {
+            let git = git::Repo::current()?;
+            let task = selected_task(&selector().await?, &id)?;
+            let keys = git::task_keys(&task);
+            let commits = git.commits_mentioning(&keys, scan)?;
+            if !out.is_text() {
+                return out.list(name, &commits);
+            }
+            if commits.is_empty() {
+                println!("No commits mention {}.", keys.join(" or "));
+                return Ok(());
+            }
+            for line in render_commits(&commits) {
+                println!("{line}");
+            }
+        }
+        TaskCommand::Parent { id, parent } => {
+            let selector = selector().await?;
+            let parent = parent.map(|p| selector.resolve(&p)).transpose()?;
+            let task = repo
+                .set_parent(selector.resolve(&id)?, parent)
+                .await
+                .map_err(lib_err)?;
+            if !out.is_text() {
+                return out.object(name, &task);
+            }
+            match parent {
+                Some(parent) => println!("Moved {} under {parent}", task.title),
+                None => println!("Moved {} to the top level", task.title),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The task a selector resolves to.
+pub fn selected_task(selector: &Selector, input: &str) -> Result<Task> {
+    let id = selector.resolve(input)?;
+    selector
+        .task(id)
+        .cloned()
+        .ok_or_else(|| categorized(ErrorCategory::NotFound, format!("task {id} not found")))
+}
+
+/// `abc1234 2026-10-18 Ada  Fix PROJ-123 timeout`
+fn render_commits(commits: &[git::Commit]) -> Vec<String> {
+    commits
+        .iter()
+        .map(|c| {
+            format!(
+                "  {} {} {}  {}",
+                &c.id[..7],
+                c.time.format("%Y-%m-%d"),
+                c.author,
+                c.summary
+            )
+        })
+        .collect()
+}
+
+/// One `task list` entry in JSON output: the stored task plus derived fields.
+#[derive(Serialize)]
+struct ListedTask<'a> {
+    /// Position in this listing, usable as a selector (`frodo task done 2`).
+    index: usize,
+    short_id: String,
+    blocked: bool,
+    tracked_seconds: i64,
+    #[serde(flatten)]
+    task: &'a Task,
+}
+
+/// Indented parent/subtask tree with derived blocked status.
+fn render_tree(tasks: &[Task]) -> Vec<String> {
+    let graph = TaskGraph::new(tasks);
+    graph
+        .tree()
+        .into_iter()
+        .map(|entry| {
+            format!(
+                "{}[{}] {} ({})",
+                "  ".repeat(entry.depth),
+                display_status(&graph, entry.task),
+                entry.task.title,
+                entry.task.id
+            )
+        })
+        .collect()
+}
+
+/// Status label, reporting open tasks with open dependencies as `blocked`.
+fn display_status(graph: &TaskGraph<'_>, task: &Task) -> &'static str {
+    if graph.is_blocked(task.id) {
+        "blocked"
+    } else {
+        status_label(&task.status)
     }
+}
 
+/// Revert the last `count` operations and report what changed back.
+pub async fn undo(config: &config::Config, count: usize, out: &Output) -> Result<()> {
+    let repo = SecureStoreTaskRepo::new(storage::store_from_config(config)?);
+    let events = repo.undo(count).await.map_err(lib_err)?;
+    if !out.is_text() {
+        return out.list("undo", &events);
+    }
+    if events.is_empty() {
+        println!("Nothing to undo.");
+        return Ok(());
+    }
+    for line in render_history(&events) {
+        println!("{line}");
+    }
     Ok(())
 }
 
+/// One line per event: timestamp, actor, what happened, and field changes.
+pub fn render_history(events: &[TaskEvent]) -> Vec<String> {
+    events
+        .iter()
+        .map(|event| {
+            let when = event.at.format("%Y-%m-%d %H:%M:%S");
+            let who = &event.actor;
+            let task = event.task_id;
+            let title = |value: fn(&FieldChange) -> &serde_json::Value| {
+                event
+                    .changes
+                    .iter()
+                    .find(|c| c.field == "title")
+                    .map(|c| display_value(value(c)))
+                    .unwrap_or_default()
+            };
+            // Undoing a creation nulls every field.
+            let uncreated = event.kind == EventKind::Undone
+                && event
+                    .changes
+                    .iter()
+                    .any(|c| c.field == "id" && c.new.is_null());
+            match event.kind {
+                EventKind::Created => {
+                    format!("{when} {who} created {task} \"{}\"", title(|c| &c.new))
+                }
+                EventKind::Undone if uncreated => {
+                    format!(
+                        "{when} {who} undid creation of {task} \"{}\"",
+                        title(|c| &c.old)
+                    )
+                }
+                EventKind::Updated | EventKind::Undone => {
+                    let verb = if event.kind == EventKind::Undone {
+                        "undid"
+                    } else {
+                        "updated"
+                    };
+                    let fields: Vec<String> = event
+                        .changes
+                        .iter()
+                        .map(|c| {
+                            format!(
+                                "{}: {} → {}",
+                                c.field,
+                                display_value(&c.old),
+                                display_value(&c.new)
+                            )
+                        })
+                        .collect();
+                    format!("{when} {who} {verb} {task} {}", fields.join("; "))
+                }
+            }
+        })
+        .collect()
+}
+
+fn display_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "none".into(),
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(items) if items.is_empty() => "[]".into(),
+        serde_json::Value::Array(items) => items
+            .iter()
+            .map(display_value)
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    }
+}
+
+/// Re-join shell-split query arguments, re-quoting any that contained spaces
+/// (e.g. `"search words"` arrives as a single argument without its quotes).
+pub fn join_query_args(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            if arg.contains(char::is_whitespace) && !arg.contains('"') {
+                format!("\"{arg}\"")
+            } else {
+                arg.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parse a due date: `YYYY-MM-DD` (end of that day, UTC), RFC 3339, `today`,
+/// `tomorrow`, or an offset such as `3d` / `2w` from now.
+pub fn parse_due(input: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let input = input.trim();
+    let end_of_day = |date: NaiveDate| {
+        date.and_hms_opt(23, 59, 59)
+            .expect("valid time of day")
+            .and_utc()
+    };
+
+    match input.to_ascii_lowercase().as_str() {
+        "today" => return Ok(end_of_day(now.date_naive())),
+        "tomorrow" => return Ok(end_of_day(now.date_naive() + Duration::days(1))),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Ok(end_of_day(date));
+    }
+    if let Ok(ts) = DateTime::parse_from_rfc3339(input) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    if let Some(unit) = input.chars().last() {
+        if let Ok(amount) = input[..input.len() - unit.len_utf8()].parse::<i64>() {
+            let offset = match unit {
+                'd' => Some(Duration::try_days(amount)),
+                'w' => Some(Duration::try_weeks(amount)),
+                'h' => Some(Duration::try_hours(amount)),
+                _ => None,
+            };
+            if let Some(offset) = offset {
+                return offset
+                    .and_then(|offset| now.checked_add_signed(offset))
+                    .ok_or_else(|| {
+                        categorized(
+                            ErrorCategory::Usage,
+                            format!("due date `{input}` is too far away"),
+                        )
+                    });
+            }
+        }
+    }
+    Err(categorized(
+        ErrorCategory::Usage,
+        format!(
+            "invalid due date `{input}` (use YYYY-MM-DD, RFC 3339, today, tomorrow, or e.g. 3d/2w)"
+        ),
+    ))
+}
+
 fn status_label(status: &TaskStatus) -> &'static str {
     match status {
         TaskStatus::Todo => "todo",
@@ -85,4 +624,90 @@ mod tests {
         assert_eq!(listed.len(), 1);
         assert_eq!(listed[0].id, created.id);
     }
+
+    #[test]
+    fn renders_history_lines() {
+        use frodo_core::history;
+
+        let task = Task::new("Ship".into(), None, vec![]);
+        let at = DateTime::parse_from_rfc3339("2026-10-18T09:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let event = |kind, changes| TaskEvent {
+            id: Uuid::new_v4(),
+            operation: Uuid::new_v4(),
+            task_id: task.id,
+            at,
+            actor: "alice".into(),
+            kind,
+            changes,
+        };
+        let status = vec![FieldChange {
+            field: "status".into(),
+            old: serde_json::json!("Todo"),
+            new: serde_json::json!("Done"),
+        }];
+        let lines = render_history(&[
+            event(EventKind::Created, history::created(&task)),
+            event(EventKind::Updated, status),
+        ]);
+        assert_eq!(
+            lines,
+            vec![
+                format!("2026-10-18 09:30:00 alice created {} \"Ship\"", task.id),
+                format!(
+                    "2026-10-18 09:30:00 alice updated {} status: Todo → Done",
+                    task.id
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn join_query_args_requotes_phrases() {
+        let args = vec!["tag:backend".to_string(), "search words".to_string()];
+        assert_eq!(join_query_args(&args), r#"tag:backend "search words""#);
+    }
+
+    #[test]
+    fn renders_tree_with_blocked_subtasks() {
+        let epic = Task::new("Epic".into(), None, vec![]);
+        let infra = Task::new("Infra".into(), None, vec![]);
+        let mut story = Task::new("Story".into(), None, vec![]);
+        story.parent_id = Some(epic.id);
+        story.blocked_by = vec![infra.id];
+        let tasks = vec![epic.clone(), story.clone(), infra.clone()];
+
+        let lines = render_tree(&tasks);
+        assert_eq!(
+            lines,
+            vec![
+                format!("[todo] Epic ({})", epic.id),
+                format!("  [blocked] Story ({})", story.id),
+                format!("[todo] Infra ({})", infra.id),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_due_formats() {
+        let now = DateTime::parse_from_rfc3339("2026-10-18T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            parse_due("2026-10-20", now).unwrap().to_rfc3339(),
+            "2026-10-20T23:59:59+00:00"
+        );
+        assert_eq!(
+            parse_due("tomorrow", now).unwrap().to_rfc3339(),
+            "2026-10-19T23:59:59+00:00"
+        );
+        assert_eq!(parse_due("3d", now).unwrap(), now + Duration::days(3));
+        assert_eq!(parse_due("2w", now).unwrap(), now + Duration::weeks(2));
+        assert!(parse_due("someday", now).is_err());
+        for far in ["99999999999d", "-99999999999w", "9223372036854775807h"] {
+            let err = parse_due(far, now).expect_err("out of range");
+            assert!(err.to_string().contains("too far away"), "{far}: {err}");
+        }
+    }
 }
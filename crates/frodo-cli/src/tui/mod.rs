@@ -1,6 +1,7 @@
-use std::{io, time::Duration};
+use std::{collections::HashMap, io, time::Duration};
 
-use crate::{config, sync, update};
+use crate::{config, output::Output, sync, update};
+use chrono::Utc;
 use color_eyre::Result;
 use crossterm::{
     event::{self, DisableMouseCapture, Event, KeyCode},
@@ -8,8 +9,12 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use frodo_core::{
+    graph::TaskGraph,
+    priority::{self, PriorityConfig},
+    query::Query,
     storage::SecureStore,
     tasks::{Task, TaskRepository, TaskStatus},
+    workspace::Scope,
 };
 use frodo_task::SecureStoreTaskRepo;
 use ratatui::{
@@ -22,13 +27,16 @@ use ratatui::{
 };
 use std::sync::Arc;
 use tokio::runtime::Handle;
+use uuid::Uuid;
 
 /// Minimal TUI that renders tasks and allows marking them done with `d`.
-/// Press `q` or `Esc` to exit.
+/// `u` undoes the last change. `/` opens the filter bar (same query language as `frodo task list`).
+/// Only tasks in `scope` are shown. Press `q` or `Esc` to exit.
 pub fn launch<S>(
     tasks: &[Task],
     repo: Arc<SecureStoreTaskRepo<S>>,
     config: config::Config,
+    scope: Scope,
     handle: Handle,
 ) -> Result<()>
 where
@@ -41,8 +49,14 @@ where
     let mut selected = 0usize;
     let mut status = String::from("Ready");
     let mut config = config;
+    let mut sort_by_priority = false;
+    let mut filter: Option<Query> = None;
+    // Text being typed into the filter bar, when it is open.
+    let mut filter_input: Option<String> = None;
 
     loop {
+        let weights = config.priority.clone().unwrap_or_default();
+        let (rows, scores) = visible_rows(&tasks, sort_by_priority, &weights, filter.as_ref());
         terminal.draw(|frame| {
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
@@ -76,16 +90,21 @@ where
             );
             frame.render_widget(header, chunks[0]);
 
-            let items: Vec<ListItem> = tasks
+            let items: Vec<ListItem> = rows
                 .iter()
                 .enumerate()
-                .map(|(idx, t)| {
+                .map(|(idx, row)| {
+                    let t = &tasks[row.index];
+                    let (label, color) = if row.blocked {
+                        ("[blocked]", Color::Red)
+                    } else {
+                        (status_label(&t.status), status_color(&t.status))
+                    };
                     let mut line = vec![
+                        Span::raw("  ".repeat(row.depth)),
                         Span::styled(
-                            status_label(&t.status),
-                            Style::default()
-                                .fg(status_color(&t.status))
-                                .add_modifier(Modifier::BOLD),
+                            label,
+                            Style::default().fg(color).add_modifier(Modifier::BOLD),
                         ),
                         Span::raw(" "),
                         Span::styled(&t.title, Style::default().add_modifier(Modifier::BOLD)),
@@ -93,6 +112,12 @@ where
                     if let Some(desc) = &t.description {
                         line.push(Span::raw(format!(" — {desc}")));
                     }
+                    if let Some(score) = scores.get(&t.id) {
+                        line.push(Span::styled(
+                            format!(" ({score})"),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                    }
                     let mut item = ListItem::new(Line::from(line));
                     if idx == selected {
                         item = item.style(
@@ -106,11 +131,18 @@ where
                 })
                 .collect();
 
-            let body = List::new(items).block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Tasks (local)"),
-            );
+            let mut title = if sort_by_priority {
+                "Tasks (local, by priority)".to_string()
+            } else {
+                "Tasks (local)".to_string()
+            };
+            if let Some(workspace) = scope.name() {
+                title.push_str(&format!(" — workspace: {workspace}"));
+            }
+            if let Some(query) = &filter {
+                title.push_str(&format!(" — filter: {query}"));
+            }
+            let body = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
             frame.render_widget(body, chunks[1]);
 
             let footer =
@@ -124,11 +156,17 @@ where
                     Span::raw(" move; "),
                     Span::styled("d", Style::default().fg(Color::Green)),
                     Span::raw(" done; "),
+                    Span::styled("p", Style::default().fg(Color::Cyan)),
+                    Span::raw(" priority sort; "),
+                    Span::styled("/", Style::default().fg(Color::Cyan)),
+                    Span::raw(" filter; "),
                     Span::styled("r", Style::default().fg(Color::Cyan)),
                     Span::raw(" refresh; "),
                     Span::styled("s", Style::default().fg(Color::Cyan)),
                     Span::raw(" sync; "),
                     Span::styled("u", Style::default().fg(Color::Cyan)),
+                    Span::raw(" undo; "),
+                    Span::styled("U", Style::default().fg(Color::Cyan)),
                     Span::raw(" update; "),
                     Span::styled("c", Style::default().fg(Color::Cyan)),
                     Span::raw(" reload config."),
@@ -137,7 +175,13 @@ where
                     vec![
                         Span::raw("Controls "),
                         Span::styled("| ", Style::default().fg(Color::DarkGray)),
-                        Span::raw(status.clone()),
+                        match &filter_input {
+                            Some(input) => Span::styled(
+                                format!("/{input}_"),
+                                Style::default().fg(Color::Yellow),
+                            ),
+                            None => Span::raw(status.clone()),
+                        },
                     ],
                 )));
             frame.render_widget(footer, chunks[2]);
@@ -145,28 +189,75 @@ where
 
         if event::poll(Duration::from_millis(150))? {
             if let Event::Key(key) = event::read()? {
+                if let Some(input) = filter_input.as_mut() {
+                    match key.code {
+                        KeyCode::Esc => filter_input = None,
+                        KeyCode::Backspace => {
+                            input.pop();
+                        }
+                        KeyCode::Char(c) => input.push(c),
+                        KeyCode::Enter => match config.query(input) {
+                            Ok(query) if query.is_empty() => {
+                                filter = None;
+                                filter_input = None;
+                                selected = 0;
+                                status = "Filter cleared".into();
+                            }
+                            Ok(query) => {
+                                status = format!("Filter: {query}");
+                                filter = Some(query);
+                                filter_input = None;
+                                selected = 0;
+                            }
+                            // Keep the bar open so the query can be fixed.
+                            Err(err) => status = err.to_string(),
+                        },
+                        _ => {}
+                    }
+                    continue;
+                }
                 match key.code {
                     KeyCode::Char('q') | KeyCode::Esc => break,
-                    KeyCode::Char('j') | KeyCode::Down => {
-                        if !tasks.is_empty() {
-                            selected = (selected + 1).min(tasks.len().saturating_sub(1));
-                        }
+                    KeyCode::Char('j') | KeyCode::Down if !rows.is_empty() => {
+                        selected = (selected + 1).min(rows.len().saturating_sub(1));
                     }
-                    KeyCode::Char('k') | KeyCode::Up => {
-                        if !tasks.is_empty() {
-                            selected = selected.saturating_sub(1);
-                        }
+                    KeyCode::Char('k') | KeyCode::Up if !rows.is_empty() => {
+                        selected = selected.saturating_sub(1);
+                    }
+                    KeyCode::Char('/') => {
+                        filter_input =
+                            Some(filter.as_ref().map(Query::to_string).unwrap_or_default());
+                    }
+                    KeyCode::Char('p') => {
+                        sort_by_priority = !sort_by_priority;
+                        selected = 0;
+                        status = if sort_by_priority {
+                            "Sorted by priority score".into()
+                        } else {
+                            "Showing task tree".into()
+                        };
                     }
                     KeyCode::Char('d') => {
-                        if let Some(task) = tasks.get_mut(selected) {
+                        if let Some(task) = rows.get(selected).and_then(|r| tasks.get_mut(r.index))
+                        {
                             task.status = TaskStatus::Done;
                             let id = task.id;
                             let repo = repo.clone();
                             let res = handle.block_on(async move {
-                                repo.set_status(id, TaskStatus::Done).await
+                                let done = repo.set_status(id, TaskStatus::Done).await?;
+                                // Recurring tasks spawn a new occurrence; reload to show it.
+                                let fresh = match done.recurrence {
+                                    Some(_) => Some(repo.list().await?),
+                                    None => None,
+                                };
+                                anyhow::Ok(fresh)
                             });
                             status = match res {
-                                Ok(_) => "Marked done".into(),
+                                Ok(Some(fresh)) => {
+                                    tasks = scope.filter(fresh);
+                                    "Marked done; next occurrence scheduled".into()
+                                }
+                                Ok(None) => "Marked done".into(),
                                 Err(err) => format!("Failed to mark done: {err}"),
                             };
                         }
@@ -175,7 +266,7 @@ where
                         let repo = repo.clone();
                         match handle.block_on(async move { repo.list().await }) {
                             Ok(fresh) => {
-                                tasks = fresh;
+                                tasks = scope.filter(fresh);
                                 selected = 0;
                                 status = "Refreshed tasks".into();
                             }
@@ -183,13 +274,32 @@ where
                         }
                     }
                     KeyCode::Char('s') => {
-                        match handle.block_on(async { sync::run(&config, false).await }) {
+                        match handle.block_on(async {
+                            sync::run(&config, &scope, false, &Output::default()).await
+                        }) {
                             Ok(_) => status = "Sync dry-run completed".into(),
                             Err(err) => status = format!("Sync failed: {err}"),
                         }
                     }
                     KeyCode::Char('u') => {
-                        match handle.block_on(async { update::run(true).await }) {
+                        let repo = repo.clone();
+                        let res = handle.block_on(async move {
+                            let undone = repo.undo(1).await?;
+                            anyhow::Ok((undone.len(), repo.list().await?))
+                        });
+                        status = match res {
+                            Ok((0, _)) => "Nothing to undo".into(),
+                            Ok((_, fresh)) => {
+                                tasks = scope.filter(fresh);
+                                selected = selected.min(tasks.len().saturating_sub(1));
+                                "Undid last change".into()
+                            }
+                            Err(err) => format!("Undo failed: {err}"),
+                        };
+                    }
+                    KeyCode::Char('U') => {
+                        match handle.block_on(async { update::run(true, &Output::default()).await })
+                        {
                             Ok(_) => status = "Update check complete".into(),
                             Err(err) => status = format!("Update check failed: {err}"),
                         }
@@ -210,6 +320,71 @@ where
     Ok(())
 }
 
+/// One visible list row: index into `tasks`, tree depth, and derived blocked state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Row {
+    index: usize,
+    depth: usize,
+    blocked: bool,
+}
+
+/// Rows in display order, plus scores when sorting by priority.
+/// The default view is the parent/subtask tree; priority order is flat, puts
+/// open tasks best-first (see `frodo next`) and done tasks last. An active
+/// filter hides non-matching tasks and flattens the tree, since matches may
+/// not include their parents.
+fn visible_rows(
+    tasks: &[Task],
+    by_priority: bool,
+    weights: &PriorityConfig,
+    filter: Option<&Query>,
+) -> (Vec<Row>, HashMap<Uuid, i64>) {
+    let (rows, scores) = ordered_rows(tasks, by_priority, weights);
+    let Some(query) = filter else {
+        return (rows, scores);
+    };
+    let graph = TaskGraph::new(tasks);
+    let now = Utc::now();
+    let rows = rows
+        .into_iter()
+        .filter(|row| query.matches(&tasks[row.index], &graph, now))
+        .map(|row| Row { depth: 0, ..row })
+        .collect();
+    (rows, scores)
+}
+
+fn ordered_rows(
+    tasks: &[Task],
+    by_priority: bool,
+    weights: &PriorityConfig,
+) -> (Vec<Row>, HashMap<Uuid, i64>) {
+    let graph = TaskGraph::new(tasks);
+    let index: HashMap<Uuid, usize> = tasks.iter().enumerate().map(|(i, t)| (t.id, i)).collect();
+    let row = |task: &Task, depth: usize| Row {
+        index: index[&task.id],
+        depth,
+        blocked: graph.is_blocked(task.id),
+    };
+    if !by_priority {
+        let rows = graph
+            .tree()
+            .into_iter()
+            .map(|entry| row(entry.task, entry.depth))
+            .collect();
+        return (rows, HashMap::new());
+    }
+    let ranked = priority::rank(tasks, weights, Utc::now());
+    let scores: HashMap<Uuid, i64> = ranked.iter().map(|s| (s.task.id, s.score)).collect();
+    let position: HashMap<Uuid, usize> = ranked
+        .iter()
+        .enumerate()
+        .map(|(pos, s)| (s.task.id, pos))
+        .collect();
+    let mut order: Vec<&Task> = tasks.iter().collect();
+    order.sort_by_key(|t| position.get(&t.id).copied().unwrap_or(usize::MAX));
+    (order.into_iter().map(|t| row(t, 0)).collect(), scores)
+}
+
 fn status_label(status: &TaskStatus) -> &'static str {
     match status {
         TaskStatus::Todo => "[todo]",
@@ -253,3 +428,82 @@ impl Drop for TerminalGuard {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use frodo_core::tasks::Priority;
+
+    use super::*;
+
+    #[test]
+    fn priority_order_ranks_open_tasks_and_sinks_done() {
+        let mut done = Task::new("done".into(), None, vec![]);
+        done.status = TaskStatus::Done;
+        done.priority = Priority::Urgent;
+        let low = Task::new("low".into(), None, vec![]);
+        let mut high = Task::new("high".into(), None, vec![]);
+        high.priority = Priority::High;
+        let tasks = vec![done, low, high];
+
+        let (plain, scores) = visible_rows(&tasks, false, &PriorityConfig::default(), None);
+        let plain: Vec<usize> = plain.iter().map(|r| r.index).collect();
+        assert_eq!(plain, vec![0, 1, 2]);
+        assert!(scores.is_empty());
+
+        let (sorted, scores) = visible_rows(&tasks, true, &PriorityConfig::default(), None);
+        let sorted: Vec<usize> = sorted.iter().map(|r| r.index).collect();
+        assert_eq!(sorted, vec![2, 1, 0]);
+        assert_eq!(scores.len(), 2);
+    }
+
+    #[test]
+    fn filter_hides_non_matching_rows_and_flattens_tree() {
+        let parent = Task::new("parent".into(), None, vec![]);
+        let mut child = Task::new("child".into(), None, vec!["backend".into()]);
+        child.parent_id = Some(parent.id);
+        let tasks = vec![parent, child];
+
+        let query = Query::parse("tag:backend").unwrap();
+        let (rows, _) = visible_rows(&tasks, false, &PriorityConfig::default(), Some(&query));
+        assert_eq!(
+            rows,
+            vec![Row {
+                index: 1,
+                depth: 0,
+                blocked: false
+            }]
+        );
+    }
+
+    #[test]
+    fn tree_rows_indent_subtasks_and_flag_blocked() {
+        let parent = Task::new("parent".into(), None, vec![]);
+        let blocker = Task::new("blocker".into(), None, vec![]);
+        let mut child = Task::new("child".into(), None, vec![]);
+        child.parent_id = Some(parent.id);
+        child.blocked_by = vec![blocker.id];
+        let tasks = vec![child, parent, blocker];
+
+        let (rows, _) = visible_rows(&tasks, false, &PriorityConfig::default(), None);
+        assert_eq!(
+            rows,
+            vec![
+                Row {
+                    index: 1,
+                    depth: 0,
+                    blocked: false
+                },
+                Row {
+                    index: 0,
+                    depth: 1,
+                    blocked: true
+                },
+                Row {
+                    index: 2,
+                    depth: 0,
+                    blocked: false
+                },
+            ]
+        );
+    }
+}
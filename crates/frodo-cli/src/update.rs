@@ -12,28 +12,54 @@ use serde::Deserialize;
 use tar::Archive;
 use tracing::info;
 
+use crate::output::Output;
+
 /// Run self-update: check latest release, optionally download and replace binary.
-pub async fn run(check_only: bool) -> Result<()> {
+pub async fn run(check_only: bool, out: &Output) -> Result<()> {
     let current = env!("CARGO_PKG_VERSION");
     let release = fetch_latest_release().await?;
     info!("current version: {}, latest: {}", current, release.tag_name);
+    let status = |updated: bool| {
+        let up_to_date = release.tag_name.trim_start_matches('v') == current;
+        out.object(
+            "self-update",
+            &serde_json::json!({
+                "current": current,
+                "latest": release.tag_name,
+                "update_available": !up_to_date && !updated,
+                "updated": updated,
+            }),
+        )
+    };
 
     if release.tag_name.trim_start_matches('v') == current {
+        if !out.is_text() {
+            return status(false);
+        }
         println!("Already up to date ({}).", current);
         return Ok(());
     }
 
-    println!("Update available: {} -> {}", current, release.tag_name);
     if check_only {
+        if !out.is_text() {
+            return status(false);
+        }
+        println!("Update available: {} -> {}", current, release.tag_name);
         println!("Use `frodo self-update` to apply.");
         return Ok(());
     }
+    if out.is_text() {
+        println!("Update available: {} -> {}", current, release.tag_name);
+    }
 
     let asset = select_asset(&release).ok_or_else(|| {
         color_eyre::eyre::eyre!("no compatible asset found for this platform; aborting")
     })?;
     let tmp = download(&asset.browser_download_url).await?;
     install(&tmp)?;
+    if !out.is_text() {
+        return status(true);
+    }
     println!("Updated to {}", release.tag_name);
     Ok(())
 }
@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+
+use color_eyre::Result;
+use frodo_core::{
+    tasks::{Task, TaskRepository},
+    workspace::{Scope, Workspace},
+};
+use frodo_task::SecureStoreTaskRepo;
+use serde::Serialize;
+
+use crate::{
+    cli::WorkspaceCommand,
+    config,
+    output::{categorized, lib_err, ErrorCategory, Output},
+    select::Selector,
+    storage, tasks,
+};
+
+/// One row of `frodo workspace list`.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+struct WorkspaceRow {
+    #[serde(flatten)]
+    workspace: Workspace,
+    /// Listed under `[[workspaces]]` in the config file.
+    configured: bool,
+    /// Detected from the current directory.
+    active: bool,
+    tasks: usize,
+}
+
+/// Execute a workspace subcommand. `scope` is the workspace detected from the
+/// current directory (ignoring `--all`).
+pub async fn handle(
+    cmd: WorkspaceCommand,
+    cfg: &config::Config,
+    scope: &Scope,
+    out: &Output,
+) -> Result<()> {
+    let store = storage::store_from_config(cfg)?;
+    let repo = SecureStoreTaskRepo::new(&store);
+    match cmd {
+        WorkspaceCommand::Show => {
+            let tasks = repo.list().await.map_err(lib_err)?;
+            let count = tasks.iter().filter(|t| scope.includes(t)).count();
+            let Some(workspace) = scope.workspace() else {
+                if !out.is_text() {
+                    return out.object("workspace.show", &serde_json::Value::Null);
+                }
+                println!("No active workspace: not inside a git repository or configured path.");
+                println!("Commands show all {} task(s).", tasks.len());
+                return Ok(());
+            };
+            if !out.is_text() {
+                return out.object(
+                    "workspace.show",
+                    &serde_json::json!({ "workspace": workspace, "tasks": count }),
+                );
+            }
+            println!("Workspace: {}", workspace.name);
+            for path in &workspace.paths {
+                println!("  path: {}", path.display());
+            }
+            if let Some(github) = &workspace.github {
+                println!("  github: {github}");
+            }
+            if let Some(project) = &workspace.jira_project {
+                println!("  jira: {project}");
+            }
+            println!("  tasks: {count}");
+        }
+        WorkspaceCommand::List => {
+            let tasks = repo.list().await.map_err(lib_err)?;
+            let rows = rows(&cfg.workspaces, scope.workspace(), &tasks);
+            if !out.is_text() {
+                return out.list("workspace.list", &rows);
+            }
+            if rows.is_empty() {
+                println!("No workspaces yet. Run frodo inside a git repository or add [[workspaces]] to the config file.");
+                return Ok(());
+            }
+            for row in &rows {
+                let marker = if row.active { "*" } else { " " };
+                let source = if row.configured { "" } else { " (from tasks)" };
+                println!(
+                    "{marker} {}  {} task(s){source}",
+                    row.workspace.name, row.tasks
+                );
+            }
+            let unassigned = tasks.iter().filter(|t| t.workspace.is_none()).count();
+            if unassigned > 0 {
+                println!("  {unassigned} task(s) in no workspace");
+            }
+        }
+        WorkspaceCommand::Assign { ids, to, clear } => {
+            let target = match (to, clear) {
+                (_, true) => None,
+                (Some(name), false) => Some(name),
+                (None, false) => Some(scope.name().map(str::to_string).ok_or_else(|| {
+                    categorized(
+                        ErrorCategory::Usage,
+                        "no active workspace here; pass --to <name> or --clear",
+                    )
+                })?),
+            };
+            let selector = Selector::load(&repo, &store).await?;
+            let mut moved = Vec::with_capacity(ids.len());
+            for input in &ids {
+                let mut task = tasks::selected_task(&selector, input)?;
+                task.workspace = target.clone();
+                moved.push(repo.update(task).await.map_err(lib_err)?);
+            }
+            if !out.is_text() {
+                return out.list("workspace.assign", &moved);
+            }
+            for task in &moved {
+                match &target {
+                    Some(name) => println!("Moved {} to workspace {name}", task.title),
+                    None => println!("Removed {} from its workspace", task.title),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Configured workspaces first, then names only found on tasks, with task counts.
+fn rows(configured: &[Workspace], active: Option<&Workspace>, tasks: &[Task]) -> Vec<WorkspaceRow> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for name in tasks.iter().filter_map(|t| t.workspace.as_deref()) {
+        *counts.entry(name).or_default() += 1;
+    }
+    let is_active = |name: &str| active.is_some_and(|w| w.name == name);
+
+    let mut rows: Vec<WorkspaceRow> = configured
+        .iter()
+        .map(|w| WorkspaceRow {
+            workspace: w.clone(),
+            configured: true,
+            active: is_active(&w.name),
+            tasks: counts.remove(w.name.as_str()).unwrap_or_default(),
+        })
+        .collect();
+    // The detected git workspace may not have any tasks yet.
+    if let Some(active) = active.filter(|a| !configured.iter().any(|w| w.name == a.name)) {
+        rows.push(WorkspaceRow {
+            workspace: active.clone(),
+            configured: false,
+            active: true,
+            tasks: counts.remove(active.name.as_str()).unwrap_or_default(),
+        });
+    }
+    rows.extend(counts.into_iter().map(|(name, tasks)| WorkspaceRow {
+        workspace: Workspace {
+            name: name.to_string(),
+            paths: Vec::new(),
+            github: None,
+            jira_project: None,
+        },
+        configured: false,
+        active: false,
+        tasks,
+    }));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn lists_configured_detected_and_task_workspaces() {
+        let configured = Workspace {
+            name: "api".into(),
+            paths: vec![PathBuf::from("/src/api")],
+            github: None,
+            jira_project: Some("API".into()),
+        };
+        let detected = Workspace {
+            name: "frodo".into(),
+            paths: vec![PathBuf::from("/src/frodo")],
+            github: Some("frodo-cli/frodo-cli".into()),
+            jira_project: None,
+        };
+        let mut tasks: Vec<Task> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|t| Task::new(t.into(), None, vec![]))
+            .collect();
+        tasks[0].workspace = Some("api".into());
+        tasks[1].workspace = Some("api".into());
+        tasks[2].workspace = Some("old".into());
+
+        let rows = rows(&[configured], Some(&detected), &tasks);
+        let summary: Vec<(&str, bool, bool, usize)> = rows
+            .iter()
+            .map(|r| (r.workspace.name.as_str(), r.configured, r.active, r.tasks))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("api", true, false, 2),
+                ("frodo", false, true, 0),
+                ("old", false, false, 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Parent/child hierarchy and "blocked by" dependencies between tasks.
+//!
+//! Both relations live on [`Task`] (`parent_id`, `blocked_by`); this module
+//! validates edits against cycles and derives views such as the task tree and
+//! whether a task is currently blocked.
+
+use std::collections::{HashMap, HashSet};
+
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::tasks::{Task, TaskStatus};
+
+/// Errors raised when an edit would corrupt the task graph.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum GraphError {
+    /// Referenced task id does not exist.
+    #[error("task not found: {0}")]
+    UnknownTask(Uuid),
+    /// A task cannot be its own parent or blocker.
+    #[error("task {0} cannot reference itself")]
+    SelfReference(Uuid),
+    /// The edit would close a loop; `path` lists the ids along the cycle.
+    #[error("edit would create a cycle: {}", format_path(.path))]
+    Cycle { path: Vec<Uuid> },
+}
+
+fn format_path(path: &[Uuid]) -> String {
+    path.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// One row of a depth-first task tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeEntry<'a> {
+    pub depth: usize,
+    pub task: &'a Task,
+}
+
+/// Read-only view over a task list answering hierarchy and dependency questions.
+pub struct TaskGraph<'a> {
+    order: Vec<&'a Task>,
+    by_id: HashMap<Uuid, &'a Task>,
+    children: HashMap<Uuid, Vec<&'a Task>>,
+}
+
+impl<'a> TaskGraph<'a> {
+    pub fn new(tasks: &'a [Task]) -> Self {
+        let by_id: HashMap<Uuid, &Task> = tasks.iter().map(|t| (t.id, t)).collect();
+        let mut children: HashMap<Uuid, Vec<&Task>> = HashMap::new();
+        for task in tasks {
+            if let Some(parent) = task.parent_id.filter(|p| by_id.contains_key(p)) {
+                children.entry(parent).or_default().push(task);
+            }
+        }
+        Self {
+            order: tasks.iter().collect(),
+            by_id,
+            children,
+        }
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&'a Task> {
+        self.by_id.get(&id).copied()
+    }
+
+    /// Direct children of a task, in list order.
+    pub fn children(&self, id: Uuid) -> &[&'a Task] {
+        self.children.get(&id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Dependencies of `id` that are not done yet. Unknown ids are ignored.
+    pub fn open_blockers(&self, id: Uuid) -> Vec<&'a Task> {
+        self.get(id)
+            .map(|task| {
+                task.blocked_by
+                    .iter()
+                    .filter_map(|b| self.get(*b))
+                    .filter(|b| b.status != TaskStatus::Done)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// A task is blocked while it is open and any of its dependencies is open.
+    pub fn is_blocked(&self, id: Uuid) -> bool {
+        self.get(id)
+            .is_some_and(|t| t.status != TaskStatus::Done && !self.open_blockers(id).is_empty())
+    }
+
+    /// Depth-first tree: roots (no parent, or a parent that no longer exists)
+    /// in list order, each followed by its descendants.
+    pub fn tree(&self) -> Vec<TreeEntry<'a>> {
+        let mut out = Vec::with_capacity(self.order.len());
+        let mut seen = HashSet::new();
+        for task in &self.order {
+            let is_root = task
+                .parent_id
+                .is_none_or(|parent| !self.by_id.contains_key(&parent));
+            if is_root {
+                self.walk(task, 0, &mut seen, &mut out);
+            }
+        }
+        // Tasks caught in a stored parent cycle have no root; surface them flat.
+        for task in &self.order {
+            if !seen.contains(&task.id) {
+                self.walk(task, 0, &mut seen, &mut out);
+            }
+        }
+        out
+    }
+
+    fn walk(
+        &self,
+        task: &'a Task,
+        depth: usize,
+        seen: &mut HashSet<Uuid>,
+        out: &mut Vec<TreeEntry<'a>>,
+    ) {
+        if !seen.insert(task.id) {
+            return;
+        }
+        out.push(TreeEntry { depth, task });
+        for child in self.children(task.id) {
+            self.walk(child, depth + 1, seen, out);
+        }
+    }
+}
+
+/// Validate making `parent` the parent of `child`.
+pub fn check_parent(tasks: &[Task], child: Uuid, parent: Uuid) -> Result<(), GraphError> {
+    let graph = TaskGraph::new(tasks);
+    ensure_known(&graph, child)?;
+    ensure_known(&graph, parent)?;
+    if child == parent {
+        return Err(GraphError::SelfReference(child));
+    }
+
+    // Walk up from the new parent; reaching `child` means a loop.
+    let mut path = vec![child, parent];
+    let mut current = graph.get(parent).and_then(|t| t.parent_id);
+    while let Some(id) = current {
+        path.push(id);
+        if id == child {
+            return Err(GraphError::Cycle { path });
+        }
+        if path.len() > tasks.len() + 1 {
+            break;
+        }
+        current = graph.get(id).and_then(|t| t.parent_id);
+    }
+    Ok(())
+}
+
+/// Validate adding a "`task` is blocked by `blocker`" edge.
+pub fn check_dependency(tasks: &[Task], task: Uuid, blocker: Uuid) -> Result<(), GraphError> {
+    let graph = TaskGraph::new(tasks);
+    ensure_known(&graph, task)?;
+    ensure_known(&graph, blocker)?;
+    if task == blocker {
+        return Err(GraphError::SelfReference(task));
+    }
+
+    // A cycle exists if `task` is already reachable from `blocker` via blocked_by edges.
+    let mut stack = vec![vec![task, blocker]];
+    let mut visited = HashSet::new();
+    while let Some(path) = stack.pop() {
+        let last = *path.last().expect("path is never empty");
+        if !visited.insert(last) {
+            continue;
+        }
+        for next in graph
+            .get(last)
+            .map(|t| t.blocked_by.as_slice())
+            .unwrap_or(&[])
+        {
+            let mut extended = path.clone();
+            extended.push(*next);
+            if *next == task {
+                return Err(GraphError::Cycle { path: extended });
+            }
+            stack.push(extended);
+        }
+    }
+    Ok(())
+}
+
+fn ensure_known(graph: &TaskGraph<'_>, id: Uuid) -> Result<(), GraphError> {
+    graph.get(id).map(|_| ()).ok_or(GraphError::UnknownTask(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tasks(n: usize) -> Vec<Task> {
+        (0..n)
+            .map(|i| Task::new(format!("t{i}"), None, vec![]))
+            .collect()
+    }
+
+    #[test]
+    fn tree_nests_children_under_parents() {
+        let mut list = tasks(4);
+        list[1].parent_id = Some(list[0].id);
+        list[2].parent_id = Some(list[1].id);
+        // Orphan pointing at a missing parent is treated as a root.
+        list[3].parent_id = Some(Uuid::new_v4());
+
+        let graph = TaskGraph::new(&list);
+        let rows: Vec<(usize, &str)> = graph
+            .tree()
+            .iter()
+            .map(|e| (e.depth, e.task.title.as_str()))
+            .collect();
+        assert_eq!(rows, vec![(0, "t0"), (1, "t1"), (2, "t2"), (0, "t3")]);
+    }
+
+    #[test]
+    fn tree_survives_stored_parent_cycle() {
+        let mut list = tasks(2);
+        list[0].parent_id = Some(list[1].id);
+        list[1].parent_id = Some(list[0].id);
+
+        let graph = TaskGraph::new(&list);
+        assert_eq!(graph.tree().len(), 2);
+    }
+
+    #[test]
+    fn blocked_while_dependency_is_open() {
+        let mut list = tasks(2);
+        list[1].blocked_by = vec![list[0].id];
+        let graph = TaskGraph::new(&list);
+        assert!(graph.is_blocked(list[1].id));
+        assert!(!graph.is_blocked(list[0].id));
+
+        list[0].status = TaskStatus::Done;
+        let graph = TaskGraph::new(&list);
+        assert!(!graph.is_blocked(list[1].id));
+    }
+
+    #[test]
+    fn rejects_parent_cycles() {
+        let mut list = tasks(3);
+        list[1].parent_id = Some(list[0].id);
+        list[2].parent_id = Some(list[1].id);
+
+        assert!(check_parent(&list, list[2].id, list[0].id).is_ok());
+        let err = check_parent(&list, list[0].id, list[2].id).expect_err("cycle");
+        assert_eq!(
+            err,
+            GraphError::Cycle {
+                path: vec![list[0].id, list[2].id, list[1].id, list[0].id]
+            }
+        );
+        assert_eq!(
+            check_parent(&list, list[0].id, list[0].id),
+            Err(GraphError::SelfReference(list[0].id))
+        );
+    }
+
+    #[test]
+    fn rejects_dependency_cycles() {
+        let mut list = tasks(3);
+        list[1].blocked_by = vec![list[0].id];
+        list[2].blocked_by = vec![list[1].id];
+
+        let err = check_dependency(&list, list[0].id, list[2].id).expect_err("cycle");
+        assert!(matches!(err, GraphError::Cycle { .. }));
+        assert!(check_dependency(&list, list[2].id, list[0].id).is_ok());
+        let missing = Uuid::new_v4();
+        assert_eq!(
+            check_dependency(&list, list[0].id, missing),
+            Err(GraphError::UnknownTask(missing))
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Change history for tasks: who changed which fields, when, and how to revert it.
+//!
+//! Repositories record a [`TaskEvent`] for every mutation. Changes are kept as
+//! JSON values per top-level task field, so new fields are tracked without
+//! touching this module.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::tasks::Task;
+
+/// Fields that change on every write and are not worth recording.
+const IGNORED_FIELDS: &[&str] = &["updated_at"];
+
+/// What kind of mutation an event records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Created,
+    Updated,
+    /// A previous operation was reverted; `changes` hold the revert itself.
+    Undone,
+}
+
+/// One field's value before and after a change (`null` when absent).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// A recorded mutation of one task.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskEvent {
+    pub id: Uuid,
+    /// Repository call this event belongs to; one call can touch several
+    /// tasks (completing a recurring task also creates the next one).
+    pub operation: Uuid,
+    pub task_id: Uuid,
+    pub at: DateTime<Utc>,
+    pub actor: String,
+    pub kind: EventKind,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Field-level differences between two versions of a task.
+pub fn diff(before: &Task, after: &Task) -> Vec<FieldChange> {
+    let before = fields(before);
+    let after = fields(after);
+    let mut names: Vec<&String> = before.keys().chain(after.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter(|name| !IGNORED_FIELDS.contains(&name.as_str()))
+        .filter_map(|name| {
+            let old = before.get(name).cloned().unwrap_or(Value::Null);
+            let new = after.get(name).cloned().unwrap_or(Value::Null);
+            (old != new).then(|| FieldChange {
+                field: name.clone(),
+                old,
+                new,
+            })
+        })
+        .collect()
+}
+
+/// Changes describing a freshly created task (every field, from `null`).
+pub fn created(task: &Task) -> Vec<FieldChange> {
+    fields(task)
+        .into_iter()
+        .filter(|(name, _)| !IGNORED_FIELDS.contains(&name.as_str()))
+        .map(|(field, new)| FieldChange {
+            field,
+            old: Value::Null,
+            new,
+        })
+        .collect()
+}
+
+/// Put the `old` side of `changes` back onto `task`.
+pub fn revert(task: &Task, changes: &[FieldChange]) -> anyhow::Result<Task> {
+    let mut map = fields(task);
+    for change in changes {
+        map.insert(change.field.clone(), change.old.clone());
+    }
+    Ok(serde_json::from_value(Value::Object(map))?)
+}
+
+fn fields(task: &Task) -> Map<String, Value> {
+    match serde_json::to_value(task) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+/// Best-effort name of the local user, for event attribution.
+pub fn local_actor() -> String {
+    ["FRODO_ACTOR", "USER", "USERNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_else(|| "unknown".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::tasks::{Priority, TaskStatus};
+
+    #[test]
+    fn diff_lists_changed_fields_only() {
+        let before = Task::new("Ship".into(), None, vec![]);
+        let mut after = before.clone();
+        after.status = TaskStatus::Done;
+        after.priority = Priority::High;
+        after.updated_at = Utc::now() + chrono::Duration::seconds(5);
+
+        let changes = diff(&before, &after);
+        assert_eq!(
+            changes,
+            vec![
+                FieldChange {
+                    field: "priority".into(),
+                    old: json!("Medium"),
+                    new: json!("High"),
+                },
+                FieldChange {
+                    field: "status".into(),
+                    old: json!("Todo"),
+                    new: json!("Done"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn revert_restores_previous_values() {
+        let before = Task::new("Ship".into(), Some("v1".into()), vec!["a".into()]);
+        let mut after = before.clone();
+        after.description = None;
+        after.tags.push("b".into());
+
+        let restored = revert(&after, &diff(&before, &after)).unwrap();
+        assert_eq!(restored.description, before.description);
+        assert_eq!(restored.tags, before.tags);
+    }
+
+    #[test]
+    fn created_records_every_field_but_timestamps() {
+        let task = Task::new("New".into(), None, vec![]);
+        let changes = created(&task);
+        assert!(changes
+            .iter()
+            .any(|c| c.field == "title" && c.new == json!("New")));
+        assert!(changes.iter().all(|c| c.old.is_null()));
+        assert!(!changes.iter().any(|c| c.field == "updated_at"));
+    }
+}
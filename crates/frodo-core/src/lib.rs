@@ -2,5 +2,14 @@
 //! This crate is intentionally small to keep dependency surface minimal.
 
 pub mod agent;
+pub mod graph;
+pub mod history;
+pub mod priority;
+pub mod query;
+pub mod recurrence;
+pub mod search;
+pub mod select;
 pub mod storage;
 pub mod tasks;
+pub mod timetrack;
+pub mod workspace;
@@ -0,0 +1,406 @@
+//! Prioritization engine: ranks open tasks to answer "what should I do next".
+//!
+//! Scores are integer points so they stay deterministic and easy to explain;
+//! every contribution is recorded as a [`ScoreReason`].
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::tasks::{Task, TaskStatus};
+
+/// Tunable weights for task scoring (configured under `[priority]`).
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct PriorityConfig {
+    /// Points per priority level above `low`.
+    pub priority_points: i64,
+    /// Flat bonus for tasks past their due date.
+    pub overdue_points: i64,
+    /// Extra points per whole day a task is overdue.
+    pub overdue_points_per_day: i64,
+    /// Window (in days) in which an upcoming due date starts to count.
+    pub due_soon_days: i64,
+    /// Maximum bonus for a due date inside the window (scaled by proximity).
+    pub due_soon_points: i64,
+    /// Points per day since creation, so old tasks do not rot.
+    pub age_points_per_day: i64,
+    /// Cap for the age bonus.
+    pub max_age_points: i64,
+    /// Bonus for tasks already in progress.
+    pub in_progress_points: i64,
+    /// Penalty for each open task blocking this one.
+    pub blocked_points: i64,
+    /// Bonus for each open task waiting on this one.
+    pub unblocks_points: i64,
+    /// Per-tag adjustments (e.g., `backend = 10`, `someday = -30`).
+    pub tag_weights: BTreeMap<String, i64>,
+}
+
+impl Default for PriorityConfig {
+    fn default() -> Self {
+        Self {
+            priority_points: 20,
+            overdue_points: 40,
+            overdue_points_per_day: 2,
+            due_soon_days: 7,
+            due_soon_points: 30,
+            age_points_per_day: 1,
+            max_age_points: 14,
+            in_progress_points: 15,
+            blocked_points: -50,
+            unblocks_points: 10,
+            tag_weights: BTreeMap::new(),
+        }
+    }
+}
+
+/// A single contribution to a task's score.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScoreReason {
+    pub points: i64,
+    pub label: String,
+}
+
+/// Score and explanation for one task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoredTask<'a> {
+    pub task: &'a Task,
+    pub score: i64,
+    pub reasons: Vec<ScoreReason>,
+}
+
+/// Score every open task in `tasks` and return them best-first.
+///
+/// Done tasks are skipped. Ties fall back to earlier due date, then older
+/// creation time, so the order is stable for identical scores.
+pub fn rank<'a>(
+    tasks: &'a [Task],
+    config: &PriorityConfig,
+    now: DateTime<Utc>,
+) -> Vec<ScoredTask<'a>> {
+    let open: HashMap<Uuid, &Task> = tasks
+        .iter()
+        .filter(|t| t.status != TaskStatus::Done)
+        .map(|t| (t.id, t))
+        .collect();
+
+    let mut dependents: HashMap<Uuid, usize> = HashMap::new();
+    for task in open.values() {
+        for blocker in &task.blocked_by {
+            *dependents.entry(*blocker).or_default() += 1;
+        }
+    }
+
+    let mut scored: Vec<ScoredTask<'a>> = tasks
+        .iter()
+        .filter(|t| t.status != TaskStatus::Done)
+        .map(|task| {
+            let open_blockers = task
+                .blocked_by
+                .iter()
+                .filter(|id| open.contains_key(id))
+                .count();
+            let waiting = dependents.get(&task.id).copied().unwrap_or(0);
+            score_with(task, config, now, open_blockers, waiting)
+        })
+        .collect();
+
+    scored.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| match (a.task.due_at, b.task.due_at) {
+                (Some(x), Some(y)) => x.cmp(&y),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            })
+            .then_with(|| a.task.created_at.cmp(&b.task.created_at))
+    });
+    scored
+}
+
+/// Score a single task in isolation (no knowledge of blockers).
+pub fn score<'a>(task: &'a Task, config: &PriorityConfig, now: DateTime<Utc>) -> ScoredTask<'a> {
+    score_with(task, config, now, 0, 0)
+}
+
+fn score_with<'a>(
+    task: &'a Task,
+    config: &PriorityConfig,
+    now: DateTime<Utc>,
+    open_blockers: usize,
+    waiting: usize,
+) -> ScoredTask<'a> {
+    let mut reasons = Vec::new();
+    let mut push = |points: i64, label: String| {
+        if points != 0 {
+            reasons.push(ScoreReason { points, label });
+        }
+    };
+
+    push(
+        task.priority.rank() * config.priority_points,
+        format!("priority {}", task.priority.label()),
+    );
+
+    if let Some(due) = task.due_at {
+        let remaining = due - now;
+        if remaining.num_seconds() < 0 {
+            let days_late = (-remaining).num_days();
+            push(
+                config.overdue_points + days_late * config.overdue_points_per_day,
+                match days_late {
+                    0 => "overdue".to_string(),
+                    1 => "overdue by 1 day".to_string(),
+                    n => format!("overdue by {n} days"),
+                },
+            );
+        } else if config.due_soon_days > 0 {
+            let days_left = remaining.num_days();
+            if days_left < config.due_soon_days {
+                let points = config.due_soon_points * (config.due_soon_days - days_left)
+                    / config.due_soon_days;
+                push(
+                    points,
+                    match days_left {
+                        0 => "due today".to_string(),
+                        1 => "due in 1 day".to_string(),
+                        n => format!("due in {n} days"),
+                    },
+                );
+            }
+        }
+    }
+
+    let age_days = (now - task.created_at).num_days().max(0);
+    push(
+        (age_days * config.age_points_per_day).min(config.max_age_points),
+        format!("open for {age_days} days"),
+    );
+
+    if task.status == TaskStatus::InProgress {
+        push(config.in_progress_points, "already in progress".to_string());
+    }
+
+    if open_blockers > 0 {
+        push(
+            config.blocked_points * open_blockers as i64,
+            format!("blocked by {open_blockers} open task(s)"),
+        );
+    }
+
+    if waiting > 0 {
+        push(
+            config.unblocks_points * waiting as i64,
+            format!("unblocks {waiting} task(s)"),
+        );
+    }
+
+    for tag in &task.tags {
+        if let Some(weight) = config.tag_weights.get(tag) {
+            push(*weight, format!("tag {tag}"));
+        }
+    }
+
+    let score = reasons.iter().map(|r| r.points).sum();
+    ScoredTask {
+        task,
+        score,
+        reasons,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::tasks::Priority;
+
+    fn task_at(title: &str, now: DateTime<Utc>) -> Task {
+        let mut task = Task::new(title.into(), None, vec![]);
+        task.created_at = now;
+        task.updated_at = now;
+        task
+    }
+
+    #[test]
+    fn higher_priority_ranks_first() {
+        let now = Utc::now();
+        let mut low = task_at("low", now);
+        low.priority = Priority::Low;
+        let mut urgent = task_at("urgent", now);
+        urgent.priority = Priority::Urgent;
+        let tasks = vec![low, urgent];
+
+        let ranked = rank(&tasks, &PriorityConfig::default(), now);
+        assert_eq!(ranked[0].task.title, "urgent");
+        assert!(ranked[0]
+            .reasons
+            .iter()
+            .any(|r| r.label == "priority urgent"));
+    }
+
+    #[test]
+    fn skips_done_tasks() {
+        let now = Utc::now();
+        let mut done = task_at("done", now);
+        done.status = TaskStatus::Done;
+        let tasks = vec![done, task_at("open", now)];
+
+        let ranked = rank(&tasks, &PriorityConfig::default(), now);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].task.title, "open");
+    }
+
+    #[test]
+    fn overdue_beats_due_soon() {
+        let now = Utc::now();
+        let mut overdue = task_at("overdue", now);
+        overdue.due_at = Some(now - Duration::days(2));
+        let mut soon = task_at("soon", now);
+        soon.due_at = Some(now + Duration::days(2));
+        let tasks = vec![soon, overdue];
+
+        let ranked = rank(&tasks, &PriorityConfig::default(), now);
+        assert_eq!(ranked[0].task.title, "overdue");
+        assert!(ranked[0]
+            .reasons
+            .iter()
+            .any(|r| r.label == "overdue by 2 days"));
+        assert!(ranked[1].reasons.iter().any(|r| r.label == "due in 2 days"));
+    }
+
+    #[test]
+    fn open_blockers_penalize_and_blockers_get_credit() {
+        let now = Utc::now();
+        let blocker = task_at("blocker", now);
+        let mut blocked = task_at("blocked", now);
+        blocked.blocked_by = vec![blocker.id];
+        let tasks = vec![blocked, blocker];
+
+        let ranked = rank(&tasks, &PriorityConfig::default(), now);
+        assert_eq!(ranked[0].task.title, "blocker");
+        assert!(ranked[0]
+            .reasons
+            .iter()
+            .any(|r| r.label == "unblocks 1 task(s)"));
+        assert!(ranked[1]
+            .reasons
+            .iter()
+            .any(|r| r.label == "blocked by 1 open task(s)"));
+    }
+
+    #[test]
+    fn done_blockers_do_not_penalize() {
+        let now = Utc::now();
+        let mut blocker = task_at("blocker", now);
+        blocker.status = TaskStatus::Done;
+        let mut blocked = task_at("blocked", now);
+        blocked.blocked_by = vec![blocker.id];
+        let tasks = vec![blocked, blocker];
+
+        let ranked = rank(&tasks, &PriorityConfig::default(), now);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(
+            ranked[0].score,
+            score(ranked[0].task, &PriorityConfig::default(), now).score
+        );
+    }
+
+    #[test]
+    fn tag_weights_apply() {
+        let now = Utc::now();
+        let mut config = PriorityConfig::default();
+        config.tag_weights.insert("someday".into(), -30);
+        let mut task = task_at("later", now);
+        task.tags = vec!["someday".into()];
+
+        let scored = score(&task, &config, now);
+        assert_eq!(scored.score, 20 - 30);
+        assert!(scored.reasons.iter().any(|r| r.label == "tag someday"));
+    }
+
+    fn priority_strategy() -> impl Strategy<Value = Priority> {
+        prop_oneof![
+            Just(Priority::Low),
+            Just(Priority::Medium),
+            Just(Priority::High),
+            Just(Priority::Urgent),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn score_is_sum_of_reasons(
+            priority in priority_strategy(),
+            due_offset in proptest::option::of(-30i64..30),
+            age in 0i64..60,
+        ) {
+            let now = Utc::now();
+            let mut task = task_at("t", now - Duration::days(age));
+            task.priority = priority;
+            task.due_at = due_offset.map(|d| now + Duration::days(d));
+
+            let scored = score(&task, &PriorityConfig::default(), now);
+            prop_assert_eq!(scored.score, scored.reasons.iter().map(|r| r.points).sum::<i64>());
+        }
+
+        #[test]
+        fn raising_priority_never_lowers_score(
+            a in priority_strategy(),
+            b in priority_strategy(),
+            due_offset in proptest::option::of(-30i64..30),
+        ) {
+            let now = Utc::now();
+            let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+            let mut task = task_at("t", now);
+            task.due_at = due_offset.map(|d| now + Duration::days(d));
+            task.priority = lo;
+            let lo_score = score(&task, &PriorityConfig::default(), now).score;
+            task.priority = hi;
+            let hi_score = score(&task, &PriorityConfig::default(), now).score;
+            prop_assert!(hi_score >= lo_score);
+        }
+
+        #[test]
+        fn earlier_due_date_never_lowers_score(
+            later in -30i64..30,
+            gap in 0i64..30,
+        ) {
+            let now = Utc::now();
+            let mut task = task_at("t", now);
+            task.due_at = Some(now + Duration::days(later));
+            let later_score = score(&task, &PriorityConfig::default(), now).score;
+            task.due_at = Some(now + Duration::days(later - gap));
+            let earlier_score = score(&task, &PriorityConfig::default(), now).score;
+            prop_assert!(earlier_score >= later_score);
+        }
+
+        #[test]
+        fn rank_is_sorted_and_excludes_done(
+            priorities in proptest::collection::vec((priority_strategy(), any::<bool>()), 0..20),
+        ) {
+            let now = Utc::now();
+            let tasks: Vec<Task> = priorities
+                .into_iter()
+                .map(|(priority, done)| {
+                    let mut task = task_at("t", now);
+                    task.priority = priority;
+                    if done {
+                        task.status = TaskStatus::Done;
+                    }
+                    task
+                })
+                .collect();
+
+            let ranked = rank(&tasks, &PriorityConfig::default(), now);
+            prop_assert!(ranked.iter().all(|s| s.task.status != TaskStatus::Done));
+            prop_assert!(ranked.windows(2).all(|w| w[0].score >= w[1].score));
+        }
+    }
+}
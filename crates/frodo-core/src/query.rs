@@ -0,0 +1,727 @@
+//! Task query language used by `frodo task list`, saved views, the TUI filter
+//! bar and sync scope rules.
+//!
+//! A query is a whitespace-separated list of terms that must all match:
+//!
+//! - `status:todo`, `status:in-progress`, `status:done`
+//! - `tag:backend` (case-insensitive, exact tag)
+//! - `priority:high`, `priority>=high`, `priority<urgent`
+//! - `due<7d`, `due>=2w`, `due<=2026-11-01`, `due:today`, `due:overdue`,
+//!   `due:none`, `due:any` (relative amounts accept `h`, `d` and `w`)
+//! - `is:open`, `is:done`, `is:blocked`, `is:recurring`, `is:subtask`
+//! - bare words or `"quoted phrases"` matched against title and description
+//! - `@name` expands a saved view (see [`Query::parse_with_views`])
+//!
+//! Prefix a term with `-` to negate it, e.g. `-tag:someday`.
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    graph::TaskGraph,
+    tasks::{Priority, Task, TaskStatus},
+};
+
+/// Parse failure with the character offset it was detected at.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("{message} (at column {})", .position + 1)]
+pub struct QueryError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl QueryError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+/// A parsed query; every term must match. The empty query matches everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+/// One (possibly negated) condition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub negated: bool,
+    pub filter: Filter,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    Status(TaskStatus),
+    /// Lowercased tag name.
+    Tag(String),
+    Priority(Cmp, Priority),
+    Due(DueFilter),
+    Is(Flag),
+    /// Lowercased text searched in title and description.
+    Text(String),
+}
+
+/// Comparison operator for ordered fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DueFilter {
+    /// No due date set.
+    None,
+    /// Any due date set.
+    Any,
+    /// Due date in the past on an open task.
+    Overdue,
+    Compare(Cmp, DueBound),
+}
+
+/// Point or day a due date is compared against, resolved at evaluation time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DueBound {
+    /// Offset from now, e.g. `7d` or `-2h`.
+    Relative {
+        amount: i64,
+        unit: char,
+    },
+    /// A whole UTC calendar day.
+    Date(NaiveDate),
+    Today,
+    Tomorrow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    Open,
+    Done,
+    Blocked,
+    Recurring,
+    Subtask,
+}
+
+impl Query {
+    /// Parse a query that may not reference saved views.
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        Parser::new(input, None).parse()
+    }
+
+    /// Parse a query, expanding `@name` terms from `views`.
+    pub fn parse_with_views(
+        input: &str,
+        views: &BTreeMap<String, Query>,
+    ) -> Result<Self, QueryError> {
+        Parser::new(input, Some(views)).parse()
+    }
+
+    pub fn terms(&self) -> &[Term] {
+        &self.terms
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Combine two queries; the result matches tasks matching both.
+    pub fn and(mut self, other: Query) -> Query {
+        self.terms.extend(other.terms);
+        self
+    }
+
+    /// Evaluate against one task. `graph` supplies derived state such as `is:blocked`.
+    pub fn matches(&self, task: &Task, graph: &TaskGraph<'_>, now: DateTime<Utc>) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.filter.matches(task, graph, now) != term.negated)
+    }
+
+    /// Tasks matching the query, in list order.
+    pub fn filter<'a>(&self, tasks: &'a [Task], now: DateTime<Utc>) -> Vec<&'a Task> {
+        let graph = TaskGraph::new(tasks);
+        tasks
+            .iter()
+            .filter(|task| self.matches(task, &graph, now))
+            .collect()
+    }
+}
+
+impl Filter {
+    fn matches(&self, task: &Task, graph: &TaskGraph<'_>, now: DateTime<Utc>) -> bool {
+        match self {
+            Filter::Status(status) => task.status == *status,
+            Filter::Tag(tag) => task.tags.iter().any(|t| t.to_lowercase() == *tag),
+            Filter::Priority(cmp, priority) => cmp.holds(task.priority.cmp(priority)),
+            Filter::Due(due) => due.matches(task, now),
+            Filter::Is(flag) => match flag {
+                Flag::Open => task.status != TaskStatus::Done,
+                Flag::Done => task.status == TaskStatus::Done,
+                Flag::Blocked => graph.is_blocked(task.id),
+                Flag::Recurring => task.recurrence.is_some(),
+                Flag::Subtask => task.parent_id.is_some(),
+            },
+            Filter::Text(text) => {
+                task.title.to_lowercase().contains(text)
+                    || task
+                        .description
+                        .as_deref()
+                        .is_some_and(|d| d.to_lowercase().contains(text))
+            }
+        }
+    }
+}
+
+impl Cmp {
+    fn holds(self, ordering: std::cmp::Ordering) -> bool {
+        use std::cmp::Ordering::*;
+        match self {
+            Cmp::Lt => ordering == Less,
+            Cmp::Le => ordering != Greater,
+            Cmp::Eq => ordering == Equal,
+            Cmp::Ge => ordering != Less,
+            Cmp::Gt => ordering == Greater,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Cmp::Lt => "<",
+            Cmp::Le => "<=",
+            Cmp::Eq => ":",
+            Cmp::Ge => ">=",
+            Cmp::Gt => ">",
+        }
+    }
+}
+
+impl DueFilter {
+    fn matches(&self, task: &Task, now: DateTime<Utc>) -> bool {
+        match (self, task.due_at) {
+            (DueFilter::None, due) => due.is_none(),
+            (DueFilter::Any, due) => due.is_some(),
+            (DueFilter::Overdue, Some(due)) => due < now && task.status != TaskStatus::Done,
+            (DueFilter::Compare(cmp, bound), Some(due)) => {
+                let (start, end) = bound.range(now);
+                match cmp {
+                    Cmp::Lt => due < start,
+                    Cmp::Le => due <= end,
+                    Cmp::Eq => start <= due && due <= end,
+                    Cmp::Ge => due >= start,
+                    Cmp::Gt => due > end,
+                }
+            }
+            (_, None) => false,
+        }
+    }
+}
+
+impl DueBound {
+    /// Inclusive instant range the bound covers: a single instant for relative
+    /// offsets, a whole day for dates.
+    fn range(&self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        let day = |date: NaiveDate| {
+            let start = date.and_hms_opt(0, 0, 0).expect("midnight").and_utc();
+            (start, start + Duration::days(1) - Duration::nanoseconds(1))
+        };
+        match self {
+            DueBound::Relative { amount, unit } => {
+                // Offsets past the representable range are open bounds.
+                let at = unit_duration(*amount, *unit)
+                    .and_then(|offset| now.checked_add_signed(offset))
+                    .unwrap_or(if *amount < 0 {
+                        DateTime::<Utc>::MIN_UTC
+                    } else {
+                        DateTime::<Utc>::MAX_UTC
+                    });
+                (at, at)
+            }
+            DueBound::Date(date) => day(*date),
+            DueBound::Today => day(now.date_naive()),
+            DueBound::Tomorrow => day(now.date_naive() + Duration::days(1)),
+        }
+    }
+}
+
+/// Largest relative offset a due filter accepts (about a century).
+const MAX_OFFSET_DAYS: i64 = 36_500;
+
+/// The offset `amount` units long, or `None` past [`MAX_OFFSET_DAYS`].
+fn unit_duration(amount: i64, unit: char) -> Option<Duration> {
+    let offset = match unit {
+        'h' => Duration::try_hours(amount),
+        'w' => Duration::try_weeks(amount),
+        _ => Duration::try_days(amount),
+    }?;
+    (offset.num_days().abs() <= MAX_OFFSET_DAYS).then_some(offset)
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    views: Option<&'a BTreeMap<String, Query>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &str, views: Option<&'a BTreeMap<String, Query>>) -> Self {
+        Self {
+            chars: input.chars().collect(),
+            pos: 0,
+            views,
+        }
+    }
+
+    fn parse(mut self) -> Result<Query, QueryError> {
+        let mut terms = Vec::new();
+        loop {
+            while self.peek().is_some_and(char::is_whitespace) {
+                self.pos += 1;
+            }
+            if self.peek().is_none() {
+                return Ok(Query { terms });
+            }
+            self.term(&mut terms)?;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn term(&mut self, terms: &mut Vec<Term>) -> Result<(), QueryError> {
+        let start = self.pos;
+        let negated = self.peek() == Some('-')
+            && self
+                .chars
+                .get(self.pos + 1)
+                .is_some_and(|c| !c.is_whitespace());
+        if negated {
+            self.pos += 1;
+        }
+
+        match self.peek() {
+            Some('"') => {
+                let text = self.quoted()?;
+                if !text.is_empty() {
+                    terms.push(Term {
+                        negated,
+                        filter: Filter::Text(text.to_lowercase()),
+                    });
+                }
+                return Ok(());
+            }
+            Some('@') => {
+                self.pos += 1;
+                let name = self.word();
+                if negated {
+                    return Err(QueryError::new(start, "saved views cannot be negated"));
+                }
+                let view = self
+                    .views
+                    .and_then(|views| views.get(&name))
+                    .ok_or_else(|| QueryError::new(start, format!("unknown view `@{name}`")))?;
+                terms.extend(view.terms.iter().cloned());
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let key_start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        let key: String = self.chars[key_start..self.pos].iter().collect();
+        let cmp = match (self.peek(), self.chars.get(self.pos + 1)) {
+            _ if key.is_empty() => None,
+            (Some(':'), _) => Some((Cmp::Eq, 1)),
+            (Some('<'), Some('=')) => Some((Cmp::Le, 2)),
+            (Some('>'), Some('=')) => Some((Cmp::Ge, 2)),
+            (Some('<'), _) => Some((Cmp::Lt, 1)),
+            (Some('>'), _) => Some((Cmp::Gt, 1)),
+            _ => None,
+        };
+        let Some((cmp, width)) = cmp else {
+            // Plain search word.
+            self.pos = key_start;
+            let word = self.word();
+            terms.push(Term {
+                negated,
+                filter: Filter::Text(word.to_lowercase()),
+            });
+            return Ok(());
+        };
+        self.pos += width;
+
+        let value_start = self.pos;
+        let value = if self.peek() == Some('"') {
+            self.quoted()?
+        } else {
+            self.word()
+        };
+        if value.is_empty() {
+            return Err(QueryError::new(
+                value_start,
+                format!("missing value for `{key}`"),
+            ));
+        }
+        let filter = field(&key.to_ascii_lowercase(), cmp, &value)
+            .map_err(|message| QueryError::new(key_start, message))?;
+        terms.push(Term { negated, filter });
+        Ok(())
+    }
+
+    /// Read up to the next whitespace.
+    fn word(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| !c.is_whitespace()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Read a `"..."` string starting at the opening quote.
+    fn quoted(&mut self) -> Result<String, QueryError> {
+        let open = self.pos;
+        self.pos += 1;
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c == '"' {
+                let text = self.chars[start..self.pos].iter().collect();
+                self.pos += 1;
+                return Ok(text);
+            }
+            self.pos += 1;
+        }
+        Err(QueryError::new(open, "unterminated quote"))
+    }
+}
+
+/// Build the filter for `key<cmp>value`.
+fn field(key: &str, cmp: Cmp, value: &str) -> Result<Filter, String> {
+    let only_eq = |filter: Filter| match cmp {
+        Cmp::Eq => Ok(filter),
+        _ => Err(format!("`{key}` only supports `{key}:value`")),
+    };
+    match key {
+        "status" => only_eq(Filter::Status(parse_status(value)?)),
+        "tag" => only_eq(Filter::Tag(value.to_lowercase())),
+        "priority" | "prio" => Ok(Filter::Priority(cmp, value.parse()?)),
+        "is" => only_eq(Filter::Is(parse_flag(value)?)),
+        "due" => parse_due(cmp, value).map(Filter::Due),
+        other => Err(format!(
+            "unknown field `{other}` (expected status, tag, priority, due or is)"
+        )),
+    }
+}
+
+fn parse_status(value: &str) -> Result<TaskStatus, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "todo" => Ok(TaskStatus::Todo),
+        "in-progress" | "in_progress" | "inprogress" | "doing" => Ok(TaskStatus::InProgress),
+        "done" => Ok(TaskStatus::Done),
+        other => Err(format!(
+            "unknown status `{other}` (expected todo, in-progress or done)"
+        )),
+    }
+}
+
+fn parse_flag(value: &str) -> Result<Flag, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "open" => Ok(Flag::Open),
+        "done" => Ok(Flag::Done),
+        "blocked" => Ok(Flag::Blocked),
+        "recurring" => Ok(Flag::Recurring),
+        "subtask" => Ok(Flag::Subtask),
+        other => Err(format!(
+            "unknown flag `is:{other}` (expected open, done, blocked, recurring or subtask)"
+        )),
+    }
+}
+
+fn parse_due(cmp: Cmp, value: &str) -> Result<DueFilter, String> {
+    let lower = value.to_ascii_lowercase();
+    match (cmp, lower.as_str()) {
+        (Cmp::Eq, "none") => return Ok(DueFilter::None),
+        (Cmp::Eq, "any") => return Ok(DueFilter::Any),
+        (Cmp::Eq, "overdue") => return Ok(DueFilter::Overdue),
+        (_, "today") => return Ok(DueFilter::Compare(cmp, DueBound::Today)),
+        (_, "tomorrow") => return Ok(DueFilter::Compare(cmp, DueBound::Tomorrow)),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&lower, "%Y-%m-%d") {
+        return Ok(DueFilter::Compare(cmp, DueBound::Date(date)));
+    }
+    if let Some(unit @ ('h' | 'd' | 'w')) = lower.chars().last() {
+        if let Ok(amount) = lower[..lower.len() - 1].parse::<i64>() {
+            if unit_duration(amount, unit).is_none() {
+                return Err(format!(
+                    "due offset `{value}` is too far away (at most {MAX_OFFSET_DAYS} days)"
+                ));
+            }
+            return Ok(DueFilter::Compare(cmp, DueBound::Relative { amount, unit }));
+        }
+    }
+    Err(format!(
+        "invalid due filter `{value}` (use e.g. due<7d, due<=2026-11-01, due:today, \
+         due:overdue, due:none or due:any)"
+    ))
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negated {
+            write!(f, "-")?;
+        }
+        match &self.filter {
+            Filter::Status(status) => write!(
+                f,
+                "status:{}",
+                match status {
+                    TaskStatus::Todo => "todo",
+                    TaskStatus::InProgress => "in-progress",
+                    TaskStatus::Done => "done",
+                }
+            ),
+            Filter::Tag(tag) => write!(f, "tag:{}", quote_if_needed(tag)),
+            Filter::Priority(cmp, priority) => {
+                write!(f, "priority{}{}", cmp.symbol(), priority.label())
+            }
+            Filter::Due(due) => match due {
+                DueFilter::None => write!(f, "due:none"),
+                DueFilter::Any => write!(f, "due:any"),
+                DueFilter::Overdue => write!(f, "due:overdue"),
+                DueFilter::Compare(cmp, bound) => {
+                    write!(f, "due{}", cmp.symbol())?;
+                    match bound {
+                        DueBound::Relative { amount, unit } => write!(f, "{amount}{unit}"),
+                        DueBound::Date(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+                        DueBound::Today => write!(f, "today"),
+                        DueBound::Tomorrow => write!(f, "tomorrow"),
+                    }
+                }
+            },
+            Filter::Is(flag) => write!(
+                f,
+                "is:{}",
+                match flag {
+                    Flag::Open => "open",
+                    Flag::Done => "done",
+                    Flag::Blocked => "blocked",
+                    Flag::Recurring => "recurring",
+                    Flag::Subtask => "subtask",
+                }
+            ),
+            Filter::Text(text) => {
+                // Words that would parse as something else are quoted.
+                let plain = !text.is_empty()
+                    && !text.contains(|c: char| {
+                        c.is_whitespace() || matches!(c, ':' | '<' | '>' | '"' | '@')
+                    })
+                    && !text.starts_with('-');
+                if plain {
+                    write!(f, "{text}")
+                } else {
+                    write!(f, "\"{text}\"")
+                }
+            }
+        }
+    }
+}
+
+fn quote_if_needed(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{value}\"")
+    } else {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, term) in self.terms.iter().enumerate() {
+            if idx > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{term}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s)
+    }
+}
+
+impl TryFrom<String> for Query {
+    type Error = QueryError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Query> for String {
+    fn from(value: Query) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap()
+    }
+
+    fn task(title: &str, tags: &[&str]) -> Task {
+        Task::new(
+            title.into(),
+            None,
+            tags.iter().map(|t| t.to_string()).collect(),
+        )
+    }
+
+    fn titles(query: &str, tasks: &[Task]) -> Vec<String> {
+        Query::parse(query)
+            .expect("parse")
+            .filter(tasks, now())
+            .iter()
+            .map(|t| t.title.clone())
+            .collect()
+    }
+
+    #[test]
+    fn parses_the_documented_example() {
+        let query = Query::parse(r#"status:todo tag:backend due<7d -tag:someday "search words""#)
+            .expect("parse");
+        assert_eq!(
+            query.terms(),
+            &[
+                Term {
+                    negated: false,
+                    filter: Filter::Status(TaskStatus::Todo)
+                },
+                Term {
+                    negated: false,
+                    filter: Filter::Tag("backend".into())
+                },
+                Term {
+                    negated: false,
+                    filter: Filter::Due(DueFilter::Compare(
+                        Cmp::Lt,
+                        DueBound::Relative {
+                            amount: 7,
+                            unit: 'd'
+                        }
+                    ))
+                },
+                Term {
+                    negated: true,
+                    filter: Filter::Tag("someday".into())
+                },
+                Term {
+                    negated: false,
+                    filter: Filter::Text("search words".into())
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn filters_by_status_tags_and_text() {
+        let mut done = task("Ship API", &["backend"]);
+        done.status = TaskStatus::Done;
+        let mut described = task("Docs", &["Backend", "someday"]);
+        described.description = Some("Write the API guide".into());
+        let tasks = vec![done, described, task("Fix login", &["frontend"])];
+
+        assert_eq!(titles("tag:backend", &tasks), vec!["Ship API", "Docs"]);
+        assert_eq!(titles("tag:backend -tag:someday", &tasks), vec!["Ship API"]);
+        assert_eq!(titles("status:todo api", &tasks), vec!["Docs"]);
+        assert_eq!(titles(r#""fix login""#, &tasks), vec!["Fix login"]);
+        assert_eq!(titles("", &tasks).len(), 3);
+    }
+
+    #[test]
+    fn compares_due_dates_and_priority() {
+        let mut soon = task("soon", &[]);
+        soon.due_at = Some(now() + Duration::days(2));
+        soon.priority = Priority::High;
+        let mut later = task("later", &[]);
+        later.due_at = Some(now() + Duration::days(30));
+        let mut overdue = task("overdue", &[]);
+        overdue.due_at = Some(now() - Duration::hours(1));
+        let tasks = vec![soon, later, overdue, task("undated", &[])];
+
+        assert_eq!(titles("due<7d", &tasks), vec!["soon", "overdue"]);
+        assert_eq!(titles("due>=7d", &tasks), vec!["later"]);
+        assert_eq!(titles("due:overdue", &tasks), vec!["overdue"]);
+        assert_eq!(titles("due:none", &tasks), vec!["undated"]);
+        assert_eq!(titles("due:2026-10-20", &tasks), vec!["soon"]);
+        assert_eq!(titles("due<=2026-10-18", &tasks), vec!["overdue"]);
+        assert_eq!(titles("priority>=high", &tasks), vec!["soon"]);
+        assert_eq!(titles("-priority:medium", &tasks), vec!["soon"]);
+    }
+
+    #[test]
+    fn flags_use_derived_graph_state() {
+        let blocker = task("blocker", &[]);
+        let mut blocked = task("blocked", &[]);
+        blocked.blocked_by = vec![blocker.id];
+        blocked.parent_id = Some(blocker.id);
+        let tasks = vec![blocker, blocked];
+
+        assert_eq!(titles("is:blocked", &tasks), vec!["blocked"]);
+        assert_eq!(titles("-is:subtask", &tasks), vec!["blocker"]);
+    }
+
+    #[test]
+    fn expands_saved_views() {
+        let mut views = BTreeMap::new();
+        views.insert("backend".to_string(), Query::parse("tag:backend").unwrap());
+        let query = Query::parse_with_views("@backend status:todo", &views).expect("parse");
+        assert_eq!(query.to_string(), "tag:backend status:todo");
+
+        let err = Query::parse("@backend").expect_err("no views");
+        assert_eq!(err.message, "unknown view `@backend`");
+    }
+
+    #[test]
+    fn reports_errors_with_positions() {
+        let err = Query::parse("status:todo colour:red").expect_err("unknown field");
+        assert_eq!(err.position, 12);
+        assert!(err.to_string().contains("unknown field `colour`"));
+        assert!(Query::parse("tag<x").is_err());
+        assert!(Query::parse("due<soon").is_err());
+        let err = Query::parse("is:open due<100000000000w").expect_err("offset overflow");
+        assert_eq!(err.position, 8);
+        assert!(err.to_string().contains("too far away"));
+        assert!(Query::parse("\"open").is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let source =
+            r#"-status:in-progress tag:"big rock" priority<urgent due>=-2h is:open "a:b" word"#;
+        let query = Query::parse(source).expect("parse");
+        assert_eq!(query.to_string(), source);
+        assert_eq!(Query::parse(&query.to_string()).unwrap(), query);
+    }
+}
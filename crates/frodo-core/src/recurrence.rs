@@ -0,0 +1,511 @@
+//! Recurrence rules for repeating tasks.
+//!
+//! Rules are stored and parsed in a compact text form:
+//! `daily`, `every:3d`, `weekly`, `weekly:mon,thu`, `monthly`, `monthly:15`,
+//! `cron:0 9 * * 1-5` (UTC) and `after:3d` (N days after completion).
+
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// How a task repeats once it is completed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Recurrence {
+    /// Every `n` days from the previous due date.
+    Days(u32),
+    /// Weekly on the given weekdays (empty = same weekday as the previous due date).
+    Weekly(Vec<Weekday>),
+    /// Monthly on a day of the month (`None` = same day as the previous due date).
+    /// Days past the end of a month clamp to its last day.
+    Monthly(Option<u32>),
+    /// Five-field cron expression evaluated in UTC.
+    Cron(Box<CronSchedule>),
+    /// `n` days after the previous occurrence was completed.
+    AfterCompletion(u32),
+}
+
+impl Recurrence {
+    /// Due date of the next occurrence after a task with `due` was completed at `completed_at`.
+    ///
+    /// Calendar rules advance from the previous due date (or the completion time
+    /// when there was none) and skip occurrences already in the past, so a late
+    /// completion does not spawn a backlog of overdue copies. Whole missed
+    /// periods are skipped arithmetically, so a long-overdue task costs the
+    /// same as a punctual one.
+    pub fn next_due(
+        &self,
+        due: Option<DateTime<Utc>>,
+        completed_at: DateTime<Utc>,
+    ) -> DateTime<Utc> {
+        let base = due.unwrap_or(completed_at);
+        let rule = match self {
+            Recurrence::AfterCompletion(days) => {
+                return completed_at + Duration::days(i64::from(*days));
+            }
+            Recurrence::Cron(schedule) => {
+                let from = base.max(completed_at);
+                return schedule
+                    .next_after(from)
+                    // Unsatisfiable expressions (e.g. Feb 30) fall back to yearly.
+                    .unwrap_or(from + Duration::days(365));
+            }
+            rule => rule.anchored(base),
+        };
+
+        let mut next = rule.step(rule.skip_missed(base, completed_at));
+        // At most a couple of steps remain once whole periods are skipped.
+        while next <= completed_at {
+            next = rule.step(next);
+        }
+        next
+    }
+
+    /// The rule a later occurrence due at `due` should carry: `monthly`
+    /// becomes `monthly:<day of due>`, so a task due on the 31st comes back
+    /// on the 31st after passing through shorter months.
+    pub fn anchored(&self, due: DateTime<Utc>) -> Recurrence {
+        match self {
+            Recurrence::Monthly(None) => Recurrence::Monthly(Some(due.day())),
+            rule => rule.clone(),
+        }
+    }
+
+    /// The last point on the rule's grid from `from` that is not after
+    /// `until` (just `from` when it is not in the past).
+    fn skip_missed(&self, from: DateTime<Utc>, until: DateTime<Utc>) -> DateTime<Utc> {
+        if from >= until {
+            return from;
+        }
+        let elapsed = until - from;
+        match self {
+            Recurrence::Days(days) | Recurrence::AfterCompletion(days) => {
+                let period = Duration::days(i64::from((*days).max(1)));
+                from + period * (elapsed.num_seconds() / period.num_seconds()) as i32
+            }
+            Recurrence::Weekly(_) => from + Duration::weeks(elapsed.num_days() / 7),
+            Recurrence::Monthly(day) => {
+                let months =
+                    (until.year() - from.year()) * 12 + until.month() as i32 - from.month() as i32;
+                if months <= 1 {
+                    return from;
+                }
+                add_months(from, months - 1, day.unwrap_or(from.day()))
+            }
+            Recurrence::Cron(_) => from,
+        }
+    }
+
+    fn step(&self, from: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Recurrence::Days(days) => from + Duration::days(i64::from((*days).max(1))),
+            Recurrence::AfterCompletion(days) => from + Duration::days(i64::from((*days).max(1))),
+            Recurrence::Weekly(days) if days.is_empty() => from + Duration::weeks(1),
+            Recurrence::Weekly(days) => (1..=7)
+                .map(|offset| from + Duration::days(offset))
+                .find(|candidate| days.contains(&candidate.weekday()))
+                .expect("every weekday occurs within seven days"),
+            Recurrence::Monthly(day) => add_months(from, 1, day.unwrap_or(from.day())),
+            Recurrence::Cron(schedule) => schedule
+                .next_after(from)
+                // Unsatisfiable expressions (e.g. Feb 30) fall back to yearly.
+                .unwrap_or(from + Duration::days(365)),
+        }
+    }
+}
+
+/// `from` moved `months` months ahead onto `day`, clamped to the month's
+/// last day, keeping the time of day.
+fn add_months(from: DateTime<Utc>, months: i32, day: u32) -> DateTime<Utc> {
+    let index = from.year() * 12 + from.month0() as i32 + months;
+    let (year, month) = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+    let day = day.clamp(1, days_in_month(year, month));
+    NaiveDate::from_ymd_opt(year, month, day)
+        .and_then(|date| date.and_hms_opt(from.hour(), from.minute(), from.second()))
+        .map(|dt| dt.and_utc())
+        .expect("clamped day is valid")
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|first| first.pred_opt())
+        .map(|last| last.day())
+        .unwrap_or(28)
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Days(1) => write!(f, "daily"),
+            Recurrence::Days(n) => write!(f, "every:{n}d"),
+            Recurrence::Weekly(days) if days.is_empty() => write!(f, "weekly"),
+            Recurrence::Weekly(days) => {
+                let names: Vec<String> =
+                    days.iter().map(|d| d.to_string().to_lowercase()).collect();
+                write!(f, "weekly:{}", names.join(","))
+            }
+            Recurrence::Monthly(None) => write!(f, "monthly"),
+            Recurrence::Monthly(Some(day)) => write!(f, "monthly:{day}"),
+            Recurrence::Cron(schedule) => write!(f, "cron:{}", schedule.expr),
+            Recurrence::AfterCompletion(n) => write!(f, "after:{n}d"),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (kind, arg) = match s.split_once(':') {
+            Some((kind, arg)) => (kind.trim().to_ascii_lowercase(), Some(arg.trim())),
+            None => (s.to_ascii_lowercase(), None),
+        };
+        match (kind.as_str(), arg) {
+            ("daily", None) => Ok(Recurrence::Days(1)),
+            ("every", Some(arg)) => parse_days(arg).map(Recurrence::Days),
+            ("weekly", None) => Ok(Recurrence::Weekly(Vec::new())),
+            ("weekly", Some(arg)) => {
+                let mut days = Vec::new();
+                for name in arg.split(',') {
+                    let day: Weekday = name
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("unknown weekday `{}`", name.trim()))?;
+                    if !days.contains(&day) {
+                        days.push(day);
+                    }
+                }
+                days.sort_by_key(|d| d.num_days_from_monday());
+                Ok(Recurrence::Weekly(days))
+            }
+            ("monthly", None) => Ok(Recurrence::Monthly(None)),
+            ("monthly", Some(arg)) => match arg.parse::<u32>() {
+                Ok(day @ 1..=31) => Ok(Recurrence::Monthly(Some(day))),
+                _ => Err(format!("invalid day of month `{arg}` (expected 1-31)")),
+            },
+            ("cron", Some(arg)) => arg.parse().map(|c| Recurrence::Cron(Box::new(c))),
+            ("after", Some(arg)) => parse_days(arg).map(Recurrence::AfterCompletion),
+            _ => Err(format!(
+                "unknown recurrence `{s}` (expected daily, every:Nd, weekly[:mon,...], \
+                 monthly[:D], cron:<expr> or after:Nd)"
+            )),
+        }
+    }
+}
+
+/// Longest accepted day interval (about a century), so stepping a rule can
+/// never overflow the date range.
+const MAX_DAYS: u32 = 36_500;
+
+fn parse_days(arg: &str) -> Result<u32, String> {
+    let digits = arg.strip_suffix('d').unwrap_or(arg);
+    match digits.parse::<u32>() {
+        Ok(n @ 1..=MAX_DAYS) => Ok(n),
+        _ => Err(format!(
+            "invalid day count `{arg}` (expected e.g. 3d, at most {MAX_DAYS}d)"
+        )),
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(value: Recurrence) -> Self {
+        value.to_string()
+    }
+}
+
+/// Parsed five-field cron expression: minute, hour, day of month, month, day of week.
+/// Supports `*`, numbers, lists, ranges and `/` steps; day of week 0 and 7 are Sunday.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    expr: String,
+    minutes: BTreeSet<u32>,
+    hours: BTreeSet<u32>,
+    days_of_month: BTreeSet<u32>,
+    months: BTreeSet<u32>,
+    days_of_week: BTreeSet<u32>,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl CronSchedule {
+    /// First matching minute strictly after `after`, searching up to five years ahead.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after
+            .with_second(0)
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap_or(after)
+            + Duration::minutes(1);
+        let start_date = start.date_naive();
+
+        for offset in 0..(366 * 5) {
+            let date = start_date + Duration::days(offset);
+            if !self.matches_date(date) {
+                continue;
+            }
+            for hour in &self.hours {
+                for minute in &self.minutes {
+                    let candidate = date.and_hms_opt(*hour, *minute, 0)?.and_utc();
+                    if candidate >= start {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !self.months.contains(&date.month()) {
+            return false;
+        }
+        let dom = self.days_of_month.contains(&date.day());
+        let dow = self
+            .days_of_week
+            .contains(&date.weekday().num_days_from_sunday());
+        // Classic cron: when both day fields are restricted, either may match.
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (true, false) => dow,
+            (false, true) => dom,
+            (false, false) => dom || dow,
+        }
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [minute, hour, dom, month, dow] = fields.as_slice() else {
+            return Err(format!(
+                "cron expression `{s}` must have 5 fields (minute hour day month weekday)"
+            ));
+        };
+        let mut days_of_week = parse_cron_field(dow, 0, 7)?;
+        if days_of_week.remove(&7) {
+            days_of_week.insert(0);
+        }
+        Ok(Self {
+            expr: fields.join(" "),
+            minutes: parse_cron_field(minute, 0, 59)?,
+            hours: parse_cron_field(hour, 0, 23)?,
+            days_of_month: parse_cron_field(dom, 1, 31)?,
+            months: parse_cron_field(month, 1, 12)?,
+            days_of_week,
+            any_day_of_month: *dom == "*",
+            any_day_of_week: *dow == "*",
+        })
+    }
+}
+
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<BTreeSet<u32>, String> {
+    let mut values = BTreeSet::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("invalid cron step in `{part}`"))?,
+            ),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (parse_cron_value(a, part)?, parse_cron_value(b, part)?)
+        } else {
+            let value = parse_cron_value(range, part)?;
+            (value, if step > 1 { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("cron field `{part}` is outside {min}-{max}"));
+        }
+        values.extend((start..=end).step_by(step as usize));
+    }
+    Ok(values)
+}
+
+fn parse_cron_value(value: &str, part: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid cron value in `{part}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn parses_and_displays_round_trip() {
+        for rule in [
+            "daily",
+            "every:3d",
+            "weekly",
+            "weekly:mon,thu",
+            "monthly",
+            "monthly:15",
+            "cron:0 9 * * 1-5",
+            "after:2d",
+        ] {
+            let parsed: Recurrence = rule.parse().expect(rule);
+            assert_eq!(parsed.to_string(), rule);
+        }
+        assert!("hourly".parse::<Recurrence>().is_err());
+        assert!("monthly:32".parse::<Recurrence>().is_err());
+        assert!("cron:0 9 * *".parse::<Recurrence>().is_err());
+        assert!("every:4000000000d".parse::<Recurrence>().is_err());
+        assert!("after:36501d".parse::<Recurrence>().is_err());
+        let longest: Recurrence = "every:36500d".parse().unwrap();
+        let due = at("2025-01-01T09:00:00Z");
+        assert!(longest.next_due(Some(due), due) > due);
+    }
+
+    #[test]
+    fn serializes_as_text() {
+        let rule: Recurrence = "weekly:fri".parse().unwrap();
+        let json = serde_json::to_string(&rule).unwrap();
+        assert_eq!(json, "\"weekly:fri\"");
+        let back: Recurrence = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, rule);
+    }
+
+    #[test]
+    fn daily_advances_from_due_date() {
+        let rule = Recurrence::Days(1);
+        let due = at("2026-10-18T09:00:00Z");
+        assert_eq!(
+            rule.next_due(Some(due), at("2026-10-18T08:00:00Z")),
+            at("2026-10-19T09:00:00Z")
+        );
+        // Completed three days late: skip the missed occurrences.
+        assert_eq!(
+            rule.next_due(Some(due), at("2026-10-21T10:00:00Z")),
+            at("2026-10-22T09:00:00Z")
+        );
+    }
+
+    #[test]
+    fn weekly_picks_next_listed_weekday() {
+        let rule: Recurrence = "weekly:mon,thu".parse().unwrap();
+        // 2026-10-19 is a Monday.
+        let due = at("2026-10-19T17:00:00Z");
+        assert_eq!(rule.next_due(Some(due), due), at("2026-10-22T17:00:00Z"));
+        let thursday = at("2026-10-22T17:00:00Z");
+        assert_eq!(
+            rule.next_due(Some(thursday), thursday),
+            at("2026-10-26T17:00:00Z")
+        );
+    }
+
+    #[test]
+    fn monthly_clamps_to_month_end() {
+        let rule = Recurrence::Monthly(Some(31));
+        let due = at("2027-01-31T12:00:00Z");
+        assert_eq!(rule.next_due(Some(due), due), at("2027-02-28T12:00:00Z"));
+        let rule = Recurrence::Monthly(None);
+        let due = at("2026-12-15T12:00:00Z");
+        assert_eq!(rule.next_due(Some(due), due), at("2027-01-15T12:00:00Z"));
+    }
+
+    #[test]
+    fn monthly_keeps_the_original_day_after_short_months() {
+        let rule = Recurrence::Monthly(None);
+        let due = at("2027-01-31T12:00:00Z");
+        let feb = rule.next_due(Some(due), due);
+        assert_eq!(feb, at("2027-02-28T12:00:00Z"));
+        let carried = rule.anchored(due);
+        assert_eq!(carried, Recurrence::Monthly(Some(31)));
+        assert_eq!(carried.next_due(Some(feb), feb), at("2027-03-31T12:00:00Z"));
+        // Completed in early March: February's slot is skipped, still on the 31st.
+        assert_eq!(
+            rule.next_due(Some(due), at("2027-03-05T00:00:00Z")),
+            at("2027-03-31T12:00:00Z")
+        );
+    }
+
+    #[test]
+    fn long_overdue_rules_jump_straight_to_the_next_slot() {
+        let due = at("2020-01-01T09:00:00Z");
+        let done = at("2026-10-21T10:00:00Z");
+        assert_eq!(
+            Recurrence::Days(1).next_due(Some(due), done),
+            at("2026-10-22T09:00:00Z")
+        );
+        assert_eq!(
+            Recurrence::Days(3).next_due(Some(due), done),
+            at("2026-10-23T09:00:00Z")
+        );
+        // 2020-01-01 was a Wednesday; 2026-10-21 is one too.
+        assert_eq!(
+            Recurrence::Weekly(Vec::new()).next_due(Some(due), done),
+            at("2026-10-28T09:00:00Z")
+        );
+        assert_eq!(
+            "weekly:mon,thu"
+                .parse::<Recurrence>()
+                .unwrap()
+                .next_due(Some(due), done),
+            at("2026-10-22T09:00:00Z")
+        );
+        assert_eq!(
+            Recurrence::Monthly(Some(1)).next_due(Some(due), done),
+            at("2026-11-01T09:00:00Z")
+        );
+        let every_minute: Recurrence = "cron:* * * * *".parse().unwrap();
+        assert_eq!(
+            every_minute.next_due(Some(due), done),
+            at("2026-10-21T10:01:00Z")
+        );
+    }
+
+    #[test]
+    fn after_completion_ignores_due_date() {
+        let rule = Recurrence::AfterCompletion(3);
+        let done = at("2026-10-20T10:00:00Z");
+        assert_eq!(
+            rule.next_due(Some(at("2026-10-01T00:00:00Z")), done),
+            at("2026-10-23T10:00:00Z")
+        );
+    }
+
+    #[test]
+    fn cron_finds_next_weekday_slot() {
+        let rule: Recurrence = "cron:30 9 * * 1-5".parse().unwrap();
+        // Saturday 2026-10-24 -> Monday 2026-10-26 09:30.
+        let done = at("2026-10-24T12:00:00Z");
+        assert_eq!(rule.next_due(None, done), at("2026-10-26T09:30:00Z"));
+
+        let every_15: CronSchedule = "*/15 * * * *".parse().unwrap();
+        assert_eq!(
+            every_15.next_after(at("2026-10-24T12:07:00Z")),
+            Some(at("2026-10-24T12:15:00Z"))
+        );
+        let sundays: CronSchedule = "0 0 * * 7".parse().unwrap();
+        assert_eq!(
+            sundays.next_after(at("2026-10-24T12:00:00Z")),
+            Some(at("2026-10-25T00:00:00Z"))
+        );
+    }
+}
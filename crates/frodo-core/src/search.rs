@@ -0,0 +1,405 @@
+//! Full-text search over tasks: tokenizer, inverted index, BM25 ranking and
+//! snippet highlighting.
+//!
+//! The index is plain data; repositories persist it through the encrypted
+//! `SecureStore` like any other blob, so terms never reach disk unencrypted.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::tasks::Task;
+
+/// Bumped when tokenization or weighting changes; older indexes are rebuilt.
+pub const INDEX_VERSION: u32 = 1;
+
+const TITLE_WEIGHT: u32 = 3;
+const TAG_WEIGHT: u32 = 2;
+const DESCRIPTION_WEIGHT: u32 = 1;
+/// Score multiplier for terms matched by prefix (`auth` → `authentication`).
+const PREFIX_FACTOR: f64 = 0.5;
+const MIN_PREFIX_LEN: usize = 3;
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "in", "is", "it", "of", "on", "or",
+    "the", "to", "with",
+];
+
+/// Lowercased alphanumeric words, without stop words.
+pub fn tokenize(text: &str) -> Vec<String> {
+    words(text)
+        .map(|(_, word)| word.to_lowercase())
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Alphanumeric runs with their byte offsets.
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut start = None;
+    let mut out = Vec::new();
+    for (idx, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(idx),
+            (false, Some(s)) => {
+                out.push((s, &text[s..idx]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        out.push((s, &text[s..]));
+    }
+    out.into_iter()
+}
+
+/// Weighted term frequencies for one task. Titles count more than tags, tags
+/// more than descriptions. Tasks have no comments yet; once they do, they
+/// belong here too.
+pub fn document(task: &Task) -> BTreeMap<String, u32> {
+    let mut terms = BTreeMap::new();
+    let mut add = |text: &str, weight: u32| {
+        for term in tokenize(text) {
+            *terms.entry(term).or_insert(0) += weight;
+        }
+    };
+    add(&task.title, TITLE_WEIGHT);
+    for tag in &task.tags {
+        add(tag, TAG_WEIGHT);
+    }
+    if let Some(desc) = &task.description {
+        add(desc, DESCRIPTION_WEIGHT);
+    }
+    terms
+}
+
+/// One ranked result: the task id, its score and the index terms it matched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub id: Uuid,
+    pub score: f64,
+    pub terms: Vec<String>,
+}
+
+/// Inverted index from terms to tasks. Only per-task term counts are
+/// serialized; postings are rebuilt in memory on load.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "IndexData", into = "IndexData")]
+pub struct SearchIndex {
+    version: u32,
+    docs: BTreeMap<Uuid, BTreeMap<String, u32>>,
+    postings: BTreeMap<String, BTreeMap<Uuid, u32>>,
+    total_len: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexData {
+    version: u32,
+    docs: BTreeMap<Uuid, BTreeMap<String, u32>>,
+}
+
+impl From<IndexData> for SearchIndex {
+    fn from(data: IndexData) -> Self {
+        let mut index = SearchIndex {
+            version: data.version,
+            ..Default::default()
+        };
+        for (id, terms) in data.docs {
+            index.insert_doc(id, terms);
+        }
+        index
+    }
+}
+
+impl From<SearchIndex> for IndexData {
+    fn from(index: SearchIndex) -> Self {
+        IndexData {
+            version: index.version,
+            docs: index.docs,
+        }
+    }
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self {
+            version: INDEX_VERSION,
+            ..Default::default()
+        }
+    }
+
+    /// Build an index over `tasks` from scratch.
+    pub fn build<'a>(tasks: impl IntoIterator<Item = &'a Task>) -> Self {
+        let mut index = Self::new();
+        for task in tasks {
+            index.upsert(task);
+        }
+        index
+    }
+
+    /// False when the index was written by an older tokenizer and must be rebuilt.
+    pub fn is_current(&self) -> bool {
+        self.version == INDEX_VERSION
+    }
+
+    pub fn len(&self) -> usize {
+        self.docs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.docs.is_empty()
+    }
+
+    /// Index (or re-index) a task. Returns false when nothing changed.
+    pub fn upsert(&mut self, task: &Task) -> bool {
+        let terms = document(task);
+        if self.docs.get(&task.id) == Some(&terms) {
+            return false;
+        }
+        self.remove(task.id);
+        self.insert_doc(task.id, terms);
+        true
+    }
+
+    pub fn remove(&mut self, id: Uuid) -> bool {
+        let Some(terms) = self.docs.remove(&id) else {
+            return false;
+        };
+        for (term, tf) in terms {
+            self.total_len -= u64::from(tf);
+            if let Some(posting) = self.postings.get_mut(&term) {
+                posting.remove(&id);
+                if posting.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+        true
+    }
+
+    fn insert_doc(&mut self, id: Uuid, terms: BTreeMap<String, u32>) {
+        for (term, tf) in &terms {
+            self.total_len += u64::from(*tf);
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(id, *tf);
+        }
+        self.docs.insert(id, terms);
+    }
+
+    fn doc_len(&self, id: &Uuid) -> u64 {
+        self.docs
+            .get(id)
+            .map(|terms| terms.values().map(|tf| u64::from(*tf)).sum())
+            .unwrap_or(0)
+    }
+
+    /// Rank tasks matching any query term (BM25). Query words also match
+    /// longer index terms by prefix at a discount.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let n = self.docs.len() as f64;
+        if n == 0.0 {
+            return Vec::new();
+        }
+        let avg_len = self.total_len as f64 / n;
+        let mut scores: BTreeMap<Uuid, (f64, BTreeSet<String>)> = BTreeMap::new();
+
+        let query_terms: BTreeSet<String> = tokenize(query).into_iter().collect();
+        for query_term in &query_terms {
+            for (term, posting, factor) in self.expand(query_term) {
+                let df = posting.len() as f64;
+                let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+                for (id, tf) in posting {
+                    let tf = f64::from(*tf);
+                    let len = self.doc_len(id) as f64;
+                    let norm = tf * (BM25_K1 + 1.0)
+                        / (tf + BM25_K1 * (1.0 - BM25_B + BM25_B * len / avg_len));
+                    let entry = scores.entry(*id).or_default();
+                    entry.0 += factor * idf * norm;
+                    entry.1.insert(term.clone());
+                }
+            }
+        }
+
+        let mut hits: Vec<SearchHit> = scores
+            .into_iter()
+            .map(|(id, (score, terms))| SearchHit {
+                id,
+                score,
+                terms: terms.into_iter().collect(),
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+        hits.truncate(limit);
+        hits
+    }
+
+    /// Exact posting for `query_term` plus discounted prefix expansions.
+    fn expand<'a>(
+        &'a self,
+        query_term: &'a str,
+    ) -> impl Iterator<Item = (&'a String, &'a BTreeMap<Uuid, u32>, f64)> + 'a {
+        let prefix_ok = query_term.chars().count() >= MIN_PREFIX_LEN;
+        self.postings
+            .range(query_term.to_string()..)
+            .take_while(move |(term, _)| term.starts_with(query_term))
+            .filter_map(move |(term, posting)| {
+                if term == query_term {
+                    Some((term, posting, 1.0))
+                } else if prefix_ok {
+                    Some((term, posting, PREFIX_FACTOR))
+                } else {
+                    None
+                }
+            })
+    }
+}
+
+/// Excerpt of `text` around the first word equal to one of `terms` (index
+/// terms, lowercased), at most about `width` characters, with every matching
+/// word passed through `highlight`. `None` when no word matches.
+pub fn snippet(
+    text: &str,
+    terms: &[String],
+    width: usize,
+    highlight: impl Fn(&str) -> String,
+) -> Option<String> {
+    let is_match = |word: &str| terms.iter().any(|t| *t == word.to_lowercase());
+    let all: Vec<(usize, &str)> = words(text).collect();
+    let first = all.iter().position(|(_, word)| is_match(word))?;
+
+    // Start at the beginning when the first match fits, otherwise a few words
+    // before it; then fill up to `width` chars.
+    let (match_at, match_word) = all[first];
+    let start_word = if text[..match_at + match_word.len()].chars().count() <= width {
+        0
+    } else {
+        first.saturating_sub(3)
+    };
+    let start = all[start_word].0;
+    let mut end = start;
+    for (offset, word) in &all[start_word..] {
+        let word_end = offset + word.len();
+        if text[start..word_end].chars().count() > width && *offset > match_at {
+            break;
+        }
+        end = word_end;
+    }
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    let mut cursor = start;
+    for (offset, word) in all.iter().filter(|(o, _)| *o >= start && *o < end) {
+        out.push_str(&text[cursor..*offset]);
+        if is_match(word) {
+            out.push_str(&highlight(word));
+        } else {
+            out.push_str(word);
+        }
+        cursor = offset + word.len();
+    }
+    out.push_str(&text[cursor..end]);
+    if end < text.trim_end().len() {
+        out.push('…');
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(title: &str, description: Option<&str>, tags: &[&str]) -> Task {
+        Task::new(
+            title.into(),
+            description.map(str::to_string),
+            tags.iter().map(|t| t.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn tokenizes_words_and_drops_stop_words() {
+        assert_eq!(
+            tokenize("Fix the OAuth-login flow, v2!"),
+            vec!["fix", "oauth", "login", "flow", "v2"]
+        );
+    }
+
+    #[test]
+    fn ranks_title_matches_above_description_matches() {
+        let in_title = task("Database migration", None, &[]);
+        let in_desc = task("Cleanup", Some("run the database vacuum"), &[]);
+        let unrelated = task("Write docs", None, &[]);
+        let tasks = vec![in_desc.clone(), in_title.clone(), unrelated];
+        let index = SearchIndex::build(&tasks);
+
+        let hits = index.search("database", 10);
+        let ids: Vec<Uuid> = hits.iter().map(|h| h.id).collect();
+        assert_eq!(ids, vec![in_title.id, in_desc.id]);
+        assert_eq!(hits[0].terms, vec!["database"]);
+    }
+
+    #[test]
+    fn matches_prefixes_at_a_discount() {
+        let exact = task("auth bug", None, &[]);
+        let longer = task("authentication bug", None, &[]);
+        let index = SearchIndex::build([&exact, &longer]);
+
+        let hits = index.search("auth", 10);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].id, exact.id);
+        assert_eq!(hits[1].terms, vec!["authentication"]);
+        // Short words only match exactly.
+        assert!(index.search("au", 10).is_empty());
+    }
+
+    #[test]
+    fn upsert_and_remove_keep_postings_consistent() {
+        let mut t = task("alpha", None, &[]);
+        let mut index = SearchIndex::new();
+        assert!(index.upsert(&t));
+        assert!(!index.upsert(&t), "unchanged text is a no-op");
+
+        t.title = "beta".into();
+        assert!(index.upsert(&t));
+        assert!(index.search("alpha", 10).is_empty());
+        assert_eq!(index.search("beta", 10).len(), 1);
+
+        assert!(index.remove(t.id));
+        assert!(index.is_empty());
+        assert!(index.search("beta", 10).is_empty());
+    }
+
+    #[test]
+    fn serializes_without_postings_and_rebuilds_them() {
+        let t = task("Encrypted index", Some("never plaintext"), &["search"]);
+        let index = SearchIndex::build([&t]);
+        let json = serde_json::to_string(&index).unwrap();
+        assert!(!json.contains("postings"));
+
+        let back: SearchIndex = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, index);
+        assert_eq!(back.search("plaintext", 1)[0].id, t.id);
+    }
+
+    #[test]
+    fn snippets_highlight_matches_with_ellipses() {
+        let text =
+            "Before we ship, rotate the signing keys and update the release checklist for v2";
+        let terms = vec!["signing".to_string(), "release".to_string()];
+        let out = snippet(text, &terms, 40, |w| format!("[{w}]")).unwrap();
+        assert_eq!(out, "Before we ship, rotate the [signing] keys…");
+        let out = snippet(text, &terms, 20, |w| format!("[{w}]")).unwrap();
+        assert_eq!(out, "…ship, rotate the [signing]…");
+
+        let short = snippet("Release notes", &terms, 40, |w| format!("[{w}]")).unwrap();
+        assert_eq!(short, "[Release] notes");
+        assert!(snippet("nothing here", &terms, 40, |w| w.to_string()).is_none());
+    }
+}
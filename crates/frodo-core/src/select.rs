@@ -0,0 +1,220 @@
+//! Resolve what a user typed into a task: full UUID, UUID prefix, numeric
+//! index from the last listing, linked remote key (`PROJ-123`, `#42`), or a
+//! fuzzy title match.
+
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::tasks::Task;
+
+/// Shortest UUID prefix accepted, to avoid matching on one or two characters.
+pub const MIN_PREFIX_LEN: usize = 4;
+/// Length of the id prefix shown in listings.
+pub const SHORT_ID_LEN: usize = 8;
+
+/// Outcome of resolving a selector.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Selection<'a> {
+    One(&'a Task),
+    /// Several tasks match equally well; best candidates first.
+    Ambiguous(Vec<&'a Task>),
+}
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum SelectError {
+    #[error("no task matches `{0}`")]
+    NotFound(String),
+    #[error("{index} is not in the last listing ({len} tasks); run `frodo task list` again")]
+    IndexOutOfRange { index: usize, len: usize },
+}
+
+/// Short form of a task id for listings.
+pub fn short_id(id: Uuid) -> String {
+    id.to_string()[..SHORT_ID_LEN].to_string()
+}
+
+/// Resolve `input` against `tasks`. `recent` holds the ids of the last
+/// listing, in display order, for 1-based numeric selectors.
+pub fn resolve<'a>(
+    input: &str,
+    tasks: &'a [Task],
+    recent: &[Uuid],
+) -> Result<Selection<'a>, SelectError> {
+    let input = input.trim();
+    let not_found = || SelectError::NotFound(input.to_string());
+    let by_id = |id: Uuid| tasks.iter().find(|t| t.id == id);
+
+    if let Ok(id) = Uuid::parse_str(input) {
+        return by_id(id).map(Selection::One).ok_or_else(not_found);
+    }
+
+    // Small numbers refer to the last listing.
+    if !recent.is_empty() && input.len() <= 4 && input.chars().all(|c| c.is_ascii_digit()) {
+        let index: usize = input.parse().map_err(|_| not_found())?;
+        let id = index.checked_sub(1).and_then(|i| recent.get(i)).ok_or(
+            SelectError::IndexOutOfRange {
+                index,
+                len: recent.len(),
+            },
+        )?;
+        return by_id(*id).map(Selection::One).ok_or_else(not_found);
+    }
+
+    let remote: Vec<&Task> = tasks
+        .iter()
+        .filter(|t| {
+            t.remote_key
+                .as_deref()
+                .is_some_and(|key| key.eq_ignore_ascii_case(input))
+        })
+        .collect();
+    if let Some(selection) = pick(remote) {
+        return Ok(selection);
+    }
+
+    let lower = input.to_ascii_lowercase();
+    if lower.len() >= MIN_PREFIX_LEN && lower.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        let prefixed: Vec<&Task> = tasks
+            .iter()
+            .filter(|t| t.id.to_string().starts_with(&lower))
+            .collect();
+        if let Some(selection) = pick(prefixed) {
+            return Ok(selection);
+        }
+    }
+
+    fuzzy(input, tasks).ok_or_else(not_found)
+}
+
+fn pick(mut matches: Vec<&Task>) -> Option<Selection<'_>> {
+    match matches.len() {
+        0 => None,
+        1 => Some(Selection::One(matches.remove(0))),
+        _ => Some(Selection::Ambiguous(matches)),
+    }
+}
+
+/// Match score for a lowercased title; lower is better, `None` is no match.
+type Scorer<'s> = &'s dyn Fn(&str) -> Option<usize>;
+
+/// Title matching, strongest tier wins: exact title, then substring, then
+/// in-order characters (`fxlgn` → "Fix login"). Open tasks are preferred when
+/// a tier also contains done ones.
+fn fuzzy<'a>(input: &str, tasks: &'a [Task]) -> Option<Selection<'a>> {
+    let needle = input.to_lowercase();
+    if needle.is_empty() {
+        return None;
+    }
+    let tiers: [Scorer; 3] = [
+        &|title| (title == needle).then_some(0),
+        &|title| title.find(&needle),
+        &|title| subsequence_gaps(&needle, title),
+    ];
+    for tier in tiers {
+        let mut scored: Vec<(usize, &Task)> = tasks
+            .iter()
+            .filter_map(|t| tier(&t.title.to_lowercase()).map(|score| (score, t)))
+            .collect();
+        if scored.is_empty() {
+            continue;
+        }
+        let open: Vec<(usize, &Task)> = scored
+            .iter()
+            .copied()
+            .filter(|(_, t)| t.status != crate::tasks::TaskStatus::Done)
+            .collect();
+        if !open.is_empty() {
+            scored = open;
+        }
+        scored.sort_by_key(|(score, t)| (*score, t.title.len()));
+        return pick(scored.into_iter().map(|(_, t)| t).collect());
+    }
+    None
+}
+
+/// Characters skipped when matching `needle` as a subsequence of `haystack`,
+/// or `None` when it is not one.
+fn subsequence_gaps(needle: &str, haystack: &str) -> Option<usize> {
+    let mut gaps = 0;
+    let mut chars = haystack.chars();
+    for wanted in needle.chars() {
+        loop {
+            let c = chars.next()?;
+            if c == wanted {
+                break;
+            }
+            gaps += 1;
+        }
+    }
+    Some(gaps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tasks::TaskStatus;
+
+    fn tasks() -> Vec<Task> {
+        let mut fix = Task::new("Fix login".into(), None, vec![]);
+        fix.remote_key = Some("PROJ-123".into());
+        let mut docs = Task::new("Write docs".into(), None, vec![]);
+        docs.remote_key = Some("#42".into());
+        let mut old = Task::new("Fix logout".into(), None, vec![]);
+        old.status = TaskStatus::Done;
+        vec![fix, docs, old]
+    }
+
+    fn one<'a>(selection: Result<Selection<'a>, SelectError>) -> &'a Task {
+        match selection {
+            Ok(Selection::One(task)) => task,
+            other => panic!("expected one task, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn resolves_ids_prefixes_and_indexes() {
+        let list = tasks();
+        let id = list[1].id;
+        assert_eq!(one(resolve(&id.to_string(), &list, &[])).id, id);
+        assert_eq!(one(resolve(&short_id(id), &list, &[])).id, id);
+        assert_eq!(
+            one(resolve(&short_id(id).to_uppercase(), &list, &[])).id,
+            id
+        );
+
+        let recent = vec![list[2].id, list[0].id];
+        assert_eq!(one(resolve("2", &list, &recent)).id, list[0].id);
+        assert_eq!(
+            resolve("3", &list, &recent),
+            Err(SelectError::IndexOutOfRange { index: 3, len: 2 })
+        );
+    }
+
+    #[test]
+    fn resolves_remote_keys() {
+        let list = tasks();
+        assert_eq!(one(resolve("proj-123", &list, &[])).title, "Fix login");
+        assert_eq!(one(resolve("#42", &list, &[])).title, "Write docs");
+    }
+
+    #[test]
+    fn fuzzy_titles_prefer_open_and_report_ambiguity() {
+        let mut list = tasks();
+        // "fix log" matches both, but the done one is dropped.
+        assert_eq!(one(resolve("fix log", &list, &[])).title, "Fix login");
+        assert_eq!(one(resolve("wrdcs", &list, &[])).title, "Write docs");
+
+        list[2].status = TaskStatus::Todo;
+        match resolve("fix log", &list, &[]) {
+            Ok(Selection::Ambiguous(matches)) => {
+                let titles: Vec<&str> = matches.iter().map(|t| t.title.as_str()).collect();
+                assert_eq!(titles, vec!["Fix login", "Fix logout"]);
+            }
+            other => panic!("expected ambiguity, got {other:?}"),
+        }
+        assert_eq!(
+            resolve("nothing like it", &list, &[]),
+            Err(SelectError::NotFound("nothing like it".into()))
+        );
+    }
+}
@@ -1,3 +1,7 @@
+mod namespace;
 mod secure_store;
 
-pub use secure_store::{InMemorySecureStore, SecureStore, SecureStoreError};
+pub use namespace::Namespaced;
+pub use secure_store::{
+    Batch, BatchOp, InMemorySecureStore, SecureStore, SecureStoreError, Versioned,
+};
@@ -0,0 +1,179 @@
+use async_trait::async_trait;
+
+use super::{Batch, BatchOp, SecureStore, SecureStoreError, Versioned};
+
+/// A [`SecureStore`] view that prefixes every key with `name/`.
+///
+/// Keys passed in and returned from [`SecureStore::list`] are relative to the
+/// namespace, and errors report them the same way. Namespaces nest:
+/// `store.namespace("tasks").namespace("history")` lives under `tasks/history/`.
+#[derive(Debug, Clone)]
+pub struct Namespaced<S> {
+    inner: S,
+    prefix: String,
+}
+
+impl<S: SecureStore> Namespaced<S> {
+    /// Wrap `inner`; trailing slashes on `name` are ignored.
+    pub fn new(inner: S, name: &str) -> Self {
+        let name = name.trim_end_matches('/');
+        debug_assert!(!name.is_empty(), "namespace name must not be empty");
+        Self {
+            inner,
+            prefix: format!("{name}/"),
+        }
+    }
+
+    /// The full key prefix, including the trailing slash.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The wrapped store.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn full(&self, key: &str) -> String {
+        format!("{}{key}", self.prefix)
+    }
+
+    /// Rewrite keys in errors from the inner store back to relative form.
+    fn relative(&self, err: SecureStoreError) -> SecureStoreError {
+        let strip = |key: String| match key.strip_prefix(&self.prefix) {
+            Some(rest) => rest.to_string(),
+            None => key,
+        };
+        match err {
+            SecureStoreError::NotFound { key } => SecureStoreError::NotFound { key: strip(key) },
+            SecureStoreError::Conflict {
+                key,
+                expected,
+                actual,
+            } => SecureStoreError::Conflict {
+                key: strip(key),
+                expected,
+                actual,
+            },
+            other => other,
+        }
+    }
+}
+
+#[async_trait]
+impl<S: SecureStore> SecureStore for Namespaced<S> {
+    async fn put(&self, key: &str, value: &[u8]) -> Result<(), SecureStoreError> {
+        self.inner
+            .put(&self.full(key), value)
+            .await
+            .map_err(|e| self.relative(e))
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, SecureStoreError> {
+        self.inner
+            .get(&self.full(key))
+            .await
+            .map_err(|e| self.relative(e))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), SecureStoreError> {
+        self.inner
+            .delete(&self.full(key))
+            .await
+            .map_err(|e| self.relative(e))
+    }
+
+    async fn get_versioned(&self, key: &str) -> Result<Versioned, SecureStoreError> {
+        self.inner
+            .get_versioned(&self.full(key))
+            .await
+            .map_err(|e| self.relative(e))
+    }
+
+    async fn put_if(
+        &self,
+        key: &str,
+        value: &[u8],
+        expected: Option<u64>,
+    ) -> Result<u64, SecureStoreError> {
+        self.inner
+            .put_if(&self.full(key), value, expected)
+            .await
+            .map_err(|e| self.relative(e))
+    }
+
+    async fn commit(&self, batch: Batch) -> Result<(), SecureStoreError> {
+        let mut scoped = Batch::new();
+        for op in batch.into_ops() {
+            match op {
+                BatchOp::Put {
+                    key,
+                    value,
+                    expected: None,
+                } => scoped.put(self.full(&key), value),
+                BatchOp::Put {
+                    key,
+                    value,
+                    expected: Some(expected),
+                } => scoped.put_if(self.full(&key), value, expected),
+                BatchOp::Delete { key } => scoped.delete(self.full(&key)),
+            };
+        }
+        self.inner
+            .commit(scoped)
+            .await
+            .map_err(|e| self.relative(e))
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, SecureStoreError> {
+        let keys = self.inner.list(&self.full(prefix)).await?;
+        Ok(keys
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&self.prefix).map(str::to_string))
+            .collect())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, SecureStoreError> {
+        self.inner.exists(&self.full(key)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemorySecureStore;
+
+    #[tokio::test]
+    async fn namespaces_isolate_keys_and_nest() {
+        let store = InMemorySecureStore::new();
+        store.put("other", b"x").await.unwrap();
+
+        let tasks = store.namespace("tasks");
+        tasks.put("index", b"i").await.unwrap();
+        tasks.namespace("history").put("a", b"h").await.unwrap();
+
+        assert_eq!(store.get("tasks/index").await.unwrap(), b"i");
+        assert!(store.exists("tasks/history/a").await.unwrap());
+        assert_eq!(tasks.list("").await.unwrap(), ["history/a", "index"]);
+        assert_eq!(tasks.namespace("history/").list("").await.unwrap(), ["a"]);
+        assert!(!tasks.exists("other").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn errors_report_relative_keys() {
+        let store = InMemorySecureStore::new();
+        let ns = store.namespace("cli");
+
+        let err = ns.get("missing").await.expect_err("absent");
+        assert_eq!(
+            err,
+            SecureStoreError::NotFound {
+                key: "missing".into()
+            }
+        );
+
+        ns.put_if("k", b"v", None).await.unwrap();
+        let err = ns.put_if("k", b"v", None).await.expect_err("exists");
+        assert!(matches!(err, SecureStoreError::Conflict { key, .. } if key == "k"));
+    }
+}
@@ -6,6 +6,8 @@ use std::{
 use async_trait::async_trait;
 use thiserror::Error;
 
+use super::Namespaced;
+
 /// Errors produced by secure storage implementations.
 #[derive(Debug, Error, PartialEq, Eq)]
 pub enum SecureStoreError {
@@ -15,6 +17,98 @@ pub enum SecureStoreError {
     /// Underlying storage failure.
     #[error("storage failure: {reason}")]
     Storage { reason: String },
+    /// A conditional write lost a race: the stored revision no longer matches.
+    #[error("revision conflict for key {key}: expected {expected:?}, found {actual:?}")]
+    Conflict {
+        key: String,
+        expected: Option<u64>,
+        actual: Option<u64>,
+    },
+}
+
+/// A stored value together with its revision (bumped on every write).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Versioned {
+    pub value: Vec<u8>,
+    pub revision: u64,
+}
+
+/// One write in a [`Batch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOp {
+    /// Store `value`; with `expected` set, only if the key's revision still
+    /// matches it (`Some(None)`: the key must not exist yet).
+    Put {
+        key: String,
+        value: Vec<u8>,
+        expected: Option<Option<u64>>,
+    },
+    /// Remove the key (a no-op if it is absent).
+    Delete { key: String },
+}
+
+impl BatchOp {
+    pub fn key(&self) -> &str {
+        match self {
+            BatchOp::Put { key, .. } | BatchOp::Delete { key } => key,
+        }
+    }
+}
+
+/// Writes to several keys that [`SecureStore::commit`] applies all together
+/// or not at all, e.g. a task, the index listing it and its history entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Batch {
+    ops: Vec<BatchOp>,
+}
+
+impl Batch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overwrite `key` unconditionally.
+    pub fn put(&mut self, key: impl Into<String>, value: impl Into<Vec<u8>>) -> &mut Self {
+        self.ops.push(BatchOp::Put {
+            key: key.into(),
+            value: value.into(),
+            expected: None,
+        });
+        self
+    }
+
+    /// Write `key` only if its revision is still `expected` (see
+    /// [`SecureStore::put_if`]); otherwise the whole batch fails.
+    pub fn put_if(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<Vec<u8>>,
+        expected: Option<u64>,
+    ) -> &mut Self {
+        self.ops.push(BatchOp::Put {
+            key: key.into(),
+            value: value.into(),
+            expected: Some(expected),
+        });
+        self
+    }
+
+    pub fn delete(&mut self, key: impl Into<String>) -> &mut Self {
+        self.ops.push(BatchOp::Delete { key: key.into() });
+        self
+    }
+
+    pub fn ops(&self) -> &[BatchOp] {
+        &self.ops
+    }
+
+    pub fn into_ops(self) -> Vec<BatchOp> {
+        self.ops
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
 }
 
 /// Simple contract for encrypted-at-rest storage used by agents, tasks, and conversations.
@@ -28,6 +122,80 @@ pub trait SecureStore: Send + Sync {
 
     /// Remove a key and its value (idempotent).
     async fn delete(&self, key: &str) -> Result<(), SecureStoreError>;
+
+    /// Retrieve the value for a key along with its current revision.
+    async fn get_versioned(&self, key: &str) -> Result<Versioned, SecureStoreError>;
+
+    /// Write only if the stored revision still equals `expected` (`None` means the
+    /// key must not exist yet). Returns the new revision, or
+    /// [`SecureStoreError::Conflict`] when another writer got there first.
+    async fn put_if(
+        &self,
+        key: &str,
+        value: &[u8],
+        expected: Option<u64>,
+    ) -> Result<u64, SecureStoreError>;
+
+    /// Apply every write in `batch` atomically: revision checks all see the
+    /// store as it was before the batch, and if any fails nothing is written
+    /// and [`SecureStoreError::Conflict`] names the key. Later writes to the
+    /// same key win.
+    async fn commit(&self, batch: Batch) -> Result<(), SecureStoreError>;
+
+    /// Keys starting with `prefix` (all keys for `""`), sorted.
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, SecureStoreError>;
+
+    /// Whether a value is stored under `key`, without decrypting it.
+    async fn exists(&self, key: &str) -> Result<bool, SecureStoreError>;
+
+    /// View of this store that keeps every key under `name/`, e.g.
+    /// `store.namespace("tasks")` reads and writes `tasks/<key>`.
+    fn namespace(&self, name: &str) -> Namespaced<&Self>
+    where
+        Self: Sized,
+    {
+        Namespaced::new(self, name)
+    }
+}
+
+#[async_trait]
+impl<S: SecureStore + ?Sized> SecureStore for &S {
+    async fn put(&self, key: &str, value: &[u8]) -> Result<(), SecureStoreError> {
+        (**self).put(key, value).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, SecureStoreError> {
+        (**self).get(key).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), SecureStoreError> {
+        (**self).delete(key).await
+    }
+
+    async fn get_versioned(&self, key: &str) -> Result<Versioned, SecureStoreError> {
+        (**self).get_versioned(key).await
+    }
+
+    async fn put_if(
+        &self,
+        key: &str,
+        value: &[u8],
+        expected: Option<u64>,
+    ) -> Result<u64, SecureStoreError> {
+        (**self).put_if(key, value, expected).await
+    }
+
+    async fn commit(&self, batch: Batch) -> Result<(), SecureStoreError> {
+        (**self).commit(batch).await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, SecureStoreError> {
+        (**self).list(prefix).await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, SecureStoreError> {
+        (**self).exists(key).await
+    }
 }
 
 /// In-memory secure store that simulates encryption for tests and smoke runs.
@@ -35,49 +203,133 @@ pub trait SecureStore: Send + Sync {
 /// SQLCipher or AES-GCM with keys wrapped by the OS keychain.
 #[derive(Debug, Default, Clone)]
 pub struct InMemorySecureStore {
-    inner: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    inner: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    revision: u64,
+    masked: Vec<u8>,
 }
 
 impl InMemorySecureStore {
     pub fn new() -> Self {
         Self::default()
     }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, HashMap<String, Entry>>, SecureStoreError> {
+        self.inner.lock().map_err(|err| SecureStoreError::Storage {
+            reason: format!("lock poisoned: {err}"),
+        })
+    }
 }
 
 #[async_trait]
 impl SecureStore for InMemorySecureStore {
     async fn put(&self, key: &str, value: &[u8]) -> Result<(), SecureStoreError> {
-        let mut map = self.inner.lock().map_err(|err| SecureStoreError::Storage {
-            reason: format!("lock poisoned: {err}"),
-        })?;
-
+        let mut map = self.lock()?;
+        let revision = map.get(key).map_or(0, |e| e.revision) + 1;
         // XOR is a placeholder to avoid storing plaintext in tests.
         let masked = mask(value);
-        map.insert(key.to_string(), masked);
+        map.insert(key.to_string(), Entry { revision, masked });
         Ok(())
     }
 
     async fn get(&self, key: &str) -> Result<Vec<u8>, SecureStoreError> {
-        let map = self.inner.lock().map_err(|err| SecureStoreError::Storage {
-            reason: format!("lock poisoned: {err}"),
+        self.get_versioned(key).await.map(|v| v.value)
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), SecureStoreError> {
+        let mut map = self.lock()?;
+        map.remove(key);
+        Ok(())
+    }
+
+    async fn get_versioned(&self, key: &str) -> Result<Versioned, SecureStoreError> {
+        let map = self.lock()?;
+        let entry = map.get(key).ok_or_else(|| SecureStoreError::NotFound {
+            key: key.to_string(),
         })?;
+        Ok(Versioned {
+            value: unmask(&entry.masked),
+            revision: entry.revision,
+        })
+    }
 
-        let masked = map
-            .get(key)
-            .cloned()
-            .ok_or_else(|| SecureStoreError::NotFound {
+    async fn put_if(
+        &self,
+        key: &str,
+        value: &[u8],
+        expected: Option<u64>,
+    ) -> Result<u64, SecureStoreError> {
+        let mut map = self.lock()?;
+        let actual = map.get(key).map(|e| e.revision);
+        if actual != expected {
+            return Err(SecureStoreError::Conflict {
                 key: key.to_string(),
-            })?;
-        Ok(unmask(&masked))
+                expected,
+                actual,
+            });
+        }
+        let revision = actual.unwrap_or(0) + 1;
+        map.insert(
+            key.to_string(),
+            Entry {
+                revision,
+                masked: mask(value),
+            },
+        );
+        Ok(revision)
     }
 
-    async fn delete(&self, key: &str) -> Result<(), SecureStoreError> {
-        let mut map = self.inner.lock().map_err(|err| SecureStoreError::Storage {
-            reason: format!("lock poisoned: {err}"),
-        })?;
-        map.remove(key);
+    async fn commit(&self, batch: Batch) -> Result<(), SecureStoreError> {
+        let mut map = self.lock()?;
+        for op in batch.ops() {
+            if let BatchOp::Put {
+                key,
+                expected: Some(expected),
+                ..
+            } = op
+            {
+                let actual = map.get(key).map(|e| e.revision);
+                if actual != *expected {
+                    return Err(SecureStoreError::Conflict {
+                        key: key.clone(),
+                        expected: *expected,
+                        actual,
+                    });
+                }
+            }
+        }
+        for op in batch.into_ops() {
+            match op {
+                BatchOp::Put { key, value, .. } => {
+                    let revision = map.get(&key).map_or(0, |e| e.revision) + 1;
+                    let masked = mask(&value);
+                    map.insert(key, Entry { revision, masked });
+                }
+                BatchOp::Delete { key } => {
+                    map.remove(&key);
+                }
+            }
+        }
         Ok(())
     }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, SecureStoreError> {
+        let map = self.lock()?;
+        let mut keys: Vec<String> = map
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, SecureStoreError> {
+        Ok(self.lock()?.contains_key(key))
+    }
 }
 
 const MASK_BYTE: u8 = 0xA5;
@@ -106,7 +358,34 @@ mod tests {
         assert_eq!(retrieved, secret);
         // Ensure we are not storing plaintext.
         let inner = store.inner.lock().expect("lock");
-        assert_ne!(inner.get(key).unwrap(), &secret.to_vec());
+        assert_ne!(inner.get(key).unwrap().masked, secret.to_vec());
+    }
+
+    #[tokio::test]
+    async fn put_if_enforces_expected_revision() {
+        let store = InMemorySecureStore::new();
+        let first = store.put_if("k", b"v1", None).await.expect("create");
+        assert_eq!(first, 1);
+
+        let err = store
+            .put_if("k", b"again", None)
+            .await
+            .expect_err("key already exists");
+        assert_eq!(
+            err,
+            SecureStoreError::Conflict {
+                key: "k".into(),
+                expected: None,
+                actual: Some(1)
+            }
+        );
+
+        store.put("k", b"v2").await.expect("unconditional put");
+        let current = store.get_versioned("k").await.expect("get");
+        assert_eq!(current.revision, 2);
+        assert!(store.put_if("k", b"stale", Some(1)).await.is_err());
+        assert_eq!(store.put_if("k", b"v3", Some(2)).await.expect("cas"), 3);
+        assert_eq!(store.get("k").await.unwrap(), b"v3");
     }
 
     #[tokio::test]
@@ -125,4 +404,45 @@ mod tests {
             .expect_err("get should fail after delete");
         assert!(matches!(err, SecureStoreError::NotFound { .. }));
     }
+
+    #[tokio::test]
+    async fn list_filters_by_prefix_and_exists_tracks_deletes() {
+        let store = InMemorySecureStore::new();
+        for key in ["tasks/b", "tasks/a", "cli/last-list", "tasksx"] {
+            store.put(key, b"v").await.expect("put");
+        }
+
+        assert_eq!(store.list("tasks/").await.unwrap(), ["tasks/a", "tasks/b"]);
+        assert_eq!(store.list("").await.unwrap().len(), 4);
+        assert!(store.exists("tasks/a").await.unwrap());
+
+        store.delete("tasks/a").await.expect("delete");
+        assert!(!store.exists("tasks/a").await.unwrap());
+        assert_eq!(store.list("tasks/").await.unwrap(), ["tasks/b"]);
+    }
+
+    #[tokio::test]
+    async fn commit_is_all_or_nothing() {
+        let store = InMemorySecureStore::new();
+        store.put("index", b"v1").await.expect("put");
+        store.put("gone", b"x").await.expect("put");
+
+        let mut stale = Batch::new();
+        stale
+            .put("task", b"t".to_vec())
+            .put_if("index", b"v2".to_vec(), Some(7));
+        let err = store.commit(stale).await.expect_err("stale revision");
+        assert!(matches!(err, SecureStoreError::Conflict { key, .. } if key == "index"));
+        assert!(!store.exists("task").await.unwrap());
+
+        let mut batch = Batch::new();
+        batch
+            .put("task", b"t".to_vec())
+            .put_if("index", b"v2".to_vec(), Some(1))
+            .delete("gone");
+        store.commit(batch).await.expect("commit");
+        assert_eq!(store.get_versioned("index").await.unwrap().revision, 2);
+        assert_eq!(store.get("task").await.unwrap(), b"t");
+        assert!(!store.exists("gone").await.unwrap());
+    }
 }
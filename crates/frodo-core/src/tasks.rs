@@ -3,6 +3,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::{history::TaskEvent, recurrence::Recurrence, timetrack::TimeEntry};
+
 /// Task status lifecycle.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub enum TaskStatus {
@@ -12,6 +14,56 @@ pub enum TaskStatus {
     Done,
 }
 
+/// Task priority, ordered from least to most pressing.
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    /// Lowercase label used by the CLI and scoring reasons.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Urgent => "urgent",
+        }
+    }
+
+    /// Zero-based rank (low = 0, urgent = 3) used for scoring.
+    pub fn rank(&self) -> i64 {
+        match self {
+            Priority::Low => 0,
+            Priority::Medium => 1,
+            Priority::High => 2,
+            Priority::Urgent => 3,
+        }
+    }
+}
+
+impl std::str::FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "low" | "l" => Ok(Priority::Low),
+            "medium" | "med" | "m" => Ok(Priority::Medium),
+            "high" | "h" => Ok(Priority::High),
+            "urgent" | "u" => Ok(Priority::Urgent),
+            other => Err(format!(
+                "unknown priority `{other}` (expected low, medium, high or urgent)"
+            )),
+        }
+    }
+}
+
 /// Task entity.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Task {
@@ -20,6 +72,33 @@ pub struct Task {
     pub description: Option<String>,
     pub tags: Vec<String>,
     pub status: TaskStatus,
+    #[serde(default)]
+    pub priority: Priority,
+    /// Optional deadline.
+    #[serde(default)]
+    pub due_at: Option<DateTime<Utc>>,
+    /// Parent task when this is a subtask.
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
+    /// Tasks that must be finished before this one can start.
+    #[serde(default)]
+    pub blocked_by: Vec<Uuid>,
+    /// Repeat rule; completing the task spawns the next occurrence.
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    /// Tracked time; at most one entry (across all tasks) is running.
+    #[serde(default)]
+    pub time_entries: Vec<TimeEntry>,
+    /// Key of the linked issue in a remote tracker (`PROJ-123`, `#42`).
+    #[serde(default)]
+    pub remote_key: Option<String>,
+    /// Workspace (project) the task belongs to; `None` for personal tasks
+    /// that only show up outside workspaces or with `--all`.
+    #[serde(default)]
+    pub workspace: Option<String>,
+    /// Git branch created for the task with `frodo task branch`.
+    #[serde(default)]
+    pub branch: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -33,10 +112,46 @@ impl Task {
             description,
             tags,
             status: TaskStatus::Todo,
+            priority: Priority::default(),
+            due_at: None,
+            parent_id: None,
+            blocked_by: Vec::new(),
+            recurrence: None,
+            time_entries: Vec::new(),
+            remote_key: None,
+            workspace: None,
+            branch: None,
             created_at: now,
             updated_at: now,
         }
     }
+
+    /// Fresh copy of a recurring task for its next occurrence, due per its rule.
+    /// Returns `None` for tasks without a recurrence.
+    pub fn next_occurrence(&self, completed_at: DateTime<Utc>) -> Option<Task> {
+        let rule = self.recurrence.as_ref()?;
+        let mut next = Task::new(
+            self.title.clone(),
+            self.description.clone(),
+            self.tags.clone(),
+        );
+        next.priority = self.priority;
+        next.parent_id = self.parent_id;
+        next.workspace = self.workspace.clone();
+        next.recurrence = Some(rule.anchored(self.due_at.unwrap_or(completed_at)));
+        next.due_at = Some(rule.next_due(self.due_at, completed_at));
+        next.created_at = completed_at;
+        next.updated_at = completed_at;
+        Some(next)
+    }
+}
+
+/// Returned by repositories when a task was changed by another writer in a way
+/// the caller has not seen. Reload the task and try again.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("task {id} was modified by another process; reload and try again")]
+pub struct TaskConflict {
+    pub id: Uuid,
 }
 
 /// Simple repository contract for task persistence.
@@ -49,5 +164,31 @@ pub trait TaskRepository: Send + Sync {
         description: Option<String>,
         tags: Vec<String>,
     ) -> anyhow::Result<Task>;
+    /// Store a task the caller built in full (fields, parent, blockers) as a
+    /// single operation, so one undo removes it. Its parent and blockers must
+    /// be existing tasks.
+    async fn add(&self, task: Task) -> anyhow::Result<Task>;
+    /// Change a task's status. Completing a task stops its timer; completing a
+    /// recurring task also creates its next occurrence.
     async fn set_status(&self, id: Uuid, status: TaskStatus) -> anyhow::Result<Task>;
+    /// Replace a stored task with the given one (matched by id). Fails with
+    /// [`TaskConflict`] if the stored copy changed since `task` was read.
+    async fn update(&self, task: Task) -> anyhow::Result<Task>;
+    /// Move a task under `parent` (or to the top level with `None`), rejecting cycles.
+    async fn set_parent(&self, id: Uuid, parent: Option<Uuid>) -> anyhow::Result<Task>;
+    /// Record that `id` cannot start until `blocked_by` is done, rejecting cycles.
+    async fn add_dependency(&self, id: Uuid, blocked_by: Uuid) -> anyhow::Result<Task>;
+    /// Remove a "blocked by" edge (idempotent).
+    async fn remove_dependency(&self, id: Uuid, blocked_by: Uuid) -> anyhow::Result<Task>;
+    /// Start a timer on a task and mark it in progress. Fails with
+    /// [`TimerError::AlreadyRunning`](crate::timetrack::TimerError) while
+    /// another timer runs.
+    async fn start_timer(&self, id: Uuid) -> anyhow::Result<Task>;
+    /// Stop the running timer, returning the task it was on (`None` if idle).
+    async fn stop_timer(&self) -> anyhow::Result<Option<Task>>;
+    /// Recorded changes to one task, oldest first.
+    async fn history(&self, id: Uuid) -> anyhow::Result<Vec<TaskEvent>>;
+    /// Revert the last `count` operations, newest first. Returns the events
+    /// recording each revert; fewer than `count` when history runs out.
+    async fn undo(&self, count: usize) -> anyhow::Result<Vec<TaskEvent>>;
 }
@@ -0,0 +1,263 @@
+//! Time tracking: timer entries recorded on tasks and aggregated reports.
+
+use std::{collections::BTreeMap, str::FromStr};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::tasks::Task;
+
+/// One tracked interval. `ended_at` is `None` while the timer is running.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+impl TimeEntry {
+    pub fn start(at: DateTime<Utc>) -> Self {
+        Self {
+            started_at: at,
+            ended_at: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.ended_at.is_none()
+    }
+
+    /// Portion of this entry inside `[from, to)`; running entries end at `to`.
+    pub fn overlap(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Duration {
+        let start = self.started_at.max(from);
+        let end = self.ended_at.unwrap_or(to).min(to);
+        (end - start).max(Duration::zero())
+    }
+}
+
+/// Errors from starting or stopping timers.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum TimerError {
+    /// Only one timer may run at a time.
+    #[error("a timer is already running on {title} ({task_id}); stop it first")]
+    AlreadyRunning { task_id: Uuid, title: String },
+}
+
+impl Task {
+    /// The running time entry, if a timer is active on this task.
+    pub fn running_entry(&self) -> Option<&TimeEntry> {
+        self.time_entries.iter().find(|e| e.is_running())
+    }
+
+    /// Close every running entry at `at`. Returns whether any was running.
+    pub fn stop_timer(&mut self, at: DateTime<Utc>) -> bool {
+        let mut stopped = false;
+        for entry in self.time_entries.iter_mut().filter(|e| e.is_running()) {
+            entry.ended_at = Some(at.max(entry.started_at));
+            stopped = true;
+        }
+        stopped
+    }
+
+    /// Total tracked time, counting a running timer up to `now`.
+    pub fn tracked(&self, now: DateTime<Utc>) -> Duration {
+        self.time_entries
+            .iter()
+            .map(|e| e.overlap(DateTime::<Utc>::MIN_UTC, now))
+            .fold(Duration::zero(), |acc, d| acc + d)
+    }
+}
+
+/// How `report` groups tracked time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupBy {
+    #[default]
+    Task,
+    /// Per tag; time on a task with several tags counts towards each of them.
+    Tag,
+    /// Per UTC calendar day the time was tracked on.
+    Day,
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "task" => Ok(GroupBy::Task),
+            "tag" => Ok(GroupBy::Tag),
+            "day" => Ok(GroupBy::Day),
+            other => Err(format!(
+                "unknown grouping `{other}` (expected task, tag or day)"
+            )),
+        }
+    }
+}
+
+/// One report line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportRow {
+    pub key: String,
+    pub duration: Duration,
+}
+
+/// Tracked time in `[since, until)`, grouped and sorted longest first.
+/// Groups with no time are omitted.
+pub fn report(
+    tasks: &[Task],
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    by: GroupBy,
+) -> Vec<ReportRow> {
+    let mut totals: BTreeMap<String, Duration> = BTreeMap::new();
+    let mut add = |key: String, duration: Duration| {
+        if duration > Duration::zero() {
+            *totals.entry(key).or_insert_with(Duration::zero) += duration;
+        }
+    };
+    for task in tasks {
+        for entry in &task.time_entries {
+            match by {
+                GroupBy::Task => add(task.title.clone(), entry.overlap(since, until)),
+                GroupBy::Tag if task.tags.is_empty() => {
+                    add("(untagged)".into(), entry.overlap(since, until))
+                }
+                GroupBy::Tag => {
+                    for tag in &task.tags {
+                        add(tag.clone(), entry.overlap(since, until));
+                    }
+                }
+                GroupBy::Day => {
+                    // Split entries that cross midnight.
+                    let mut day = since.max(entry.started_at).date_naive();
+                    let end = entry.ended_at.unwrap_or(until).min(until);
+                    while let Some(start) = day.and_hms_opt(0, 0, 0).map(|t| t.and_utc()) {
+                        if start >= end {
+                            break;
+                        }
+                        let next = start + Duration::days(1);
+                        add(
+                            day.format("%Y-%m-%d").to_string(),
+                            entry.overlap(start.max(since), next.min(until)),
+                        );
+                        day = next.date_naive();
+                    }
+                }
+            }
+        }
+    }
+
+    let mut rows: Vec<ReportRow> = totals
+        .into_iter()
+        .map(|(key, duration)| ReportRow { key, duration })
+        .collect();
+    if by != GroupBy::Day {
+        rows.sort_by(|a, b| b.duration.cmp(&a.duration).then(a.key.cmp(&b.key)));
+    }
+    rows
+}
+
+/// `1h 05m` style duration, with seconds only for very short spans.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.num_seconds().max(0);
+    match (secs / 3600, (secs % 3600) / 60) {
+        (0, 0) => format!("{secs}s"),
+        (0, m) => format!("{m}m"),
+        (h, m) => format!("{h}h {m:02}m"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, day, hour, 0, 0).unwrap()
+    }
+
+    fn tracked(title: &str, tags: &[&str], entries: &[(DateTime<Utc>, DateTime<Utc>)]) -> Task {
+        let mut task = Task::new(
+            title.into(),
+            None,
+            tags.iter().map(|t| t.to_string()).collect(),
+        );
+        task.time_entries = entries
+            .iter()
+            .map(|(start, end)| TimeEntry {
+                started_at: *start,
+                ended_at: Some(*end),
+            })
+            .collect();
+        task
+    }
+
+    #[test]
+    fn running_entries_count_up_to_now() {
+        let mut task = Task::new("t".into(), None, vec![]);
+        task.time_entries.push(TimeEntry::start(at(12, 9)));
+        assert_eq!(task.tracked(at(12, 11)), Duration::hours(2));
+        assert!(task.running_entry().is_some());
+
+        assert!(task.stop_timer(at(12, 10)));
+        assert!(!task.stop_timer(at(12, 12)));
+        assert_eq!(task.tracked(at(12, 23)), Duration::hours(1));
+    }
+
+    #[test]
+    fn reports_by_tag_within_window() {
+        let tasks = vec![
+            tracked("api", &["backend", "billing"], &[(at(12, 9), at(12, 11))]),
+            tracked("ui", &["frontend"], &[(at(12, 13), at(12, 14))]),
+            tracked("old", &["backend"], &[(at(5, 9), at(5, 17))]),
+            tracked(
+                "misc",
+                &[],
+                &[(at(12, 15), at(12, 15) + Duration::minutes(30))],
+            ),
+        ];
+
+        let rows = report(&tasks, at(12, 0), at(13, 0), GroupBy::Tag);
+        let summary: Vec<(&str, i64)> = rows
+            .iter()
+            .map(|r| (r.key.as_str(), r.duration.num_minutes()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("backend", 120),
+                ("billing", 120),
+                ("frontend", 60),
+                ("(untagged)", 30)
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_by_day_split_at_midnight() {
+        let tasks = vec![tracked("late", &[], &[(at(12, 22), at(13, 2))])];
+        let rows = report(&tasks, at(1, 0), at(20, 0), GroupBy::Day);
+        assert_eq!(
+            rows,
+            vec![
+                ReportRow {
+                    key: "2026-10-12".into(),
+                    duration: Duration::hours(2)
+                },
+                ReportRow {
+                    key: "2026-10-13".into(),
+                    duration: Duration::hours(2)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(Duration::seconds(42)), "42s");
+        assert_eq!(format_duration(Duration::minutes(5)), "5m");
+        assert_eq!(format_duration(Duration::minutes(65)), "1h 05m");
+    }
+}
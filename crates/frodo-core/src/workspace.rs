@@ -0,0 +1,255 @@
+//! Workspaces: named projects that group tasks, usually one per git repo.
+//!
+//! The active workspace is the configured one whose `paths` contain the
+//! current directory or, failing that, an implicit workspace for the
+//! enclosing git repository. Commands only see tasks of the active workspace
+//! unless asked for everything (`Scope::All`).
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::tasks::Task;
+
+/// A named project with the repositories it covers and where it syncs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Workspace {
+    pub name: String,
+    /// Repository or project directories; subdirectories belong to the workspace too.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<PathBuf>,
+    /// GitHub repository (`owner/repo`) that issues sync with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github: Option<String>,
+    /// Jira project key that issues sync with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jira_project: Option<String>,
+}
+
+impl Workspace {
+    /// Length of the longest workspace path containing `dir`, used to pick
+    /// the most specific of nested workspaces.
+    fn depth(&self, dir: &Path) -> Option<usize> {
+        self.paths
+            .iter()
+            .filter(|path| dir.starts_with(path))
+            .map(|path| path.components().count())
+            .max()
+    }
+}
+
+/// Which tasks a command works on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Scope {
+    /// Every task (`--all`, or no workspace is active).
+    #[default]
+    All,
+    /// Only tasks assigned to this workspace.
+    Workspace(Workspace),
+}
+
+impl Scope {
+    /// Scope for `cwd`: the detected workspace, or everything when `all` is
+    /// set or the directory belongs to no workspace.
+    pub fn resolve(cwd: &Path, configured: &[Workspace], all: bool) -> Self {
+        if all {
+            return Scope::All;
+        }
+        detect(cwd, configured).map_or(Scope::All, Scope::Workspace)
+    }
+
+    /// The active workspace, if scoped.
+    pub fn workspace(&self) -> Option<&Workspace> {
+        match self {
+            Scope::All => None,
+            Scope::Workspace(workspace) => Some(workspace),
+        }
+    }
+
+    /// Name of the active workspace, if scoped.
+    pub fn name(&self) -> Option<&str> {
+        self.workspace().map(|w| w.name.as_str())
+    }
+
+    /// Whether `task` is visible in this scope.
+    pub fn includes(&self, task: &Task) -> bool {
+        match self {
+            Scope::All => true,
+            Scope::Workspace(workspace) => task.workspace.as_deref() == Some(&workspace.name),
+        }
+    }
+
+    /// Keep only the tasks visible in this scope.
+    pub fn filter(&self, mut tasks: Vec<Task>) -> Vec<Task> {
+        tasks.retain(|task| self.includes(task));
+        tasks
+    }
+}
+
+/// Find the active workspace for `cwd`. A configured workspace covering the
+/// directory wins (the most specific one if they nest); otherwise the
+/// enclosing git repository becomes an implicit workspace named after its
+/// directory, with its GitHub `origin` as sync target.
+pub fn detect(cwd: &Path, configured: &[Workspace]) -> Option<Workspace> {
+    if let Some(found) = configured
+        .iter()
+        .filter_map(|w| w.depth(cwd).map(|depth| (depth, w)))
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, w)| w.clone())
+    {
+        return Some(found);
+    }
+
+    let root = repo_root(cwd)?;
+    // A configured workspace may list the repository under another spelling
+    // of the same path (symlinks, `..`).
+    if let Ok(canonical) = root.canonicalize() {
+        if let Some(found) = configured.iter().find(|w| {
+            w.paths
+                .iter()
+                .any(|p| p.canonicalize().is_ok_and(|p| p == canonical))
+        }) {
+            return Some(found.clone());
+        }
+    }
+    let name = root.file_name()?.to_string_lossy().into_owned();
+    Some(Workspace {
+        name,
+        github: origin_github(&root),
+        paths: vec![root],
+        jira_project: None,
+    })
+}
+
+/// Nearest ancestor of `start` (inclusive) that contains a `.git` entry.
+pub fn repo_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// The git directory of the repository at `root`. Follows the `gitdir:`
+/// pointer that worktrees and submodules keep in a `.git` file.
+pub fn git_dir(root: &Path) -> Option<PathBuf> {
+    let dot_git = root.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let pointer = fs::read_to_string(&dot_git).ok()?;
+    let target = pointer.trim().strip_prefix("gitdir:")?.trim();
+    Some(root.join(target))
+}
+
+/// Directory holding the shared `config` and refs; differs from the git dir
+/// for linked worktrees, which record it in a `commondir` file.
+pub fn common_dir(git_dir: &Path) -> PathBuf {
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common) => git_dir.join(common.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+/// `owner/repo` of the repository's `origin` remote, when it is on GitHub.
+pub fn origin_github(root: &Path) -> Option<String> {
+    let config = fs::read_to_string(common_dir(&git_dir(root)?).join("config")).ok()?;
+    let mut in_origin = false;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_origin = line == r#"[remote "origin"]"#;
+            continue;
+        }
+        if !in_origin {
+            continue;
+        }
+        if let Some(("url", url)) = line.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+            return github_slug(url);
+        }
+    }
+    None
+}
+
+/// `owner/repo` from an SSH or HTTPS GitHub remote URL.
+fn github_slug(url: &str) -> Option<String> {
+    let path = url
+        .strip_prefix("git@github.com:")
+        .or_else(|| url.strip_prefix("ssh://git@github.com/"))
+        .or_else(|| url.strip_prefix("https://github.com/"))
+        .or_else(|| url.strip_prefix("http://github.com/"))?;
+    let path = path.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let (owner, repo) = path.split_once('/')?;
+    (!owner.is_empty() && !repo.is_empty() && !repo.contains('/'))
+        .then(|| format!("{owner}/{repo}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace(name: &str, path: &Path) -> Workspace {
+        Workspace {
+            name: name.into(),
+            paths: vec![path.to_path_buf()],
+            github: None,
+            jira_project: None,
+        }
+    }
+
+    #[test]
+    fn detects_configured_then_git_workspaces() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let repo = dir.path().join("frodo");
+        let nested = repo.join("crates/core");
+        fs::create_dir_all(repo.join(".git")).expect("git dir");
+        fs::create_dir_all(&nested).expect("nested");
+        fs::write(
+            repo.join(".git/config"),
+            "[core]\n\tbare = false\n[remote \"origin\"]\n\turl = git@github.com:frodo-cli/frodo-cli.git\n",
+        )
+        .expect("git config");
+
+        let implicit = detect(&nested, &[]).expect("git workspace");
+        assert_eq!(implicit.name, "frodo");
+        assert_eq!(implicit.github.as_deref(), Some("frodo-cli/frodo-cli"));
+        assert_eq!(implicit.paths, vec![repo.clone()]);
+
+        let outer = workspace("outer", dir.path());
+        let inner = workspace("core", &nested);
+        let found = detect(&nested, &[outer.clone(), inner.clone()]).expect("configured");
+        assert_eq!(found, inner);
+        assert_eq!(detect(&repo, &[outer.clone(), inner]), Some(outer));
+
+        let elsewhere = tempfile::tempdir().expect("tempdir");
+        assert_eq!(detect(elsewhere.path(), &[]), None);
+        assert_eq!(Scope::resolve(&nested, &[], true), Scope::All);
+    }
+
+    #[test]
+    fn scope_filters_by_workspace() {
+        let mut mine = Task::new("mine".into(), None, vec![]);
+        mine.workspace = Some("frodo".into());
+        let other = Task::new("unassigned".into(), None, vec![]);
+        let tasks = vec![mine.clone(), other];
+
+        let scope = Scope::Workspace(workspace("frodo", Path::new("/src/frodo")));
+        assert_eq!(scope.filter(tasks.clone()), vec![mine]);
+        assert_eq!(Scope::All.filter(tasks.clone()), tasks);
+    }
+
+    #[test]
+    fn parses_github_remotes() {
+        for url in [
+            "git@github.com:acme/app.git",
+            "https://github.com/acme/app",
+            "ssh://git@github.com/acme/app.git",
+            "https://github.com/acme/app/",
+        ] {
+            assert_eq!(github_slug(url).as_deref(), Some("acme/app"), "{url}");
+        }
+        assert_eq!(github_slug("https://gitlab.com/acme/app.git"), None);
+    }
+}
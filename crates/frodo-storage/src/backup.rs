@@ -0,0 +1,363 @@
+//! Full-store backup archives: every key and value in one [age] file,
+//! encrypted to a passphrase or to X25519 recipient public keys. Unlike a
+//! copy of the data dir or a key backup, an archive does not depend on the
+//! store's keys, so it restores into a fresh store on another machine.
+//!
+//! [age]: https://age-encryption.org
+
+use std::{collections::BTreeSet, io::Read, path::Path};
+
+use age::{scrypt, secrecy::SecretString, x25519, Decryptor, Encryptor};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use frodo_core::storage::{Batch, SecureStore, SecureStoreError};
+use serde::{Deserialize, Serialize};
+
+use crate::key_provider::unix_now;
+
+/// Archive format written by this version; newer archives are rejected.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Marks the decrypted payload as a frodo archive rather than any age file.
+const ARCHIVE_FORMAT: &str = "frodo-backup";
+
+/// Who can open an archive being written.
+pub enum Protection {
+    /// Anyone with this passphrase (age's scrypt recipient).
+    Passphrase(String),
+    /// The holders of these X25519 identities.
+    Recipients(Vec<x25519::Recipient>),
+}
+
+impl Protection {
+    /// Parse `age1...` public keys, as printed by `age-keygen`.
+    pub fn recipients<S: AsRef<str>>(keys: &[S]) -> Result<Self, SecureStoreError> {
+        let recipients = keys
+            .iter()
+            .map(|key| {
+                let key = key.as_ref().trim();
+                key.parse::<x25519::Recipient>()
+                    .map_err(|err| SecureStoreError::Storage {
+                        reason: format!("invalid recipient `{key}`: {err}"),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if recipients.is_empty() {
+            return Err(SecureStoreError::Storage {
+                reason: "no recipients given".to_string(),
+            });
+        }
+        Ok(Self::Recipients(recipients))
+    }
+}
+
+/// What opens an archive being read.
+pub enum Unlock {
+    Passphrase(String),
+    Identities(Vec<x25519::Identity>),
+}
+
+impl Unlock {
+    /// Read `AGE-SECRET-KEY-...` lines from an identity file; blank lines
+    /// and `#` comments are skipped.
+    pub fn identity_file(path: &Path) -> Result<Self, SecureStoreError> {
+        let text = std::fs::read_to_string(path).map_err(|err| SecureStoreError::Storage {
+            reason: format!("cannot read {}: {err}", path.display()),
+        })?;
+        let identities = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                line.parse::<x25519::Identity>()
+                    .map_err(|err| SecureStoreError::Storage {
+                        reason: format!("{}: {err}", path.display()),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if identities.is_empty() {
+            return Err(SecureStoreError::Storage {
+                reason: format!("no identities in {}", path.display()),
+            });
+        }
+        Ok(Self::Identities(identities))
+    }
+}
+
+/// Plaintext of an archive. Values are base64 so binary blobs survive JSON.
+#[derive(Debug, Serialize, Deserialize)]
+struct Document {
+    format: String,
+    version: u32,
+    /// Unix seconds.
+    created_at: u64,
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Item {
+    key: String,
+    value: String,
+}
+
+/// The decrypted contents of a store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Archive {
+    /// Unix seconds when the archive was written.
+    pub created_at: u64,
+    items: Vec<(String, Vec<u8>)>,
+}
+
+impl Archive {
+    /// An archive of `items`, e.g. from [`EncryptedFileStore::snapshot`].
+    ///
+    /// [`EncryptedFileStore::snapshot`]: crate::secure_file_store::EncryptedFileStore::snapshot
+    pub fn new(items: Vec<(String, Vec<u8>)>) -> Self {
+        Self {
+            created_at: unix_now(),
+            items,
+        }
+    }
+
+    /// Keys and values, in the order they were archived.
+    pub fn items(&self) -> &[(String, Vec<u8>)] {
+        &self.items
+    }
+
+    /// Number of keys.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Encrypt the archive into age's binary format.
+    pub fn seal(&self, protection: &Protection) -> Result<Vec<u8>, SecureStoreError> {
+        let document = Document {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            created_at: self.created_at,
+            items: self
+                .items
+                .iter()
+                .map(|(key, value)| Item {
+                    key: key.clone(),
+                    value: STANDARD.encode(value),
+                })
+                .collect(),
+        };
+        let plaintext = serde_json::to_vec(&document).map_err(backup_err)?;
+        let encryptor = match protection {
+            Protection::Passphrase(passphrase) => {
+                let mut recipient = scrypt::Recipient::new(SecretString::from(passphrase.clone()));
+                // The default work factor is calibrated to about a second.
+                if cfg!(test) {
+                    recipient.set_work_factor(10);
+                }
+                Encryptor::with_recipients(std::iter::once(&recipient as _))
+            }
+            Protection::Recipients(recipients) => {
+                Encryptor::with_recipients(recipients.iter().map(|r| r as _))
+            }
+        }
+        .map_err(backup_err)?;
+
+        let mut out = Vec::new();
+        let mut writer = encryptor.wrap_output(&mut out).map_err(backup_err)?;
+        std::io::Write::write_all(&mut writer, &plaintext).map_err(backup_err)?;
+        writer.finish().map_err(backup_err)?;
+        Ok(out)
+    }
+
+    /// Decrypt and check an archive written by [`Archive::seal`]. Truncated
+    /// or modified files fail age's authentication and are rejected whole.
+    pub fn open(bytes: &[u8], unlock: &Unlock) -> Result<Self, SecureStoreError> {
+        let decryptor =
+            Decryptor::new_buffered(bytes).map_err(|err| SecureStoreError::Storage {
+                reason: format!("not a backup archive: {err}"),
+            })?;
+        let passphrase;
+        let mut reader = match unlock {
+            Unlock::Passphrase(secret) => {
+                passphrase = scrypt::Identity::new(SecretString::from(secret.clone()));
+                decryptor.decrypt(std::iter::once(&passphrase as _))
+            }
+            Unlock::Identities(identities) => decryptor.decrypt(identities.iter().map(|i| i as _)),
+        }
+        .map_err(|err| match err {
+            age::DecryptError::NoMatchingKeys | age::DecryptError::DecryptionFailed => {
+                SecureStoreError::Storage {
+                    reason: "wrong passphrase or identity for this archive".to_string(),
+                }
+            }
+            err => backup_err(err),
+        })?;
+        let mut plaintext = Vec::new();
+        reader.read_to_end(&mut plaintext).map_err(backup_err)?;
+
+        let document: Document =
+            serde_json::from_slice(&plaintext).map_err(|err| SecureStoreError::Storage {
+                reason: format!("archive contents are not a frodo backup: {err}"),
+            })?;
+        if document.format != ARCHIVE_FORMAT {
+            return Err(SecureStoreError::Storage {
+                reason: format!("archive format `{}` is not a frodo backup", document.format),
+            });
+        }
+        if document.version > ARCHIVE_VERSION {
+            return Err(SecureStoreError::Storage {
+                reason: format!(
+                    "archive v{} is newer than this frodo supports",
+                    document.version
+                ),
+            });
+        }
+        let mut seen = BTreeSet::new();
+        let items = document
+            .items
+            .into_iter()
+            .map(|item| {
+                if !seen.insert(item.key.clone()) {
+                    return Err(SecureStoreError::Storage {
+                        reason: format!("archive lists `{}` twice", item.key),
+                    });
+                }
+                let value =
+                    STANDARD
+                        .decode(&item.value)
+                        .map_err(|err| SecureStoreError::Storage {
+                            reason: format!("archive value for `{}` is corrupt: {err}", item.key),
+                        })?;
+                Ok((item.key, value))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            created_at: document.created_at,
+            items,
+        })
+    }
+}
+
+/// Outcome of [`restore`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RestoreReport {
+    /// Keys written from the archive.
+    pub restored: usize,
+    /// Keys the store held that the archive does not, now deleted.
+    pub removed: usize,
+}
+
+/// Make `store` hold exactly the archive's keys, in one batch, then read
+/// every key back and compare it with the archive.
+pub async fn restore<S: SecureStore + ?Sized>(
+    store: &S,
+    archive: &Archive,
+) -> Result<RestoreReport, SecureStoreError> {
+    let keep: BTreeSet<&str> = archive.items.iter().map(|(key, _)| key.as_str()).collect();
+    let stale: Vec<String> = store
+        .list("")
+        .await?
+        .into_iter()
+        .filter(|key| !keep.contains(key.as_str()))
+        .collect();
+
+    let mut batch = Batch::new();
+    for (key, value) in &archive.items {
+        batch.put(key.clone(), value.clone());
+    }
+    for key in &stale {
+        batch.delete(key.clone());
+    }
+    store.commit(batch).await?;
+
+    for (key, value) in &archive.items {
+        if store.get(key).await? != *value {
+            return Err(SecureStoreError::Storage {
+                reason: format!("`{key}` does not match the archive after restoring"),
+            });
+        }
+    }
+    let held = store.list("").await?;
+    if held.len() != keep.len() {
+        return Err(SecureStoreError::Storage {
+            reason: format!(
+                "store holds {} key(s) after restoring, the archive {}",
+                held.len(),
+                keep.len()
+            ),
+        });
+    }
+    Ok(RestoreReport {
+        restored: archive.items.len(),
+        removed: stale.len(),
+    })
+}
+
+fn backup_err(err: impl std::fmt::Display) -> SecureStoreError {
+    SecureStoreError::Storage {
+        reason: format!("backup archive: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{key_provider::InMemoryKeyProvider, secure_file_store::EncryptedFileStore};
+
+    #[tokio::test]
+    async fn archives_restore_into_a_store_with_other_keys() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let source = EncryptedFileStore::new(dir.path().join("a"), InMemoryKeyProvider::default());
+        source.put("tasks/index", b"[1]").await.expect("put");
+        source.put("tasks/1", &[0, 159, 255]).await.expect("put");
+
+        let archive = Archive::new(source.snapshot().await.expect("snapshot"));
+        let sealed = archive
+            .seal(&Protection::Passphrase("pw".into()))
+            .expect("seal");
+        assert!(!sealed.windows(3).any(|w| w == b"[1]"));
+
+        let target = EncryptedFileStore::new(dir.path().join("b"), InMemoryKeyProvider::default());
+        target.put("stale", b"x").await.expect("put");
+        let opened = Archive::open(&sealed, &Unlock::Passphrase("pw".into())).expect("open");
+        assert_eq!(opened, archive);
+
+        let report = restore(&target, &opened).await.expect("restore");
+        assert_eq!((report.restored, report.removed), (2, 1));
+        assert_eq!(target.get("tasks/1").await.expect("get"), [0, 159, 255]);
+        assert!(!target.exists("stale").await.expect("exists"));
+
+        assert!(Archive::open(&sealed, &Unlock::Passphrase("nope".into())).is_err());
+        let mut truncated = sealed.clone();
+        truncated.truncate(sealed.len() - 8);
+        assert!(Archive::open(&truncated, &Unlock::Passphrase("pw".into())).is_err());
+    }
+
+    #[test]
+    fn recipients_open_with_their_identity_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let identity = x25519::Identity::generate();
+        let public = identity.to_public().to_string();
+        let archive = Archive::new(vec![("k".into(), b"v".to_vec())]);
+        let sealed = archive
+            .seal(&Protection::recipients(&[public]).expect("recipient"))
+            .expect("seal");
+
+        let path = dir.path().join("key.txt");
+        std::fs::write(
+            &path,
+            format!(
+                "# created by age-keygen\n{}\n",
+                age::secrecy::ExposeSecret::expose_secret(&identity.to_string())
+            ),
+        )
+        .expect("write identity");
+        let unlock = Unlock::identity_file(&path).expect("identity");
+        assert_eq!(Archive::open(&sealed, &unlock).expect("open"), archive);
+
+        let other = Unlock::Identities(vec![x25519::Identity::generate()]);
+        assert!(Archive::open(&sealed, &other).is_err());
+        assert!(Protection::recipients(&["age1nope"]).is_err());
+    }
+}
@@ -1,14 +1,31 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
 use async_trait::async_trait;
 use base64::{engine::general_purpose, Engine as _};
 use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-/// Key material used for encryption at rest.
+/// Id of the first key, and of keys stored before rotation existed.
+pub const DEFAULT_KEY_ID: &str = "default";
+
+/// A 256-bit key: a key-encryption key (KEK) from a provider, or a data key
+/// the store keeps wrapped in its header.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct KeyMaterial {
-    /// Identifier for logging/rotation (never log key bytes).
+    /// Identifier used to find the right key after a rotation (never log key bytes).
     pub id: String,
     /// 256-bit symmetric key.
     pub bytes: [u8; 32],
@@ -22,12 +39,30 @@ pub enum KeyError {
     Decode(String),
     #[error("generation error: {0}")]
     Generation(String),
+    #[error("key `{0}` is active and cannot be retired")]
+    ActiveKey(String),
+    #[error("passphrase: {0}")]
+    Passphrase(String),
+    #[error("key file: {0}")]
+    KeyFile(String),
 }
 
-/// Provides access to encryption keys (OS keychain in production; memory in tests).
+/// Supplies key-encryption keys (OS keychain in production; memory in tests).
+/// A KEK never encrypts data itself: it unwraps the store's master key,
+/// which in turn unwraps the data keys kept in the store header.
+///
+/// One key is active. Rotation makes a fresh key active and keeps the
+/// previous ones readable by id until they are retired.
 #[async_trait]
 pub trait KeyProvider: Send + Sync {
+    /// The active key, created on first use.
     async fn get_or_create(&self) -> Result<KeyMaterial, KeyError>;
+    /// A key by id: the active one or one kept from before a rotation.
+    async fn get(&self, id: &str) -> Result<Option<KeyMaterial>, KeyError>;
+    /// Make a newly generated key active; the old one stays available via `get`.
+    async fn rotate(&self) -> Result<KeyMaterial, KeyError>;
+    /// Forget an inactive key once no data is encrypted with it.
+    async fn retire(&self, id: &str) -> Result<(), KeyError>;
 }
 
 /// OS keyring-backed provider. Uses the `keyring` crate to store the key.
@@ -43,24 +78,65 @@ impl KeyringProvider {
             account: account.into(),
         }
     }
+
+    /// The active key lives under `account`; rotated-out keys under `account/<id>`.
+    fn entry(&self, id: Option<&str>) -> Result<keyring::Entry, KeyError> {
+        let account = match id {
+            Some(id) => format!("{}/{id}", self.account),
+            None => self.account.clone(),
+        };
+        keyring::Entry::new(&self.service, &account).map_err(|e| KeyError::Keyring(e.to_string()))
+    }
+
+    fn read(&self, id: Option<&str>) -> Result<Option<KeyMaterial>, KeyError> {
+        match self.entry(id)?.get_password() {
+            Ok(secret) => decode_key(&secret).map(Some),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(KeyError::Keyring(err.to_string())),
+        }
+    }
+
+    fn write(&self, id: Option<&str>, material: &KeyMaterial) -> Result<(), KeyError> {
+        self.entry(id)?
+            .set_password(&encode_key(material))
+            .map_err(|e| KeyError::Keyring(e.to_string()))
+    }
 }
 
+// Keyring operations are synchronous; wrapped in async for trait compatibility.
 #[async_trait]
 impl KeyProvider for KeyringProvider {
     async fn get_or_create(&self) -> Result<KeyMaterial, KeyError> {
-        // Keyring operations are synchronous; wrap in async for trait compatibility.
-        match keyring::Entry::new(&self.service, &self.account) {
-            Ok(entry) => {
-                if let Ok(secret) = entry.get_password() {
-                    return decode_key(&secret);
-                }
+        if let Some(material) = self.read(None)? {
+            return Ok(material);
+        }
+        let material = generate_key(DEFAULT_KEY_ID.to_string());
+        self.write(None, &material)?;
+        Ok(material)
+    }
 
-                let material = generate_key();
-                entry
-                    .set_password(&encode_key(&material))
-                    .map_err(|e| KeyError::Keyring(e.to_string()))?;
-                Ok(material)
-            }
+    async fn get(&self, id: &str) -> Result<Option<KeyMaterial>, KeyError> {
+        match self.read(None)? {
+            Some(active) if active.id == id => Ok(Some(active)),
+            _ => self.read(Some(id)),
+        }
+    }
+
+    async fn rotate(&self) -> Result<KeyMaterial, KeyError> {
+        let previous = self.get_or_create().await?;
+        // Keep the old key first, so a crash in between loses nothing.
+        self.write(Some(&previous.id), &previous)?;
+        let material = generate_key(new_key_id());
+        self.write(None, &material)?;
+        Ok(material)
+    }
+
+    async fn retire(&self, id: &str) -> Result<(), KeyError> {
+        if self.read(None)?.is_some_and(|active| active.id == id) {
+            return Err(KeyError::ActiveKey(id.to_string()));
+        }
+        match self.entry(Some(id))?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
             Err(err) => Err(KeyError::Keyring(err.to_string())),
         }
     }
@@ -69,41 +145,468 @@ impl KeyProvider for KeyringProvider {
 /// In-memory key provider for tests and ephemeral sessions.
 #[derive(Debug, Default, Clone)]
 pub struct InMemoryKeyProvider {
-    inner: Arc<Mutex<Option<KeyMaterial>>>,
+    inner: Arc<Mutex<MemoryKeys>>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryKeys {
+    active: Option<KeyMaterial>,
+    previous: HashMap<String, KeyMaterial>,
+}
+
+impl InMemoryKeyProvider {
+    fn keys(&self) -> Result<std::sync::MutexGuard<'_, MemoryKeys>, KeyError> {
+        self.inner
+            .lock()
+            .map_err(|err| KeyError::Generation(format!("lock poisoned: {err}")))
+    }
 }
 
 #[async_trait]
 impl KeyProvider for InMemoryKeyProvider {
     async fn get_or_create(&self) -> Result<KeyMaterial, KeyError> {
-        let mut guard = self
-            .inner
+        let mut keys = self.keys()?;
+        Ok(keys
+            .active
+            .get_or_insert_with(|| generate_key(DEFAULT_KEY_ID.to_string()))
+            .clone())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<KeyMaterial>, KeyError> {
+        let keys = self.keys()?;
+        Ok(match &keys.active {
+            Some(active) if active.id == id => Some(active.clone()),
+            _ => keys.previous.get(id).cloned(),
+        })
+    }
+
+    async fn rotate(&self) -> Result<KeyMaterial, KeyError> {
+        let mut keys = self.keys()?;
+        let material = generate_key(new_key_id());
+        if let Some(previous) = keys.active.replace(material.clone()) {
+            keys.previous.insert(previous.id.clone(), previous);
+        }
+        Ok(material)
+    }
+
+    async fn retire(&self, id: &str) -> Result<(), KeyError> {
+        let mut keys = self.keys()?;
+        if keys.active.as_ref().is_some_and(|active| active.id == id) {
+            return Err(KeyError::ActiveKey(id.to_string()));
+        }
+        keys.previous.remove(id);
+        Ok(())
+    }
+}
+
+/// Argon2id cost parameters for deriving a passphrase KEK. Only used for
+/// newly created keys; existing ones keep the parameters they were made with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KdfParams {
+    /// Memory cost in KiB.
+    pub memory_kib: u32,
+    /// Number of passes over memory.
+    pub iterations: u32,
+    /// Degree of parallelism (lanes).
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    pub(crate) fn derive(&self, passphrase: &str, salt: &[u8]) -> Result<[u8; 32], KeyError> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| KeyError::Generation(format!("invalid KDF parameters: {e}")))?;
+        let mut out = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), salt, &mut out)
+            .map_err(|e| KeyError::Generation(format!("key derivation failed: {e}")))?;
+        Ok(out)
+    }
+}
+
+/// Where [`PassphraseKeyProvider`] reads the passphrase from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PassphraseSource {
+    /// Ask on the terminal (twice when creating the key file).
+    Prompt,
+    /// Read from this environment variable.
+    Env(String),
+    /// Read the first line from this open file descriptor (Unix only),
+    /// e.g. `3<passphrase-file` or a pipe from a secret manager.
+    Fd(i32),
+}
+
+impl PassphraseSource {
+    fn read(&self, confirm: bool) -> Result<String, KeyError> {
+        self.read_as("Frodo passphrase", confirm)
+    }
+
+    /// Read a secret, prompting with `label` when interactive. With
+    /// `confirm`, a prompted secret must be entered twice.
+    pub fn read_as(&self, label: &str, confirm: bool) -> Result<String, KeyError> {
+        let passphrase = match self {
+            PassphraseSource::Prompt => {
+                let first = rpassword::prompt_password(format!("{label}: "))
+                    .map_err(|e| KeyError::Passphrase(e.to_string()))?;
+                if confirm {
+                    let second = rpassword::prompt_password(format!("Repeat {label}: "))
+                        .map_err(|e| KeyError::Passphrase(e.to_string()))?;
+                    if first != second {
+                        return Err(KeyError::Passphrase("passphrases do not match".into()));
+                    }
+                }
+                first
+            }
+            PassphraseSource::Env(name) => std::env::var(name).map_err(|_| {
+                KeyError::Passphrase(format!("environment variable {name} is not set"))
+            })?,
+            PassphraseSource::Fd(fd) => read_fd(*fd)?,
+        };
+        if passphrase.is_empty() {
+            return Err(KeyError::Passphrase("passphrase is empty".into()));
+        }
+        Ok(passphrase)
+    }
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<String, KeyError> {
+    let contents = fs::read_to_string(format!("/dev/fd/{fd}"))
+        .map_err(|e| KeyError::Passphrase(format!("reading fd {fd}: {e}")))?;
+    Ok(contents.lines().next().unwrap_or_default().to_string())
+}
+
+#[cfg(not(unix))]
+fn read_fd(fd: i32) -> Result<String, KeyError> {
+    Err(KeyError::Passphrase(format!(
+        "reading from fd {fd} is only supported on Unix"
+    )))
+}
+
+/// Remembers the derived wrapping key for a while so every command does not
+/// prompt again. Should live on a tmpfs such as `$XDG_RUNTIME_DIR`.
+#[derive(Debug, Clone)]
+pub struct SessionCache {
+    path: PathBuf,
+    ttl: Duration,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionEntry {
+    /// Salt of the key file the cached key belongs to.
+    salt: String,
+    key: String,
+    expires_at: u64,
+}
+
+impl SessionCache {
+    pub fn new(path: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self {
+            path: path.into(),
+            ttl,
+        }
+    }
+
+    fn load(&self, salt: &str) -> Option<[u8; 32]> {
+        let entry: SessionEntry = serde_json::from_slice(&fs::read(&self.path).ok()?).ok()?;
+        if entry.salt != salt || entry.expires_at <= unix_now() {
+            return None;
+        }
+        general_purpose::STANDARD
+            .decode(entry.key)
+            .ok()?
+            .try_into()
+            .ok()
+    }
+
+    /// Best effort: a failed write only means prompting again next time.
+    fn store(&self, salt: &str, key: &[u8; 32]) {
+        let entry = SessionEntry {
+            salt: salt.to_string(),
+            key: general_purpose::STANDARD.encode(key),
+            expires_at: unix_now() + self.ttl.as_secs(),
+        };
+        if let Err(err) = write_private(&self.path, &entry) {
+            tracing::debug!("session cache not written: {err}");
+        }
+    }
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// On-disk key file: what is needed to re-derive each passphrase KEK.
+/// Holds no key material itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeyFile {
+    active: String,
+    keks: BTreeMap<String, DerivedKek>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DerivedKek {
+    kdf: KdfParams,
+    salt: String,
+    /// A zero block sealed with the derived key, so a wrong passphrase is
+    /// reported as such rather than as a failure to unlock the store.
+    check: WrappedKey,
+}
+
+/// AES-GCM wrapped 32-byte key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct WrappedKey {
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Key provider for machines without an OS keychain (SSH boxes, CI). The
+/// KEK is derived from a passphrase with Argon2id; the key file only keeps
+/// the salt and cost parameters.
+pub struct PassphraseKeyProvider {
+    path: PathBuf,
+    source: PassphraseSource,
+    params: KdfParams,
+    session: Option<SessionCache>,
+    /// Keys derived in this process, by id.
+    derived: Mutex<HashMap<String, KeyMaterial>>,
+}
+
+impl PassphraseKeyProvider {
+    pub fn new(path: impl Into<PathBuf>, source: PassphraseSource) -> Self {
+        Self {
+            path: path.into(),
+            source,
+            params: KdfParams::default(),
+            session: None,
+            derived: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// KDF cost used for newly created keys.
+    pub fn with_params(mut self, params: KdfParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Cache the derived key between commands.
+    pub fn with_session(mut self, session: SessionCache) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    fn derived(&self) -> Result<std::sync::MutexGuard<'_, HashMap<String, KeyMaterial>>, KeyError> {
+        self.derived
             .lock()
-            .map_err(|err| KeyError::Generation(format!("lock poisoned: {err}")))?;
+            .map_err(|err| KeyError::Generation(format!("lock poisoned: {err}")))
+    }
+
+    fn load(&self) -> Result<Option<KeyFile>, KeyError> {
+        match fs::read(&self.path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| KeyError::KeyFile(format!("{}: {e}", self.path.display()))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(KeyError::KeyFile(err.to_string())),
+        }
+    }
 
-        if let Some(existing) = guard.clone() {
-            return Ok(existing);
+    fn save(&self, file: &KeyFile) -> Result<(), KeyError> {
+        write_private(&self.path, file).map_err(|e| KeyError::KeyFile(e.to_string()))
+    }
+
+    /// Re-derive a KEK from the session cache or the passphrase.
+    fn derive(
+        &self,
+        derived: &mut HashMap<String, KeyMaterial>,
+        id: &str,
+        entry: &DerivedKek,
+    ) -> Result<KeyMaterial, KeyError> {
+        if let Some(material) = derived.get(id) {
+            return Ok(material.clone());
+        }
+        let cached = self.session.as_ref().and_then(|s| s.load(&entry.salt));
+        let bytes = match cached.filter(|kek| unwrap_key(kek, &entry.check).is_ok()) {
+            Some(kek) => kek,
+            None => {
+                let salt = general_purpose::STANDARD
+                    .decode(&entry.salt)
+                    .map_err(|e| KeyError::KeyFile(format!("salt: {e}")))?;
+                let kek = entry.kdf.derive(&self.source.read(false)?, &salt)?;
+                unwrap_key(&kek, &entry.check)
+                    .map_err(|_| KeyError::Passphrase("wrong passphrase".into()))?;
+                if let Some(session) = &self.session {
+                    session.store(&entry.salt, &kek);
+                }
+                kek
+            }
+        };
+        let material = KeyMaterial {
+            id: id.to_string(),
+            bytes,
+        };
+        derived.insert(id.to_string(), material.clone());
+        Ok(material)
+    }
+
+    /// Derive a KEK from a newly entered passphrase with a fresh salt.
+    fn create(
+        &self,
+        derived: &mut HashMap<String, KeyMaterial>,
+    ) -> Result<(KeyMaterial, DerivedKek), KeyError> {
+        let passphrase = self.source.read(true)?;
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let material = KeyMaterial {
+            id: format!("p-{:016x}", OsRng.next_u64()),
+            bytes: self.params.derive(&passphrase, &salt)?,
+        };
+        let entry = DerivedKek {
+            kdf: self.params,
+            salt: general_purpose::STANDARD.encode(salt),
+            check: wrap_key(&material.bytes, &[0; 32])?,
+        };
+        if let Some(session) = &self.session {
+            session.store(&entry.salt, &material.bytes);
+        }
+        derived.insert(material.id.clone(), material.clone());
+        Ok((material, entry))
+    }
+}
+
+#[async_trait]
+impl KeyProvider for PassphraseKeyProvider {
+    async fn get_or_create(&self) -> Result<KeyMaterial, KeyError> {
+        let mut derived = self.derived()?;
+        if let Some(file) = self.load()? {
+            let entry = file.keks.get(&file.active).ok_or_else(|| {
+                KeyError::KeyFile(format!("active key `{}` missing", file.active))
+            })?;
+            return self.derive(&mut derived, &file.active, entry);
         }
+        let (material, entry) = self.create(&mut derived)?;
+        self.save(&KeyFile {
+            active: material.id.clone(),
+            keks: BTreeMap::from([(material.id.clone(), entry)]),
+        })?;
+        Ok(material)
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<KeyMaterial>, KeyError> {
+        let mut derived = self.derived()?;
+        let Some(entry) = self.load()?.and_then(|mut file| file.keks.remove(id)) else {
+            return Ok(None);
+        };
+        self.derive(&mut derived, id, &entry).map(Some)
+    }
 
-        let material = generate_key();
-        *guard = Some(material.clone());
+    /// Asks for a (new) passphrase and derives the next KEK from it.
+    async fn rotate(&self) -> Result<KeyMaterial, KeyError> {
+        self.get_or_create().await?;
+        let mut derived = self.derived()?;
+        let mut file = self
+            .load()?
+            .ok_or_else(|| KeyError::KeyFile("key file disappeared".into()))?;
+        let (material, entry) = self.create(&mut derived)?;
+        file.keks.insert(material.id.clone(), entry);
+        file.active = material.id.clone();
+        self.save(&file)?;
         Ok(material)
     }
+
+    async fn retire(&self, id: &str) -> Result<(), KeyError> {
+        let mut derived = self.derived()?;
+        let Some(mut file) = self.load()? else {
+            return Ok(());
+        };
+        if file.active == id {
+            return Err(KeyError::ActiveKey(id.to_string()));
+        }
+        derived.remove(id);
+        if file.keks.remove(id).is_some() {
+            self.save(&file)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn wrap_key(kek: &[u8; 32], key: &[u8; 32]) -> Result<WrappedKey, KeyError> {
+    let cipher = Aes256Gcm::new_from_slice(kek).map_err(|e| KeyError::Generation(e.to_string()))?;
+    let nonce = Aes256Gcm::generate_nonce(&mut aes_gcm::aead::OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, key.as_slice())
+        .map_err(|e| KeyError::Generation(format!("wrap failed: {e}")))?;
+    Ok(WrappedKey {
+        nonce: general_purpose::STANDARD.encode(nonce),
+        ciphertext: general_purpose::STANDARD.encode(ciphertext),
+    })
+}
+
+pub(crate) fn unwrap_key(kek: &[u8; 32], wrapped: &WrappedKey) -> Result<[u8; 32], KeyError> {
+    let cipher = Aes256Gcm::new_from_slice(kek).map_err(|e| KeyError::Decode(e.to_string()))?;
+    let nonce = general_purpose::STANDARD
+        .decode(&wrapped.nonce)
+        .map_err(|e| KeyError::Decode(e.to_string()))?;
+    let ciphertext = general_purpose::STANDARD
+        .decode(&wrapped.ciphertext)
+        .map_err(|e| KeyError::Decode(e.to_string()))?;
+    let key = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|e| KeyError::Decode(format!("unwrap failed: {e}")))?;
+    key.try_into()
+        .map_err(|_| KeyError::Decode("unexpected key length".into()))
+}
+
+/// Atomically write `value` as JSON, readable only by the owner.
+fn write_private<T: Serialize>(path: &Path, value: &T) -> std::io::Result<()> {
+    let parent = path
+        .parent()
+        .ok_or_else(|| std::io::Error::other("path has no parent"))?;
+    fs::create_dir_all(parent)?;
+    // Temp files are created with mode 0600 on Unix.
+    let mut tmp = tempfile::NamedTempFile::new_in(parent)?;
+    tmp.write_all(&serde_json::to_vec(value).map_err(std::io::Error::other)?)?;
+    tmp.flush()?;
+    tmp.persist(path).map_err(|e| e.error)?;
+    Ok(())
 }
 
-fn generate_key() -> KeyMaterial {
+pub(crate) fn generate_key(id: String) -> KeyMaterial {
     let mut bytes = [0u8; 32];
     OsRng.fill_bytes(&mut bytes);
-    KeyMaterial {
-        id: "default".to_string(),
-        bytes,
-    }
+    KeyMaterial { id, bytes }
 }
 
+/// Random id for a rotated key, e.g. `k-3f2a9c1b7d4e6a05`.
+fn new_key_id() -> String {
+    format!("k-{:016x}", OsRng.next_u64())
+}
+
+/// `<id>:<base64>`; the active key from before rotation existed is bare base64.
 fn encode_key(material: &KeyMaterial) -> String {
-    general_purpose::STANDARD.encode(material.bytes)
+    let key = general_purpose::STANDARD.encode(material.bytes);
+    if material.id == DEFAULT_KEY_ID {
+        key
+    } else {
+        format!("{}:{key}", material.id)
+    }
 }
 
 fn decode_key(secret: &str) -> Result<KeyMaterial, KeyError> {
+    let (id, secret) = secret.split_once(':').unwrap_or((DEFAULT_KEY_ID, secret));
     let bytes = general_purpose::STANDARD
         .decode(secret)
         .map_err(|e| KeyError::Decode(e.to_string()))?;
@@ -118,7 +621,7 @@ fn decode_key(secret: &str) -> Result<KeyMaterial, KeyError> {
     let mut out = [0u8; 32];
     out.copy_from_slice(&bytes);
     Ok(KeyMaterial {
-        id: "default".to_string(),
+        id: id.to_string(),
         bytes: out,
     })
 }
@@ -137,6 +640,107 @@ mod tests {
         assert_eq!(first.id, second.id);
     }
 
+    #[tokio::test]
+    async fn rotation_keeps_previous_keys_until_retired() {
+        let provider = InMemoryKeyProvider::default();
+        let first = provider.get_or_create().await.unwrap();
+        assert_eq!(first.id, DEFAULT_KEY_ID);
+
+        let second = provider.rotate().await.unwrap();
+        assert_ne!(second.id, first.id);
+        assert_eq!(provider.get_or_create().await.unwrap(), second);
+        assert_eq!(provider.get(&first.id).await.unwrap(), Some(first.clone()));
+
+        assert!(matches!(
+            provider.retire(&second.id).await,
+            Err(KeyError::ActiveKey(_))
+        ));
+        provider.retire(&first.id).await.unwrap();
+        assert_eq!(provider.get(&first.id).await.unwrap(), None);
+    }
+
+    #[test]
+    fn encodes_key_ids_alongside_legacy_secrets() {
+        let legacy = generate_key(DEFAULT_KEY_ID.to_string());
+        assert!(!encode_key(&legacy).contains(':'));
+        assert_eq!(decode_key(&encode_key(&legacy)).unwrap(), legacy);
+
+        let rotated = generate_key(new_key_id());
+        assert_eq!(decode_key(&encode_key(&rotated)).unwrap(), rotated);
+    }
+
+    /// Cheap parameters so tests do not spend seconds in Argon2.
+    const TEST_KDF: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    fn passphrase_provider(path: &Path, source: PassphraseSource) -> PassphraseKeyProvider {
+        PassphraseKeyProvider::new(path, source).with_params(TEST_KDF)
+    }
+
+    #[tokio::test]
+    async fn passphrase_provider_wraps_keys_in_a_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join(".keys");
+        std::env::set_var("FRODO_TEST_PASSPHRASE_A", "correct horse");
+        let source = PassphraseSource::Env("FRODO_TEST_PASSPHRASE_A".into());
+
+        let first = passphrase_provider(&path, source.clone());
+        let key = first.get_or_create().await.expect("create");
+        let stored = fs::read_to_string(&path).expect("key file");
+        assert!(!stored.contains(&general_purpose::STANDARD.encode(key.bytes)));
+        let rotated = first.rotate().await.expect("rotate");
+
+        let reopened = passphrase_provider(&path, source);
+        assert_eq!(reopened.get_or_create().await.unwrap(), rotated);
+        assert_eq!(reopened.get(&key.id).await.unwrap(), Some(key.clone()));
+        reopened.retire(&key.id).await.expect("retire");
+        assert_eq!(reopened.get(&key.id).await.unwrap(), None);
+
+        std::env::set_var("FRODO_TEST_PASSPHRASE_B", "wrong");
+        let wrong = passphrase_provider(
+            &path,
+            PassphraseSource::Env("FRODO_TEST_PASSPHRASE_B".into()),
+        );
+        assert!(matches!(
+            wrong.get_or_create().await,
+            Err(KeyError::Passphrase(_))
+        ));
+        let missing = passphrase_provider(&path, PassphraseSource::Env("FRODO_TEST_UNSET".into()));
+        assert!(matches!(
+            missing.get_or_create().await,
+            Err(KeyError::Passphrase(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn session_cache_skips_the_passphrase() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join(".keys");
+        let session = SessionCache::new(dir.path().join("session"), Duration::from_secs(60));
+        std::env::set_var("FRODO_TEST_PASSPHRASE_C", "hunter2");
+
+        let key = passphrase_provider(
+            &path,
+            PassphraseSource::Env("FRODO_TEST_PASSPHRASE_C".into()),
+        )
+        .with_session(session.clone())
+        .get_or_create()
+        .await
+        .expect("create");
+        let cached = passphrase_provider(&path, PassphraseSource::Env("FRODO_TEST_UNSET".into()))
+            .with_session(session);
+        assert_eq!(cached.get_or_create().await.expect("from session"), key);
+
+        let expired = SessionCache::new(dir.path().join("session"), Duration::ZERO);
+        expired.store("other-salt", &[0; 32]);
+        let prompted = passphrase_provider(&path, PassphraseSource::Env("FRODO_TEST_UNSET".into()))
+            .with_session(expired);
+        assert!(prompted.get_or_create().await.is_err());
+    }
+
     #[test]
     fn decode_rejects_wrong_length() {
         let err = decode_key("abcd").expect_err("should reject wrong length");
@@ -1,5 +1,9 @@
 //! Concrete storage implementations with encryption at rest.
-//! Uses AES-GCM with keys sourced from the OS keyring (or test doubles).
+//! Uses AES-GCM with keys sourced from the OS keyring (or test doubles);
+//! sealed blobs live in per-key files or a SQLite database.
 
+pub mod backup;
 pub mod key_provider;
+pub mod recovery;
 pub mod secure_file_store;
+pub mod sqlite_store;
@@ -0,0 +1,143 @@
+//! Recovery material for when the key provider is lost (wiped keychain,
+//! forgotten passphrase): printable recovery codes and passphrase-protected
+//! key backup files. Both wrap the store's master key, so they keep working
+//! across data-key and KEK rotations.
+
+use std::fmt;
+
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::key_provider::{KdfParams, WrappedKey};
+
+/// Crockford base32: no I, L, O or U, so codes survive handwriting.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+/// 160 random bits, printed as 8 groups of 4 characters.
+const CODE_BYTES: usize = 20;
+/// Codes are random, not chosen by people, so a light KDF is enough.
+pub(crate) const CODE_KDF: KdfParams = KdfParams {
+    memory_kib: 1024,
+    iterations: 1,
+    parallelism: 1,
+};
+
+/// A printable recovery code such as `7K3M-Q9XA-...`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RecoveryCode(String);
+
+impl RecoveryCode {
+    pub(crate) fn generate() -> Self {
+        let mut bytes = [0u8; CODE_BYTES];
+        OsRng.fill_bytes(&mut bytes);
+        Self(encode(&bytes))
+    }
+
+    /// Parse a code as typed back in: case, dashes and spaces don't matter,
+    /// and the look-alikes `O`, `I`, `L` read as `0` and `1`.
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut normalized = String::with_capacity(32);
+        for c in input.chars().filter(|c| !matches!(c, '-' | ' ')) {
+            let c = match c.to_ascii_uppercase() {
+                'O' => '0',
+                'I' | 'L' => '1',
+                c if c.is_ascii() && ALPHABET.contains(&(c as u8)) => c,
+                _ => return None,
+            };
+            normalized.push(c);
+        }
+        (normalized.len() == CODE_BYTES * 8 / 5).then_some(Self(normalized))
+    }
+
+    /// Canonical characters without separators, used for key derivation.
+    pub(crate) fn secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RecoveryCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let groups: Vec<&str> = self
+            .0
+            .as_bytes()
+            .chunks(4)
+            .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+            .collect();
+        f.write_str(&groups.join("-"))
+    }
+}
+
+impl fmt::Debug for RecoveryCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RecoveryCode(..)")
+    }
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+/// Kind of recovery material recorded in the store header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecoveryKind {
+    Code,
+    File,
+}
+
+/// Recovery material generated for a store. Codes also keep the salt and
+/// KDF parameters needed to turn the code back into a key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryRecord {
+    pub id: String,
+    pub kind: RecoveryKind,
+    /// Unix seconds.
+    pub created_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) kdf: Option<KdfParams>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) salt: Option<String>,
+}
+
+/// Contents of a key backup file: the master key wrapped under a key
+/// derived from the backup passphrase. Useless without the data dir.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyBackup {
+    pub(crate) id: String,
+    pub(crate) kdf: KdfParams,
+    pub(crate) salt: String,
+    pub(crate) master: WrappedKey,
+    pub(crate) created_at: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_round_trip_through_sloppy_typing() {
+        let code = RecoveryCode::generate();
+        let printed = code.to_string();
+        assert_eq!(printed.len(), 39);
+        assert_eq!(printed.split('-').count(), 8);
+        let typed = printed.to_lowercase().replace('-', " ");
+        assert_eq!(RecoveryCode::parse(&typed), Some(code.clone()));
+
+        let with_lookalikes = code.secret().replace('0', "o").replace('1', "l");
+        assert_eq!(RecoveryCode::parse(&with_lookalikes), Some(code));
+        assert_eq!(RecoveryCode::parse("ABCD-EFGH"), None);
+        assert_eq!(RecoveryCode::parse(&"U".repeat(32)), None);
+    }
+}
@@ -1,96 +1,658 @@
 use std::{
-    fs::{self, File},
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::{self, File, OpenOptions},
     io::{Read, Write},
     path::{Path, PathBuf},
+    sync::Mutex,
 };
 
 use aes_gcm::{
-    aead::{Aead, AeadCore, KeyInit, OsRng},
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
     Aes256Gcm, Nonce,
 };
 use async_trait::async_trait;
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
-use frodo_core::storage::{SecureStore, SecureStoreError};
+use frodo_core::storage::{Batch, BatchOp, SecureStore, SecureStoreError, Versioned};
+use fs4::fs_std::FileExt;
 use serde::{Deserialize, Serialize};
 use tempfile::NamedTempFile;
 use tracing::instrument;
 
-use crate::key_provider::{KeyMaterial, KeyProvider};
+use crate::{
+    key_provider::{
+        generate_key, unix_now, unwrap_key, wrap_key, KdfParams, KeyError, KeyMaterial,
+        KeyProvider, WrappedKey, DEFAULT_KEY_ID,
+    },
+    recovery::{KeyBackup, RecoveryCode, RecoveryKind, RecoveryRecord, CODE_KDF},
+};
+
+/// Advisory lock file serializing writers across processes sharing a data dir.
+/// Encoded key names never start with `.`, so it cannot collide with a blob.
+const LOCK_FILE: &str = ".lock";
+
+/// Present while `rotate_key` is re-encrypting blobs; a later run resumes it.
+const ROTATION_FILE: &str = ".rotation";
+
+/// Wrapped master and data keys; see [`Header`].
+const HEADER_FILE: &str = ".header";
+
+/// Write-ahead manifest of a multi-blob batch in the file backend.
+const BATCH_FILE: &str = ".batch";
+
+/// Where [`EncryptedFileStore::repair`] moves blobs that fail the check.
+pub const QUARANTINE_DIR: &str = ".quarantine";
+
+/// Prefix of the temp files atomic writes rename into place.
+const TEMP_PREFIX: &str = ".tmp";
+
+/// Younger temp files may belong to a write still in progress elsewhere
+/// (key files are written without the store lock), so they are left alone.
+const ORPHAN_AGE: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Blob format written by this version. Version 1 binds each ciphertext to
+/// its encoded key name and format version as AES-GCM associated data, so blobs
+/// cannot be swapped between keys; version 0 blobs were sealed without it.
+pub const BLOB_VERSION: u32 = 1;
+
+/// Key hierarchy of a store. A random master key is wrapped once per
+/// key-encryption key (KEK) able to unlock the store, and the data keys
+/// (DEKs) that seal blobs are wrapped under the master key. Rotating a KEK
+/// replaces the master key and rewraps the DEKs without touching blobs;
+/// rotating a DEK only needs the master key, whichever slot unlocked it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Header {
+    /// Master key wrapped under each KEK, by KEK id.
+    slots: BTreeMap<String, WrappedKey>,
+    /// DEK used for new writes.
+    active: String,
+    /// DEKs blobs may still be sealed with, wrapped under the master key.
+    deks: BTreeMap<String, WrappedKey>,
+    /// Recovery codes and key backups made for this store. Codes have a
+    /// slot of their own; backup files carry their wrapped master key.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    recovery: Vec<RecoveryRecord>,
+    /// Oldest blob format accepted. Raised by `migrate` once every blob is
+    /// re-sealed, so an old unbound blob cannot be planted afterwards.
+    #[serde(default)]
+    blob_format: u32,
+    /// Id of the current master key; `rotate_kek` replaces both. Empty in
+    /// headers written before master keys were rotated.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    master_id: String,
+    /// Provider keys adopted as DEKs from a store that predates the header.
+    /// Their blobs are re-sealed and the keys dropped right after adoption
+    /// (or by `migrate` if that was interrupted).
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    legacy: BTreeSet<String>,
+}
+
+/// Keys unwrapped by this process. Ids never change meaning, so entries
+/// stay valid while other processes rotate.
+#[derive(Default)]
+struct Unlocked {
+    /// Master key with its `Header::master_id`.
+    master: Option<(String, [u8; 32])>,
+    deks: HashMap<String, KeyMaterial>,
+    /// `Header::blob_format` as last read; it only ever increases.
+    blob_format: Option<u32>,
+}
+
+/// Outcome of [`EncryptedFileStore::rotate_key`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RotationReport {
+    /// Id of the key every blob is now encrypted with.
+    pub key_id: String,
+    /// Blobs re-encrypted by this run.
+    pub reencrypted: usize,
+    /// Whether this run finished an interrupted rotation.
+    pub resumed: bool,
+    /// Old data key ids dropped from the header.
+    pub retired: Vec<String>,
+}
+
+/// Outcome of [`EncryptedFileStore::migrate`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationReport {
+    /// Blobs re-sealed in the current format.
+    pub resealed: usize,
+    /// Blob format the store now requires.
+    pub format: u32,
+}
+
+/// Outcome of [`EncryptedFileStore::rotate_kek`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KekRotationReport {
+    /// Id of the KEK that now unlocks the store.
+    pub key_id: String,
+    /// Previous KEK, removed from the header and the key provider.
+    pub retired: String,
+    /// Other providers' slots, recovery codes and key backups. They unlock
+    /// the replaced master key, so they no longer work and were dropped.
+    pub invalidated: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RotationMarker {
+    /// Data key that was active before the rotation started.
+    previous: String,
+}
+
+/// Outcome of [`EncryptedFileStore::transfer_to`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransferReport {
+    /// Blobs copied into the target backend.
+    pub moved: usize,
+    /// Blobs the target already held at the same or a newer revision.
+    pub skipped: usize,
+}
+
+/// A blob that failed [`EncryptedFileStore::check`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CorruptBlob {
+    /// Key the blob is stored under.
+    pub key: String,
+    /// Why it failed: unreadable, undecryptable or rejected by the validator.
+    pub problem: String,
+}
+
+/// Outcome of [`EncryptedFileStore::check`] and [`EncryptedFileStore::repair`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CheckReport {
+    /// Blobs that decrypted and passed validation.
+    pub healthy: usize,
+    /// Blobs that did not; moved to [`QUARANTINE_DIR`] by a repair.
+    pub corrupt: Vec<CorruptBlob>,
+    /// Temp files left in the data dir by writes interrupted before their
+    /// rename; deleted by a repair.
+    pub orphans: Vec<String>,
+    /// Whether the problems listed were fixed.
+    pub repaired: bool,
+}
+
+impl CheckReport {
+    pub fn is_clean(&self) -> bool {
+        self.corrupt.is_empty() && self.orphans.is_empty()
+    }
+}
+
+/// Where a store's sealed blobs live, by encoded key name (the unpadded
+/// base64url of the key). The header, lock and markers stay dot-files in the
+/// data dir whichever backend holds the blobs. Stores call mutating methods
+/// with their write lock held.
+pub trait BlobBackend: Send + Sync {
+    /// Names of every stored blob, sorted.
+    fn names(&self) -> Result<Vec<String>, SecureStoreError>;
+
+    /// The blob stored under `name`, if any.
+    fn read(&self, name: &str) -> Result<Option<StoredBlob>, SecureStoreError>;
+
+    /// Store blobs (`Some`) and remove them (`None`) in order, as one unit:
+    /// after a crash readers see none of the changes or, once
+    /// [`recover`](Self::recover) has run, all of them.
+    fn apply(&self, changes: Vec<(String, Option<StoredBlob>)>) -> Result<(), SecureStoreError>;
+
+    /// Finish a batch interrupted by a crash. The store calls this whenever
+    /// it takes its write lock; backends with native transactions have
+    /// nothing to do.
+    fn recover(&self) -> Result<(), SecureStoreError> {
+        Ok(())
+    }
+
+    /// Whether a batch is in flight or was interrupted. Readers then wait
+    /// for the write lock (recovering if needed) so they never see half of it.
+    fn batch_pending(&self) -> bool {
+        false
+    }
+
+    fn contains(&self, name: &str) -> Result<bool, SecureStoreError> {
+        Ok(self.read(name)?.is_some())
+    }
+
+    fn write(&self, name: &str, blob: StoredBlob) -> Result<(), SecureStoreError> {
+        self.apply(vec![(name.to_string(), Some(blob))])
+    }
+
+    fn remove(&self, name: &str) -> Result<(), SecureStoreError> {
+        self.apply(vec![(name.to_string(), None)])
+    }
+
+    /// Move a blob out of the store into the file `target`, keeping it for
+    /// inspection. Backends whose blobs can be stored but not read back
+    /// should override this to move the raw bytes.
+    fn quarantine(&self, name: &str, target: &Path) -> Result<(), SecureStoreError> {
+        if let Some(blob) = self.read(name)? {
+            write_json(target, &blob)?;
+        }
+        self.remove(name)
+    }
+}
+
+/// One JSON file per blob in the data dir, replaced atomically on write.
+pub struct FileBlobs {
+    root: PathBuf,
+}
+
+impl FileBlobs {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Replace or remove each blob file, then sync the data dir once so
+    /// every rename and removal is on disk before this returns.
+    fn write_each(
+        &self,
+        changes: Vec<(String, Option<StoredBlob>)>,
+    ) -> Result<(), SecureStoreError> {
+        for (name, blob) in changes {
+            let path = self.root.join(&name);
+            match blob {
+                Some(blob) => write_blob(&path, &blob)?,
+                None => match fs::remove_file(path) {
+                    Ok(_) => {}
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(err) => return Err(storage_err(err)),
+                },
+            }
+        }
+        sync_dir(&self.root)
+    }
+}
+
+/// Changes of a batch, written in full before any blob file is touched.
+#[derive(Serialize, Deserialize)]
+struct BatchManifest {
+    changes: Vec<(String, Option<StoredBlob>)>,
+}
+
+impl BlobBackend for FileBlobs {
+    /// Skips dot-files (lock, header, markers, temp files) and anything
+    /// else whose name is not an encoded key.
+    fn names(&self) -> Result<Vec<String>, SecureStoreError> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(storage_err(err)),
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(storage_err)?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') || unsanitize_key(&name).is_none() {
+                continue;
+            }
+            if entry.file_type().map_err(storage_err)?.is_file() {
+                names.push(name);
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn read(&self, name: &str) -> Result<Option<StoredBlob>, SecureStoreError> {
+        match read_blob(&self.root.join(name)) {
+            Ok(blob) => Ok(Some(blob)),
+            Err(SecureStoreError::NotFound { .. }) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Single changes replace one file atomically. Larger batches are first
+    /// written to a `.batch` manifest holding every sealed blob; it is only
+    /// removed once all files are in place, so [`recover`](Self::recover)
+    /// can replay an interrupted batch.
+    fn apply(&self, changes: Vec<(String, Option<StoredBlob>)>) -> Result<(), SecureStoreError> {
+        if changes.len() < 2 {
+            return self.write_each(changes);
+        }
+        // The manifest must be on disk (contents and directory entry) before
+        // the first blob changes; `write_json` syncs both.
+        write_json(&self.root.join(BATCH_FILE), &BatchManifest { changes })?;
+        self.recover()
+    }
+
+    fn recover(&self) -> Result<(), SecureStoreError> {
+        let path = self.root.join(BATCH_FILE);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(storage_err(err)),
+        };
+        let manifest: BatchManifest =
+            serde_json::from_slice(&bytes).map_err(|e| SecureStoreError::Storage {
+                reason: format!("batch manifest: {e}"),
+            })?;
+        // Replaying is idempotent: every change carries the complete blob.
+        // The blobs are durable before the manifest goes.
+        self.write_each(manifest.changes)?;
+        fs::remove_file(&path).map_err(storage_err)?;
+        sync_dir(&self.root)
+    }
+
+    fn batch_pending(&self) -> bool {
+        self.root.join(BATCH_FILE).exists()
+    }
 
-/// AES-GCM encrypted file-backed store implementing the shared `SecureStore` contract.
-/// Keys are persisted via a `KeyProvider` (OS keyring in production).
-pub struct EncryptedFileStore<P: KeyProvider> {
+    /// Renames the file, so blobs that no longer parse are kept byte for byte.
+    fn quarantine(&self, name: &str, target: &Path) -> Result<(), SecureStoreError> {
+        fs::rename(self.root.join(name), target).map_err(storage_err)
+    }
+
+    fn contains(&self, name: &str) -> Result<bool, SecureStoreError> {
+        match fs::metadata(self.root.join(name)) {
+            Ok(meta) => Ok(meta.is_file()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(storage_err(err)),
+        }
+    }
+}
+
+/// AES-GCM encrypted store implementing the shared `SecureStore` contract.
+/// Keys are persisted via a `KeyProvider` (OS keyring in production); blobs
+/// live in a [`BlobBackend`], one file per key by default or a SQLite
+/// database ([`crate::sqlite_store::SqliteSecureStore`]).
+///
+/// Every blob carries a revision. Writes take an exclusive advisory lock on
+/// `<root>/.lock` while they check and bump it, so conditional writes
+/// (`put_if`) from concurrent processes cannot both succeed.
+///
+/// Blobs are sealed with data keys kept wrapped in `<root>/.header` (see
+/// [`Header`]); the key provider only supplies the KEK that unlocks them.
+/// Every blob records the id of its data key, so after a rotation blobs
+/// written under older keys stay readable until re-encrypted.
+pub struct EncryptedFileStore<P: KeyProvider, B: BlobBackend = FileBlobs> {
     root: PathBuf,
     key_provider: P,
+    blobs: B,
+    unlocked: Mutex<Unlocked>,
 }
 
 impl<P: KeyProvider> EncryptedFileStore<P> {
     pub fn new(root: impl Into<PathBuf>, key_provider: P) -> Self {
+        let root = root.into();
+        Self::with_backend(root.clone(), key_provider, FileBlobs::new(root))
+    }
+
+    #[cfg(test)]
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.root.join(sanitize_key(key))
+    }
+}
+
+impl<P: KeyProvider, B: BlobBackend> EncryptedFileStore<P, B> {
+    /// A store in `root` whose blobs live in `blobs`.
+    pub fn with_backend(root: impl Into<PathBuf>, key_provider: P, blobs: B) -> Self {
         Self {
             root: root.into(),
             key_provider,
+            blobs,
+            unlocked: Mutex::new(Unlocked::default()),
         }
     }
 
-    fn path_for(&self, key: &str) -> PathBuf {
-        self.root.join(sanitize_key(key))
+    /// Data dir holding the header, lock and (for the file backend) blobs.
+    pub fn root(&self) -> &Path {
+        &self.root
     }
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-struct StoredBlob {
-    nonce: String,
-    ciphertext: String,
-}
+    /// The provider supplying this store's keys.
+    pub fn key_provider(&self) -> &P {
+        &self.key_provider
+    }
 
-#[async_trait]
-impl<P: KeyProvider> SecureStore for EncryptedFileStore<P> {
-    #[instrument(skip_all, fields(key))]
-    async fn put(&self, key: &str, value: &[u8]) -> Result<(), SecureStoreError> {
-        fs::create_dir_all(&self.root).map_err(storage_err)?;
+    /// Where this store's blobs live.
+    pub fn backend(&self) -> &B {
+        &self.blobs
+    }
 
-        let key_material =
-            self.key_provider
-                .get_or_create()
+    /// Resolve this process's keys, then wait for the store-wide write lock.
+    /// Unlocking first means a passphrase prompt or KDF run never happens
+    /// while other processes are kept waiting. The lock is released when the
+    /// returned file is dropped.
+    async fn lock(&self) -> Result<File, SecureStoreError> {
+        self.prepare_keys().await?;
+        self.flock().await
+    }
+
+    /// Unwrap the master key ahead of taking the lock, or for a store with
+    /// no header yet, fetch the KEK that will create it. Failures are left
+    /// for the locked section to report: recovery runs without a working KEK.
+    async fn prepare_keys(&self) -> Result<(), SecureStoreError> {
+        match self.read_header()? {
+            Some(header) => {
+                let _ = self.master(&header).await;
+            }
+            None => {
+                let _ = self.key_provider.get_or_create().await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Wait for the write lock on a blocking thread, so a lock held by
+    /// another process stalls this task but not the runtime, then finish any
+    /// interrupted batch.
+    async fn flock(&self) -> Result<File, SecureStoreError> {
+        fs::create_dir_all(&self.root).map_err(storage_err)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(self.root.join(LOCK_FILE))
+            .map_err(storage_err)?;
+        let file =
+            tokio::task::spawn_blocking(move || FileExt::lock_exclusive(&file).map(|()| file))
                 .await
+                .map_err(storage_err)?
                 .map_err(|e| SecureStoreError::Storage {
-                    reason: format!("key provider: {e}"),
+                    reason: format!("lock failed: {e}"),
                 })?;
+        self.blobs.recover()?;
+        Ok(file)
+    }
+
+    /// Wait out (or finish) a pending batch before reading.
+    async fn settle(&self) -> Result<(), SecureStoreError> {
+        if self.blobs.batch_pending() {
+            drop(self.flock().await?);
+        }
+        Ok(())
+    }
+
+    fn unlocked(&self) -> Result<std::sync::MutexGuard<'_, Unlocked>, SecureStoreError> {
+        self.unlocked
+            .lock()
+            .map_err(|err| SecureStoreError::Storage {
+                reason: format!("lock poisoned: {err}"),
+            })
+    }
+
+    fn read_header(&self) -> Result<Option<Header>, SecureStoreError> {
+        match fs::read(self.root.join(HEADER_FILE)) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes)
+                    .map(Some)
+                    .map_err(|e| SecureStoreError::Storage {
+                        reason: format!("header: {e}"),
+                    })
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(storage_err(err)),
+        }
+    }
+
+    fn write_header(&self, header: &Header) -> Result<(), SecureStoreError> {
+        write_json(&self.root.join(HEADER_FILE), header)
+    }
+
+    /// The header, creating it if the store has none. Callers hold the lock.
+    ///
+    /// Stores written before envelope encryption sealed blobs directly with
+    /// the provider's keys. Those keys are adopted as data keys just long
+    /// enough to re-seal their blobs under a fresh one, so the KEK never
+    /// stays in use as a data key.
+    async fn header_locked(&self) -> Result<Header, SecureStoreError> {
+        if let Some(header) = self.read_header()? {
+            return Ok(header);
+        }
+        let kek = self.key_provider.get_or_create().await.map_err(key_err)?;
+        let master = generate_key(String::new()).bytes;
+
+        let mut legacy = BTreeSet::new();
+        for name in self.blobs.names()? {
+            if let Some(blob) = self.blobs.read(&name)? {
+                legacy.insert(blob.key_id);
+            }
+        }
+        let mut deks = BTreeMap::new();
+        for id in &legacy {
+            let key = self.key_provider.get(id).await.map_err(key_err)?;
+            let key = key.ok_or_else(|| SecureStoreError::Storage {
+                reason: format!("key `{id}` is no longer available"),
+            })?;
+            deks.insert(id.clone(), wrap_key(&master, &key.bytes).map_err(key_err)?);
+        }
+        let dek = generate_key(new_dek_id());
+        deks.insert(
+            dek.id.clone(),
+            wrap_key(&master, &dek.bytes).map_err(key_err)?,
+        );
+        let header = Header {
+            slots: BTreeMap::from([(
+                kek.id.clone(),
+                wrap_key(&kek.bytes, &master).map_err(key_err)?,
+            )]),
+            active: dek.id,
+            deks,
+            recovery: Vec::new(),
+            // A store with no blobs yet can require the current format from the start.
+            blob_format: if legacy.is_empty() { BLOB_VERSION } else { 0 },
+            master_id: new_master_id(),
+            legacy,
+        };
+        self.write_header(&header)?;
+        self.unlocked()?.master = Some((header.master_id.clone(), master));
+        if header.legacy.is_empty() {
+            return Ok(header);
+        }
+        // Boxed: re-sealing goes through this function again.
+        Box::pin(self.reseal_legacy()).await?;
+        self.existing_header()
+    }
+
+    /// The header for readers, created under the lock on first use.
+    async fn header(&self) -> Result<Header, SecureStoreError> {
+        if let Some(header) = self.read_header()? {
+            return Ok(header);
+        }
+        let _lock = self.lock().await?;
+        self.header_locked().await
+    }
+
+    /// Unwrap the master key with the provider's active KEK, falling back
+    /// to older ones it still holds (e.g. after an interrupted KEK rotation).
+    async fn master(&self, header: &Header) -> Result<[u8; 32], SecureStoreError> {
+        match &self.unlocked()?.master {
+            Some((id, master)) if *id == header.master_id => return Ok(*master),
+            _ => {}
+        }
+        let active = self.key_provider.get_or_create().await.map_err(key_err)?;
+        let mut master = header
+            .slots
+            .get(&active.id)
+            .and_then(|slot| unwrap_key(&active.bytes, slot).ok());
+        for (id, slot) in header.slots.iter().filter(|(id, _)| **id != active.id) {
+            if master.is_some() {
+                break;
+            }
+            if let Some(kek) = self.key_provider.get(id).await.map_err(key_err)? {
+                master = unwrap_key(&kek.bytes, slot).ok();
+            }
+        }
+        let master = master.ok_or_else(|| SecureStoreError::Storage {
+            reason: "none of the available keys unlocks this store".to_string(),
+        })?;
+        self.unlocked()?.master = Some((header.master_id.clone(), master));
+        Ok(master)
+    }
+
+    /// A data key by id, which may predate the active one.
+    async fn dek(&self, id: &str) -> Result<KeyMaterial, SecureStoreError> {
+        if let Some(key) = self.unlocked()?.deks.get(id) {
+            return Ok(key.clone());
+        }
+        let header = self.header().await?;
+        let wrapped = header
+            .deks
+            .get(id)
+            .ok_or_else(|| SecureStoreError::Storage {
+                reason: format!("key `{id}` is no longer available"),
+            })?;
+        let master = self.master(&header).await?;
+        let key = KeyMaterial {
+            id: id.to_string(),
+            bytes: unwrap_key(&master, wrapped).map_err(key_err)?,
+        };
+        self.unlocked()?.deks.insert(id.to_string(), key.clone());
+        Ok(key)
+    }
 
-        let cipher = build_cipher(&key_material)?;
+    /// Seal `value` for the blob named `name` with the active data key.
+    /// Callers hold the lock.
+    async fn seal(
+        &self,
+        name: &str,
+        value: &[u8],
+        revision: u64,
+    ) -> Result<StoredBlob, SecureStoreError> {
+        let header = self.header_locked().await?;
+        let key = self.dek(&header.active).await?;
+        let cipher = build_cipher(&key)?;
         let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let aad = associated_data(BLOB_VERSION, name);
         let ciphertext = cipher
-            .encrypt(&nonce, value)
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: value,
+                    aad: &aad,
+                },
+            )
             .map_err(|e| SecureStoreError::Storage {
                 reason: format!("encrypt failed: {e}"),
             })?;
 
-        let blob = StoredBlob {
+        Ok(StoredBlob {
+            version: BLOB_VERSION,
             nonce: URL_SAFE_NO_PAD.encode(nonce.as_slice()),
             ciphertext: URL_SAFE_NO_PAD.encode(ciphertext),
-        };
-
-        let path = self.path_for(key);
-        write_blob(&path, &blob)
+            revision,
+            key_id: key.id,
+        })
     }
 
-    #[instrument(skip_all, fields(key))]
-    async fn get(&self, key: &str) -> Result<Vec<u8>, SecureStoreError> {
-        let path = self.path_for(key);
-        let blob = read_blob(&path)?;
-
-        let key_material =
-            self.key_provider
-                .get_or_create()
-                .await
-                .map_err(|e| SecureStoreError::Storage {
-                    reason: format!("key provider: {e}"),
-                })?;
-        let cipher = build_cipher(&key_material)?;
-
+    /// Decrypt the blob stored under `name`.
+    async fn unseal(&self, name: &str, blob: &StoredBlob) -> Result<Vec<u8>, SecureStoreError> {
+        if blob.version > BLOB_VERSION {
+            return Err(SecureStoreError::Storage {
+                reason: format!(
+                    "blob format v{} is newer than this frodo supports",
+                    blob.version
+                ),
+            });
+        }
+        if blob.version < self.blob_format().await? {
+            return Err(SecureStoreError::Storage {
+                reason: format!(
+                    "unbound v{} blob in a migrated store; it may have been planted",
+                    blob.version
+                ),
+            });
+        }
+        let cipher = build_cipher(&self.dek(&blob.key_id).await?)?;
         let nonce_bytes =
             URL_SAFE_NO_PAD
-                .decode(blob.nonce)
+                .decode(&blob.nonce)
                 .map_err(|e| SecureStoreError::Storage {
                     reason: format!("nonce decode failed: {e}"),
                 })?;
@@ -98,40 +660,764 @@ impl<P: KeyProvider> SecureStore for EncryptedFileStore<P> {
 
         let ciphertext =
             URL_SAFE_NO_PAD
-                .decode(blob.ciphertext)
+                .decode(&blob.ciphertext)
                 .map_err(|e| SecureStoreError::Storage {
                     reason: format!("ciphertext decode failed: {e}"),
                 })?;
 
+        let aad = associated_data(blob.version, name);
         cipher
-            .decrypt(nonce, ciphertext.as_ref())
+            .decrypt(
+                nonce,
+                Payload {
+                    msg: &ciphertext,
+                    aad: &aad,
+                },
+            )
             .map_err(|e| SecureStoreError::Storage {
                 reason: format!("decrypt failed: {e}"),
             })
     }
 
+    /// Oldest blob format this store accepts.
+    pub async fn blob_format(&self) -> Result<u32, SecureStoreError> {
+        if let Some(format) = self.unlocked()?.blob_format {
+            return Ok(format);
+        }
+        let format = self.header().await?.blob_format;
+        self.unlocked()?.blob_format = Some(format);
+        Ok(format)
+    }
+
+    /// Re-seal every blob written in an older format or under a provider
+    /// key adopted as a data key, then drop those keys and require the
+    /// current format. Safe to re-run; blobs already current are skipped.
+    pub async fn migrate(&self) -> Result<MigrationReport, SecureStoreError> {
+        let _lock = self.lock().await?;
+        let resealed = self.reseal_legacy().await?;
+        Ok(MigrationReport {
+            resealed,
+            format: BLOB_VERSION,
+        })
+    }
+
+    /// The work of [`migrate`](Self::migrate). Besides `Header::legacy`,
+    /// any data key that is also a KEK slot counts as legacy: stores adopted
+    /// by older versions kept the KEK as their active data key. Callers hold
+    /// the lock.
+    async fn reseal_legacy(&self) -> Result<usize, SecureStoreError> {
+        let mut header = self.header_locked().await?;
+        let legacy: BTreeSet<String> = header
+            .deks
+            .keys()
+            .filter(|id| header.legacy.contains(*id) || header.slots.contains_key(*id))
+            .cloned()
+            .collect();
+        if legacy.contains(&header.active) {
+            self.add_dek(&mut header).await?;
+        }
+        let mut resealed = 0;
+        for name in self.blobs.names()? {
+            let Some(blob) = self.blobs.read(&name)? else {
+                continue;
+            };
+            if blob.version >= BLOB_VERSION && !legacy.contains(&blob.key_id) {
+                continue;
+            }
+            let value = self.unseal(&name, &blob).await?;
+            let sealed = self.seal(&name, &value, blob.revision).await?;
+            self.blobs.write(&name, sealed)?;
+            resealed += 1;
+        }
+        let mut header = self.header_locked().await?;
+        header.deks.retain(|id, _| !legacy.contains(id));
+        header.legacy.clear();
+        header.blob_format = BLOB_VERSION;
+        self.write_header(&header)?;
+        let mut unlocked = self.unlocked()?;
+        unlocked.deks.retain(|id, _| !legacy.contains(id));
+        unlocked.blob_format = Some(BLOB_VERSION);
+        Ok(resealed)
+    }
+
+    /// Switch to a freshly generated data key and re-encrypt every blob with
+    /// it, keeping revisions. Holds the write lock throughout; readers keep
+    /// working because old data keys are only dropped once no blob uses them.
+    ///
+    /// Progress is tracked by a `.rotation` marker: if the process dies
+    /// midway, the next call finishes the job with the same new key instead
+    /// of starting over.
+    pub async fn rotate_key(&self) -> Result<RotationReport, SecureStoreError> {
+        let _lock = self.lock().await?;
+        let mut header = self.header_locked().await?;
+        let marker_path = self.root.join(ROTATION_FILE);
+        let (marker, resumed) = match fs::read(&marker_path) {
+            Ok(bytes) => (
+                serde_json::from_slice::<RotationMarker>(&bytes).map_err(storage_err)?,
+                true,
+            ),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                let marker = RotationMarker {
+                    previous: header.active.clone(),
+                };
+                write_json(&marker_path, &marker)?;
+                (marker, false)
+            }
+            Err(err) => return Err(storage_err(err)),
+        };
+        if header.active == marker.previous {
+            // Not yet switched (possibly interrupted before the new key existed).
+            self.add_dek(&mut header).await?;
+        }
+        let active = header.active.clone();
+
+        let mut old_ids = BTreeSet::from([marker.previous]);
+        let mut reencrypted = 0;
+        for name in self.blobs.names()? {
+            let Some(blob) = self.blobs.read(&name)? else {
+                continue;
+            };
+            if blob.key_id == active {
+                continue;
+            }
+            let value = self.unseal(&name, &blob).await?;
+            let sealed = self.seal(&name, &value, blob.revision).await?;
+            self.blobs.write(&name, sealed)?;
+            old_ids.insert(blob.key_id);
+            reencrypted += 1;
+        }
+
+        fs::remove_file(&marker_path).map_err(storage_err)?;
+        old_ids.remove(&active);
+        let mut header = self.header_locked().await?;
+        header.deks.retain(|id, _| !old_ids.contains(id));
+        self.write_header(&header)?;
+        let mut unlocked = self.unlocked()?;
+        unlocked.deks.retain(|id, _| !old_ids.contains(id));
+        Ok(RotationReport {
+            key_id: active,
+            reencrypted,
+            resumed,
+            retired: old_ids.into_iter().collect(),
+        })
+    }
+
+    /// Generate a data key, make it active and persist the header.
+    async fn add_dek(&self, header: &mut Header) -> Result<(), SecureStoreError> {
+        let master = self.master(header).await?;
+        let dek = generate_key(new_dek_id());
+        header.deks.insert(
+            dek.id.clone(),
+            wrap_key(&master, &dek.bytes).map_err(key_err)?,
+        );
+        header.active = dek.id.clone();
+        self.write_header(header)?;
+        self.unlocked()?.deks.insert(dek.id.clone(), dek);
+        Ok(())
+    }
+
+    /// Replace the provider's KEK together with the master key it unlocks.
+    /// Every DEK is rewrapped under a fresh master key, so the old KEK and an
+    /// old copy of the header cannot unwrap data keys created afterwards.
+    /// Blobs are untouched, so this is cheap; after a leak, follow up with
+    /// `rotate_key` to re-encrypt the data as well.
+    ///
+    /// Slots of other providers and recovery material unlock the old master
+    /// key only, so they are dropped and listed in the report.
+    pub async fn rotate_kek(&self) -> Result<KekRotationReport, SecureStoreError> {
+        let _lock = self.lock().await?;
+        let header = self.header_locked().await?;
+        let old_master = self.master(&header).await?;
+        let previous = self.key_provider.get_or_create().await.map_err(key_err)?;
+        let master = generate_key(String::new()).bytes;
+        let mut deks = BTreeMap::new();
+        for (id, wrapped) in &header.deks {
+            let dek = unwrap_key(&old_master, wrapped).map_err(key_err)?;
+            deks.insert(id.clone(), wrap_key(&master, &dek).map_err(key_err)?);
+        }
+        let invalidated: BTreeSet<String> = header
+            .slots
+            .keys()
+            .filter(|id| **id != previous.id)
+            .chain(header.recovery.iter().map(|record| &record.id))
+            .cloned()
+            .collect();
+
+        let kek = self.key_provider.rotate().await.map_err(key_err)?;
+        let header = Header {
+            slots: BTreeMap::from([(
+                kek.id.clone(),
+                wrap_key(&kek.bytes, &master).map_err(key_err)?,
+            )]),
+            deks,
+            recovery: Vec::new(),
+            master_id: new_master_id(),
+            ..header
+        };
+        self.write_header(&header)?;
+        self.unlocked()?.master = Some((header.master_id.clone(), master));
+        self.key_provider
+            .retire(&previous.id)
+            .await
+            .map_err(key_err)?;
+        Ok(KekRotationReport {
+            key_id: kek.id,
+            retired: previous.id,
+            invalidated: invalidated.into_iter().collect(),
+        })
+    }
+
+    /// Let `other` unlock this store too, by wrapping the master key under
+    /// its KEK. Returns the id of that KEK.
+    pub async fn add_unlock<Q: KeyProvider>(&self, other: &Q) -> Result<String, SecureStoreError> {
+        let _lock = self.lock().await?;
+        let mut header = self.header_locked().await?;
+        let master = self.master(&header).await?;
+        let kek = other.get_or_create().await.map_err(key_err)?;
+        header.slots.insert(
+            kek.id.clone(),
+            wrap_key(&kek.bytes, &master).map_err(key_err)?,
+        );
+        self.write_header(&header)?;
+        Ok(kek.id)
+    }
+
+    /// Generate a recovery code that unlocks the store on its own, e.g. after
+    /// the keychain is wiped. The code is only returned here; the header
+    /// keeps a salt and the master key wrapped under the derived key.
+    pub async fn add_recovery_code(&self) -> Result<RecoveryCode, SecureStoreError> {
+        let code = RecoveryCode::generate();
+        let salt = random_salt();
+        let kek = CODE_KDF.derive(code.secret(), &salt).map_err(key_err)?;
+        let _lock = self.lock().await?;
+        let mut header = self.header_locked().await?;
+        let master = self.master(&header).await?;
+        let id = format!("r-{:016x}", OsRng.next_u64());
+        header
+            .slots
+            .insert(id.clone(), wrap_key(&kek, &master).map_err(key_err)?);
+        header.recovery.push(RecoveryRecord {
+            id,
+            kind: RecoveryKind::Code,
+            created_at: unix_now(),
+            kdf: Some(CODE_KDF),
+            salt: Some(URL_SAFE_NO_PAD.encode(salt)),
+        });
+        self.write_header(&header)?;
+        Ok(code)
+    }
+
+    /// Export the master key wrapped under `passphrase`, for a backup file.
+    pub async fn export_key(
+        &self,
+        passphrase: &str,
+        kdf: KdfParams,
+    ) -> Result<KeyBackup, SecureStoreError> {
+        let salt = random_salt();
+        let kek = kdf.derive(passphrase, &salt).map_err(key_err)?;
+        let _lock = self.lock().await?;
+        let mut header = self.header_locked().await?;
+        let master = self.master(&header).await?;
+        let backup = KeyBackup {
+            id: format!("f-{:016x}", OsRng.next_u64()),
+            kdf,
+            salt: URL_SAFE_NO_PAD.encode(salt),
+            master: wrap_key(&kek, &master).map_err(key_err)?,
+            created_at: unix_now(),
+        };
+        header.recovery.push(RecoveryRecord {
+            id: backup.id.clone(),
+            kind: RecoveryKind::File,
+            created_at: backup.created_at,
+            kdf: None,
+            salt: None,
+        });
+        self.write_header(&header)?;
+        Ok(backup)
+    }
+
+    /// Regain access with a recovery code: the provider's current KEK gets
+    /// a slot, so later commands unlock normally. Returns that KEK's id.
+    pub async fn recover_with_code(&self, code: &RecoveryCode) -> Result<String, SecureStoreError> {
+        // The slow code KDF runs before the lock is taken.
+        let header = self.existing_header()?;
+        let mut master = None;
+        let mut slot_id = None;
+        for record in header
+            .recovery
+            .iter()
+            .filter(|r| r.kind == RecoveryKind::Code)
+        {
+            let (Some(kdf), Some(salt), Some(slot)) =
+                (record.kdf, &record.salt, header.slots.get(&record.id))
+            else {
+                continue;
+            };
+            let salt = URL_SAFE_NO_PAD.decode(salt).map_err(storage_err)?;
+            let kek = kdf.derive(code.secret(), &salt).map_err(key_err)?;
+            if let Ok(key) = unwrap_key(&kek, slot) {
+                master = Some(key);
+                slot_id = Some(record.id.clone());
+                break;
+            }
+        }
+        let master = master.ok_or_else(|| SecureStoreError::Storage {
+            reason: "recovery code does not match this store".to_string(),
+        })?;
+        let _lock = self.lock().await?;
+        let header = self.existing_header()?;
+        // A KEK rotation meanwhile replaced the master key and dropped the code.
+        if !slot_id.is_some_and(|id| header.slots.contains_key(&id)) {
+            return Err(SecureStoreError::Storage {
+                reason: "recovery code was invalidated by a key rotation".to_string(),
+            });
+        }
+        self.grant(header, master).await
+    }
+
+    /// Regain access with a key backup file and its passphrase.
+    pub async fn recover_with_backup(
+        &self,
+        backup: &KeyBackup,
+        passphrase: &str,
+    ) -> Result<String, SecureStoreError> {
+        let salt = URL_SAFE_NO_PAD.decode(&backup.salt).map_err(storage_err)?;
+        let kek = backup.kdf.derive(passphrase, &salt).map_err(key_err)?;
+        let master = unwrap_key(&kek, &backup.master).map_err(|_| SecureStoreError::Storage {
+            reason: "wrong passphrase for key backup".to_string(),
+        })?;
+        let _lock = self.lock().await?;
+        let header = self.existing_header()?;
+        let belongs = header
+            .deks
+            .get(&header.active)
+            .is_some_and(|dek| unwrap_key(&master, dek).is_ok());
+        if !belongs {
+            return Err(SecureStoreError::Storage {
+                reason: "key backup belongs to a different store".to_string(),
+            });
+        }
+        self.grant(header, master).await
+    }
+
+    /// Recovery material generated for this store so far.
+    pub fn recovery_records(&self) -> Result<Vec<RecoveryRecord>, SecureStoreError> {
+        Ok(self
+            .read_header()?
+            .map(|header| header.recovery)
+            .unwrap_or_default())
+    }
+
+    /// Recovery needs the header of the store being recovered, never a new one.
+    fn existing_header(&self) -> Result<Header, SecureStoreError> {
+        self.read_header()?
+            .ok_or_else(|| SecureStoreError::Storage {
+                reason: format!(
+                    "no key header in {}; copy the whole data dir first",
+                    self.root.display()
+                ),
+            })
+    }
+
+    /// Give the provider's KEK a slot for `master`. Callers hold the lock.
+    async fn grant(
+        &self,
+        mut header: Header,
+        master: [u8; 32],
+    ) -> Result<String, SecureStoreError> {
+        let kek = self.key_provider.get_or_create().await.map_err(key_err)?;
+        header.slots.insert(
+            kek.id.clone(),
+            wrap_key(&kek.bytes, &master).map_err(key_err)?,
+        );
+        self.write_header(&header)?;
+        self.unlocked()?.master = Some((header.master_id.clone(), master));
+        Ok(kek.id)
+    }
+
+    /// Move every blob into `target`, another backend over the same data
+    /// dir (e.g. from files into SQLite). Both share this store's header and
+    /// name-bound associated data, so ciphertexts are copied as they are
+    /// after checking that each one decrypts. The batch is written to
+    /// `target` before anything is removed here, and blobs `target` already
+    /// holds at the same or a newer revision (left by an interrupted run) are
+    /// kept, so re-running after a crash is safe.
+    pub async fn transfer_to<C: BlobBackend>(
+        &self,
+        target: &C,
+    ) -> Result<TransferReport, SecureStoreError> {
+        self.transfer_then(target, || Ok(())).await
+    }
+
+    /// Like [`transfer_to`](Self::transfer_to), then run `finish` before
+    /// the lock is released, e.g. to delete the emptied source backend
+    /// before another process can open it and write there.
+    pub async fn transfer_then<C: BlobBackend>(
+        &self,
+        target: &C,
+        finish: impl FnOnce() -> Result<(), SecureStoreError>,
+    ) -> Result<TransferReport, SecureStoreError> {
+        let _lock = self.lock().await?;
+        self.header_locked().await?;
+        let names = self.blobs.names()?;
+        let mut changes = Vec::new();
+        let mut skipped = 0;
+        for name in &names {
+            let Some(blob) = self.blobs.read(name)? else {
+                continue;
+            };
+            self.unseal(name, &blob).await?;
+            match target.read(name)? {
+                Some(existing) if existing.revision >= blob.revision => skipped += 1,
+                _ => changes.push((name.clone(), Some(blob))),
+            }
+        }
+        let moved = changes.len();
+        target.apply(changes)?;
+        self.blobs
+            .apply(names.into_iter().map(|name| (name, None)).collect())?;
+        finish()?;
+        Ok(TransferReport { moved, skipped })
+    }
+
+    /// Every key with its decrypted value, read under the lock so writers
+    /// cannot interleave, e.g. for a full backup. Sorted by encoded name.
+    pub async fn snapshot(&self) -> Result<Vec<(String, Vec<u8>)>, SecureStoreError> {
+        let _lock = self.lock().await?;
+        self.header_locked().await?;
+        let mut items = Vec::new();
+        for name in self.blobs.names()? {
+            let (Some(key), Some(blob)) = (unsanitize_key(&name), self.blobs.read(&name)?) else {
+                continue;
+            };
+            items.push((key, self.unseal(&name, &blob).await?));
+        }
+        Ok(items)
+    }
+
+    /// Read and decrypt every blob, passing each plaintext to `validate`
+    /// (which knows what the key should hold), and look for temp files
+    /// orphaned by interrupted writes. Fails only if the store cannot be
+    /// unlocked at all; individual bad blobs are reported.
+    pub async fn check(
+        &self,
+        validate: impl Fn(&str, &[u8]) -> Result<(), String>,
+    ) -> Result<CheckReport, SecureStoreError> {
+        self.scan(validate, false).await
+    }
+
+    /// Like [`check`](Self::check), then move corrupt blobs into
+    /// `<root>/.quarantine/` and delete orphaned temp files, in the same
+    /// locked pass so nothing written meanwhile is touched.
+    pub async fn repair(
+        &self,
+        validate: impl Fn(&str, &[u8]) -> Result<(), String>,
+    ) -> Result<CheckReport, SecureStoreError> {
+        self.scan(validate, true).await
+    }
+
+    async fn scan(
+        &self,
+        validate: impl Fn(&str, &[u8]) -> Result<(), String>,
+        repair: bool,
+    ) -> Result<CheckReport, SecureStoreError> {
+        let _lock = self.lock().await?;
+        let header = self.header_locked().await?;
+        // A store that does not unlock is a key problem, not corruption.
+        self.master(&header).await?;
+
+        let mut report = CheckReport {
+            repaired: repair,
+            ..CheckReport::default()
+        };
+        let mut bad = Vec::new();
+        for name in self.blobs.names()? {
+            let key = unsanitize_key(&name).unwrap_or_else(|| name.clone());
+            let problem = match self.blobs.read(&name) {
+                Ok(None) => continue,
+                Err(err) => Some(format!("unreadable: {err}")),
+                Ok(Some(blob)) => match self.unseal(&name, &blob).await {
+                    Err(err) => Some(format!("does not decrypt: {err}")),
+                    Ok(value) => validate(&key, &value).err(),
+                },
+            };
+            match problem {
+                None => report.healthy += 1,
+                Some(problem) => {
+                    bad.push(name);
+                    report.corrupt.push(CorruptBlob { key, problem });
+                }
+            }
+        }
+        let orphans = self.orphaned_temp_files()?;
+        report.orphans = orphans
+            .iter()
+            .map(|path| {
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+
+        if repair {
+            let dir = self.root.join(QUARANTINE_DIR);
+            if !bad.is_empty() {
+                fs::create_dir_all(&dir).map_err(storage_err)?;
+            }
+            let stamp = unix_now();
+            for name in &bad {
+                self.blobs
+                    .quarantine(name, &dir.join(format!("{name}.{stamp}")))?;
+            }
+            for path in &orphans {
+                match fs::remove_file(path) {
+                    Ok(()) => {}
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(err) => return Err(storage_err(err)),
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// `.tmp*` files in the data dir older than [`ORPHAN_AGE`].
+    fn orphaned_temp_files(&self) -> Result<Vec<PathBuf>, SecureStoreError> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(storage_err(err)),
+        };
+        let mut orphans = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(storage_err)?;
+            if !entry.file_name().to_string_lossy().starts_with(TEMP_PREFIX) {
+                continue;
+            }
+            let meta = entry.metadata().map_err(storage_err)?;
+            let age = meta
+                .modified()
+                .ok()
+                .and_then(|at| at.elapsed().ok())
+                .unwrap_or_default();
+            if meta.is_file() && age >= ORPHAN_AGE {
+                orphans.push(entry.path());
+            }
+        }
+        orphans.sort();
+        Ok(orphans)
+    }
+}
+
+/// A sealed value as a backend stores it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredBlob {
+    /// Format version (see [`BLOB_VERSION`]); absent in unbound older blobs.
+    #[serde(default)]
+    pub(crate) version: u32,
+    pub(crate) nonce: String,
+    pub(crate) ciphertext: String,
+    /// Write counter used for optimistic concurrency; absent in older blobs.
+    #[serde(default)]
+    pub(crate) revision: u64,
+    /// Key that sealed this blob; blobs from before rotation used the default key.
+    #[serde(default = "default_key_id")]
+    pub(crate) key_id: String,
+}
+
+fn default_key_id() -> String {
+    DEFAULT_KEY_ID.to_string()
+}
+
+/// AAD binding a ciphertext to its blob name; empty for unbound v0 blobs.
+fn associated_data(version: u32, name: &str) -> Vec<u8> {
+    match version {
+        0 => Vec::new(),
+        v => format!("frodo/v{v}/{name}").into_bytes(),
+    }
+}
+
+#[async_trait]
+impl<P: KeyProvider, B: BlobBackend> SecureStore for EncryptedFileStore<P, B> {
+    #[instrument(skip_all, fields(key))]
+    async fn put(&self, key: &str, value: &[u8]) -> Result<(), SecureStoreError> {
+        let name = sanitize_key(key);
+        let _lock = self.lock().await?;
+        // An unreadable blob is overwritten rather than blocking the write.
+        let revision = self
+            .blobs
+            .read(&name)
+            .ok()
+            .flatten()
+            .map_or(0, |b| b.revision)
+            + 1;
+        let blob = self.seal(&name, value, revision).await?;
+        self.blobs.write(&name, blob)
+    }
+
+    #[instrument(skip_all, fields(key))]
+    async fn get(&self, key: &str) -> Result<Vec<u8>, SecureStoreError> {
+        self.get_versioned(key).await.map(|v| v.value)
+    }
+
     #[instrument(skip_all, fields(key))]
     async fn delete(&self, key: &str) -> Result<(), SecureStoreError> {
-        let path = self.path_for(key);
-        match fs::remove_file(path) {
-            Ok(_) => Ok(()),
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
-            Err(err) => Err(storage_err(err)),
+        let _lock = self.flock().await?;
+        self.blobs.remove(&sanitize_key(key))
+    }
+
+    #[instrument(skip_all, fields(key))]
+    async fn put_if(
+        &self,
+        key: &str,
+        value: &[u8],
+        expected: Option<u64>,
+    ) -> Result<u64, SecureStoreError> {
+        let name = sanitize_key(key);
+        let _lock = self.lock().await?;
+        let actual = self.blobs.read(&name)?.map(|b| b.revision);
+        if actual != expected {
+            return Err(SecureStoreError::Conflict {
+                key: key.to_string(),
+                expected,
+                actual,
+            });
+        }
+        let revision = actual.unwrap_or(0) + 1;
+        let blob = self.seal(&name, value, revision).await?;
+        self.blobs.write(&name, blob)?;
+        Ok(revision)
+    }
+
+    #[instrument(skip_all, fields(key))]
+    async fn get_versioned(&self, key: &str) -> Result<Versioned, SecureStoreError> {
+        let name = sanitize_key(key);
+        // Writers replace blobs atomically, so reads need no lock.
+        self.settle().await?;
+        // Adopting a store from before the header re-seals its blobs, so
+        // that happens before one is read.
+        self.header().await?;
+        let blob = self
+            .blobs
+            .read(&name)?
+            .ok_or_else(|| SecureStoreError::NotFound {
+                key: key.to_string(),
+            })?;
+        let value = self.unseal(&name, &blob).await?;
+        Ok(Versioned {
+            value,
+            revision: blob.revision,
+        })
+    }
+
+    #[instrument(skip_all, fields(prefix))]
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, SecureStoreError> {
+        // Blob names are the base64url of the key, so listing decodes them back.
+        self.settle().await?;
+        let mut keys: Vec<String> = self
+            .blobs
+            .names()?
+            .iter()
+            .filter_map(|name| unsanitize_key(name))
+            .filter(|key| key.starts_with(prefix))
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    #[instrument(skip_all, fields(key))]
+    async fn exists(&self, key: &str) -> Result<bool, SecureStoreError> {
+        self.settle().await?;
+        self.blobs.contains(&sanitize_key(key))
+    }
+
+    #[instrument(skip_all, fields(ops = batch.ops().len()))]
+    async fn commit(&self, batch: Batch) -> Result<(), SecureStoreError> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let _lock = self.lock().await?;
+        for op in batch.ops() {
+            if let BatchOp::Put {
+                key,
+                expected: Some(expected),
+                ..
+            } = op
+            {
+                let actual = self.blobs.read(&sanitize_key(key))?.map(|b| b.revision);
+                if actual != *expected {
+                    return Err(SecureStoreError::Conflict {
+                        key: key.clone(),
+                        expected: *expected,
+                        actual,
+                    });
+                }
+            }
         }
+        // Revisions as they will be once earlier ops in the batch apply.
+        let mut revisions: HashMap<String, Option<u64>> = HashMap::new();
+        let mut changes = Vec::new();
+        for op in batch.into_ops() {
+            let name = sanitize_key(op.key());
+            let current = match revisions.get(&name) {
+                Some(revision) => *revision,
+                None => self.blobs.read(&name).ok().flatten().map(|b| b.revision),
+            };
+            match op {
+                BatchOp::Put { value, .. } => {
+                    let revision = current.unwrap_or(0) + 1;
+                    let blob = self.seal(&name, &value, revision).await?;
+                    changes.push((name.clone(), Some(blob)));
+                    revisions.insert(name, Some(revision));
+                }
+                BatchOp::Delete { .. } => {
+                    changes.push((name.clone(), None));
+                    revisions.insert(name, None);
+                }
+            }
+        }
+        self.blobs.apply(changes)
     }
 }
 
+/// Replace a blob file; its directory entry is durable once the caller
+/// syncs the data dir.
 fn write_blob(path: &Path, blob: &StoredBlob) -> Result<(), SecureStoreError> {
+    replace_json(path, blob).map(drop)
+}
+
+/// Atomically and durably replace `path` with `value` as JSON.
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), SecureStoreError> {
+    let parent = replace_json(path, value)?;
+    sync_dir(&parent)
+}
+
+/// Write `value` to a temp file, sync it and rename it over `path`.
+/// Returns the directory whose entry the rename changed.
+fn replace_json<T: Serialize>(path: &Path, value: &T) -> Result<PathBuf, SecureStoreError> {
     let parent = path.parent().ok_or_else(|| SecureStoreError::Storage {
         reason: "invalid storage path".to_string(),
     })?;
     fs::create_dir_all(parent).map_err(storage_err)?;
 
     let mut tmp = NamedTempFile::new_in(parent).map_err(storage_err)?;
-    let json = serde_json::to_vec(blob).map_err(storage_err)?;
+    let json = serde_json::to_vec(value).map_err(storage_err)?;
     tmp.write_all(&json).map_err(storage_err)?;
     tmp.flush().map_err(storage_err)?;
+    tmp.as_file().sync_all().map_err(storage_err)?;
     tmp.persist(path).map_err(|e| storage_err(e.error))?;
+    Ok(parent.to_path_buf())
+}
+
+/// Flush a directory's entries (renames, removals) to disk. Directories
+/// cannot be opened for syncing on Windows, where this is a no-op.
+fn sync_dir(dir: &Path) -> Result<(), SecureStoreError> {
+    #[cfg(unix)]
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .map_err(storage_err)?;
+    #[cfg(not(unix))]
+    let _ = dir;
     Ok(())
 }
 
@@ -157,11 +1443,40 @@ fn build_cipher(material: &KeyMaterial) -> Result<Aes256Gcm, SecureStoreError> {
     })
 }
 
-fn sanitize_key(key: &str) -> String {
+/// File name for a key: unpadded base64url, which never starts with `.` so
+/// blobs cannot collide with the store's own dot-files.
+pub(crate) fn sanitize_key(key: &str) -> String {
     URL_SAFE_NO_PAD.encode(key)
 }
 
-fn storage_err<E: ToString>(err: E) -> SecureStoreError {
+/// Inverse of [`sanitize_key`]; `None` for names that are not encoded keys.
+pub(crate) fn unsanitize_key(name: &str) -> Option<String> {
+    let bytes = URL_SAFE_NO_PAD.decode(name).ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+fn random_salt() -> [u8; 16] {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+/// Random id for a data key, e.g. `d-3f2a9c1b7d4e6a05`.
+fn new_dek_id() -> String {
+    format!("d-{:016x}", OsRng.next_u64())
+}
+
+fn new_master_id() -> String {
+    format!("m-{:016x}", OsRng.next_u64())
+}
+
+fn key_err(err: KeyError) -> SecureStoreError {
+    SecureStoreError::Storage {
+        reason: format!("key provider: {err}"),
+    }
+}
+
+pub(crate) fn storage_err<E: ToString>(err: E) -> SecureStoreError {
     SecureStoreError::Storage {
         reason: err.to_string(),
     }
@@ -169,7 +1484,7 @@ fn storage_err<E: ToString>(err: E) -> SecureStoreError {
 
 #[cfg(test)]
 mod tests {
-    use frodo_core::storage::SecureStore;
+    use frodo_core::storage::{Batch, SecureStore};
 
     use super::*;
     use crate::key_provider::InMemoryKeyProvider;
@@ -206,4 +1521,450 @@ mod tests {
         let err = store.get(key).await.expect_err("should be missing");
         assert!(matches!(err, SecureStoreError::NotFound { .. }));
     }
+
+    #[tokio::test]
+    async fn lists_keys_by_decoding_file_names() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = EncryptedFileStore::new(dir.path(), InMemoryKeyProvider::default());
+        let tasks = store.namespace("tasks");
+        tasks.put("index", b"i").await.expect("put");
+        tasks.put("history/ä", b"h").await.expect("put");
+        store.put("cli/last-list", b"l").await.expect("put");
+        // Stray files that are not encoded keys are ignored.
+        std::fs::write(dir.path().join("not base64!"), b"x").expect("stray");
+
+        assert_eq!(
+            store.list("").await.expect("list"),
+            ["cli/last-list", "tasks/history/ä", "tasks/index"]
+        );
+        assert_eq!(tasks.list("").await.expect("list"), ["history/ä", "index"]);
+        assert!(tasks.exists("index").await.expect("exists"));
+        assert!(!store.exists("index").await.expect("exists"));
+
+        let err = tasks.get("missing").await.expect_err("absent");
+        assert_eq!(
+            err,
+            SecureStoreError::NotFound {
+                key: "missing".into()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn revisions_detect_stale_writers() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let keys = InMemoryKeyProvider::default();
+        // Two handles on one directory stand in for two processes.
+        let a = EncryptedFileStore::new(dir.path(), keys.clone());
+        let b = EncryptedFileStore::new(dir.path(), keys);
+
+        assert_eq!(a.put_if("k", b"v1", None).await.expect("create"), 1);
+        let seen_by_a = a.get_versioned("k").await.expect("a reads");
+        let seen_by_b = b.get_versioned("k").await.expect("b reads");
+
+        b.put_if("k", b"from-b", Some(seen_by_b.revision))
+            .await
+            .expect("b writes first");
+        let err = a
+            .put_if("k", b"from-a", Some(seen_by_a.revision))
+            .await
+            .expect_err("a is stale");
+        assert!(matches!(
+            err,
+            SecureStoreError::Conflict {
+                expected: Some(1),
+                actual: Some(2),
+                ..
+            }
+        ));
+        assert_eq!(a.get("k").await.unwrap(), b"from-b");
+    }
+
+    #[tokio::test]
+    async fn rotation_reencrypts_blobs_and_resumes_after_interruption() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = EncryptedFileStore::new(dir.path(), InMemoryKeyProvider::default());
+        store.put("a", b"alpha").await.expect("put a");
+        store.put("a", b"alpha-2").await.expect("put a again");
+        store.put("b", b"beta").await.expect("put b");
+        let original = store.read_header().unwrap().expect("header").active;
+
+        let report = store.rotate_key().await.expect("rotate");
+        assert!(!report.resumed);
+        assert_eq!(report.reencrypted, 2);
+        assert_eq!(report.retired, vec![original]);
+        let header = store.read_header().unwrap().expect("header");
+        assert_eq!(header.deks.keys().collect::<Vec<_>>(), vec![&report.key_id]);
+        let a = read_blob(&store.path_for("a")).expect("blob a");
+        assert_eq!((a.key_id.as_str(), a.revision), (report.key_id.as_str(), 2));
+        assert_eq!(store.get("a").await.unwrap(), b"alpha-2");
+
+        // Simulate a crash after the new key became active but before any
+        // blob was re-encrypted: old blobs must stay readable meanwhile.
+        let first = report.key_id;
+        write_json(
+            &dir.path().join(ROTATION_FILE),
+            &RotationMarker {
+                previous: first.clone(),
+            },
+        )
+        .expect("marker");
+        let mut header = store.read_header().unwrap().expect("header");
+        store.add_dek(&mut header).await.expect("new key");
+        let second = header.active;
+        let fresh = EncryptedFileStore::new(dir.path(), store.key_provider().clone());
+        assert_eq!(fresh.get("b").await.unwrap(), b"beta");
+
+        let report = store.rotate_key().await.expect("resume");
+        assert!(report.resumed);
+        assert_eq!(report.key_id, second);
+        assert_eq!(report.reencrypted, 2);
+        assert_eq!(report.retired, vec![first]);
+        assert!(!dir.path().join(ROTATION_FILE).exists());
+        assert_eq!(store.get("b").await.unwrap(), b"beta");
+    }
+
+    #[tokio::test]
+    async fn kek_rotation_and_extra_unlocks_leave_blobs_alone() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let keys = InMemoryKeyProvider::default();
+        let store = EncryptedFileStore::new(dir.path(), keys.clone());
+        store.put("k", b"value").await.expect("put");
+        let before = fs::read(store.path_for("k")).expect("blob");
+        let header = store.read_header().unwrap().expect("header");
+        assert!(!header.deks.contains_key(DEFAULT_KEY_ID), "fresh data key");
+
+        let report = store.rotate_kek().await.expect("rotate kek");
+        assert_eq!(report.retired, DEFAULT_KEY_ID);
+        assert_eq!(keys.get(DEFAULT_KEY_ID).await.unwrap(), None);
+        assert_eq!(fs::read(store.path_for("k")).expect("blob"), before);
+        let reopened = EncryptedFileStore::new(dir.path(), keys.clone());
+        assert_eq!(reopened.get("k").await.unwrap(), b"value");
+
+        let other = InMemoryKeyProvider::default();
+        store.add_unlock(&other).await.expect("add unlock");
+        let via_other = EncryptedFileStore::new(dir.path(), other);
+        assert_eq!(via_other.get("k").await.unwrap(), b"value");
+        let stranger = EncryptedFileStore::new(dir.path(), InMemoryKeyProvider::default());
+        assert!(matches!(
+            stranger.get("k").await,
+            Err(SecureStoreError::Storage { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn kek_rotation_replaces_the_master_key() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let keys = InMemoryKeyProvider::default();
+        let store = EncryptedFileStore::new(dir.path(), keys.clone());
+        store.put("k", b"value").await.expect("put");
+        let reader = EncryptedFileStore::new(dir.path(), keys.clone());
+        assert_eq!(reader.get("k").await.unwrap(), b"value");
+        let other = InMemoryKeyProvider::default();
+        // Both providers start with the default id; give `other` its own.
+        other.rotate().await.unwrap();
+        store.add_unlock(&other).await.expect("add unlock");
+        store.add_recovery_code().await.expect("code");
+        // What a leak exposes: the KEK and a copy of the header.
+        let old_kek = keys.get_or_create().await.unwrap();
+        let old_header = store.read_header().unwrap().expect("header");
+
+        let report = store.rotate_kek().await.expect("rotate kek");
+        assert_eq!(report.invalidated.len(), 2);
+        assert!(store.recovery_records().unwrap().is_empty());
+        let rotated = store.rotate_key().await.expect("rotate data key");
+
+        let header = store.read_header().unwrap().expect("header");
+        assert_ne!(header.master_id, old_header.master_id);
+        let old_master = unwrap_key(&old_kek.bytes, &old_header.slots[&old_kek.id]).unwrap();
+        assert!(unwrap_key(&old_master, &header.deks[&rotated.key_id]).is_err());
+
+        // A process that cached the old master key notices the new one.
+        assert_eq!(reader.get("k").await.unwrap(), b"value");
+        let via_other = EncryptedFileStore::new(dir.path(), other);
+        assert!(via_other.get("k").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn recovery_code_and_backup_restore_access_after_losing_keys() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = EncryptedFileStore::new(dir.path(), InMemoryKeyProvider::default());
+        store.put("k", b"value").await.expect("put");
+        assert!(store.recovery_records().unwrap().is_empty());
+
+        let code = store.add_recovery_code().await.expect("code");
+        let kdf = KdfParams {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let backup = store.export_key("backup pw", kdf).await.expect("backup");
+        let kinds: Vec<RecoveryKind> = store
+            .recovery_records()
+            .unwrap()
+            .iter()
+            .map(|r| r.kind)
+            .collect();
+        assert_eq!(kinds, vec![RecoveryKind::Code, RecoveryKind::File]);
+        // Recovery keeps working after the data key changes.
+        store.rotate_key().await.expect("rotate");
+
+        // A new machine: same data dir, empty keychain.
+        let new_keys = InMemoryKeyProvider::default();
+        let fresh = EncryptedFileStore::new(dir.path(), new_keys.clone());
+        assert!(fresh.get("k").await.is_err());
+        let typed = RecoveryCode::parse(&code.to_string().to_lowercase()).expect("parse");
+        fresh.recover_with_code(&typed).await.expect("recover");
+        let later = EncryptedFileStore::new(dir.path(), new_keys);
+        assert_eq!(later.get("k").await.unwrap(), b"value");
+
+        let other = EncryptedFileStore::new(dir.path(), InMemoryKeyProvider::default());
+        assert!(other.recover_with_backup(&backup, "wrong").await.is_err());
+        other
+            .recover_with_backup(&backup, "backup pw")
+            .await
+            .expect("recover from file");
+        assert_eq!(other.get("k").await.unwrap(), b"value");
+        let wrong = RecoveryCode::parse(&"0".repeat(32)).unwrap();
+        assert!(other.recover_with_code(&wrong).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn adopts_blobs_sealed_before_the_header_existed() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let keys = InMemoryKeyProvider::default();
+        let legacy = keys.get_or_create().await.unwrap();
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = build_cipher(&legacy)
+            .unwrap()
+            .encrypt(&nonce, b"old".as_slice())
+            .unwrap();
+        let path = dir.path().join(sanitize_key("k"));
+        write_json(
+            &path,
+            &serde_json::json!({
+                "nonce": URL_SAFE_NO_PAD.encode(nonce.as_slice()),
+                "ciphertext": URL_SAFE_NO_PAD.encode(ciphertext),
+                "revision": 3,
+            }),
+        )
+        .expect("legacy blob");
+
+        let legacy_blob = fs::read(&path).expect("read legacy");
+
+        let store = EncryptedFileStore::new(dir.path(), keys.clone());
+        assert_eq!(store.get("k").await.unwrap(), b"old");
+        // Adoption re-sealed the blob under a fresh data key and dropped the
+        // provider key, which stays a KEK only.
+        let header = store.read_header().unwrap().expect("header");
+        assert_ne!(header.active, DEFAULT_KEY_ID);
+        assert!(header.slots.contains_key(DEFAULT_KEY_ID));
+        assert_eq!(header.deks.keys().collect::<Vec<_>>(), vec![&header.active]);
+        assert!(header.legacy.is_empty());
+        assert_eq!(header.blob_format, BLOB_VERSION);
+        let blob = read_blob(&path).expect("blob");
+        assert_eq!(
+            (blob.version, blob.revision, &blob.key_id),
+            (BLOB_VERSION, 3, &header.active)
+        );
+        assert_eq!(store.migrate().await.expect("again").resealed, 0);
+
+        // Stores adopted by older versions used the KEK as their data key;
+        // `migrate` moves them off it.
+        let master = store.master(&header).await.unwrap();
+        let mut old_style = header.clone();
+        old_style.active = DEFAULT_KEY_ID.to_string();
+        old_style.deks.insert(
+            DEFAULT_KEY_ID.to_string(),
+            wrap_key(&master, &legacy.bytes).unwrap(),
+        );
+        store.write_header(&old_style).unwrap();
+        store.put("k", b"old").await.expect("put under the KEK");
+        assert_eq!(read_blob(&path).unwrap().key_id, DEFAULT_KEY_ID);
+        assert_eq!(store.migrate().await.expect("migrate").resealed, 1);
+        let header = store.read_header().unwrap().expect("header");
+        assert!(!header.deks.contains_key(DEFAULT_KEY_ID));
+        assert_ne!(read_blob(&path).unwrap().key_id, DEFAULT_KEY_ID);
+        assert_eq!(store.get("k").await.unwrap(), b"old");
+
+        // An unbound blob planted after migration is refused.
+        fs::write(&path, legacy_blob).expect("plant");
+        let reopened = EncryptedFileStore::new(dir.path(), keys);
+        assert!(matches!(
+            reopened.get("k").await,
+            Err(SecureStoreError::Storage { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn batches_commit_together_and_replay_after_a_crash() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let keys = InMemoryKeyProvider::default();
+        let store = EncryptedFileStore::new(dir.path(), keys.clone());
+        store.put("index", b"[]").await.expect("put");
+
+        let mut stale = Batch::new();
+        stale
+            .put("task", b"t1".to_vec())
+            .put_if("index", b"[1]".to_vec(), Some(9));
+        assert!(matches!(
+            store.commit(stale).await,
+            Err(SecureStoreError::Conflict { key, .. }) if key == "index"
+        ));
+        assert!(!store.exists("task").await.expect("exists"));
+
+        let mut batch = Batch::new();
+        batch
+            .put("task", b"t1".to_vec())
+            .put_if("index", b"[1]".to_vec(), Some(1))
+            .put("task", b"t2".to_vec());
+        store.commit(batch).await.expect("commit");
+        assert_eq!(store.get_versioned("task").await.expect("task").revision, 2);
+        assert!(!dir.path().join(BATCH_FILE).exists());
+
+        // A crash right after the manifest was written: no blob changed yet.
+        let seal = |name: &str, value: &'static [u8], revision| {
+            let store = &store;
+            let name = sanitize_key(name);
+            async move {
+                let _lock = store.lock().await.expect("lock");
+                let blob = store.seal(&name, value, revision).await.expect("seal");
+                (name, Some(blob))
+            }
+        };
+        let manifest = BatchManifest {
+            changes: vec![
+                seal("index", b"[1,2]", 3).await,
+                seal("other", b"o", 1).await,
+                (sanitize_key("task"), None),
+            ],
+        };
+        write_json(&dir.path().join(BATCH_FILE), &manifest).expect("manifest");
+
+        let reopened = EncryptedFileStore::new(dir.path(), keys);
+        assert_eq!(reopened.get("index").await.expect("index"), b"[1,2]");
+        assert_eq!(reopened.get("other").await.expect("other"), b"o");
+        assert!(!reopened.exists("task").await.expect("exists"));
+        assert!(!dir.path().join(BATCH_FILE).exists());
+    }
+
+    #[tokio::test]
+    async fn waiting_for_the_lock_leaves_the_runtime_free() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = EncryptedFileStore::new(dir.path(), InMemoryKeyProvider::default());
+        store.put("a", b"1").await.expect("put");
+
+        // Another process holds the lock; on this single-threaded runtime the
+        // blocked writer must still let the reader run and release it.
+        let held = File::open(dir.path().join(LOCK_FILE)).expect("open lock");
+        FileExt::lock_exclusive(&held).expect("hold lock");
+        let reader = async {
+            assert_eq!(store.get("a").await.expect("get"), b"1");
+            FileExt::unlock(&held).expect("release");
+        };
+        let (written, ()) = tokio::join!(store.put("b", b"2"), reader);
+        written.expect("put after release");
+        assert_eq!(store.get("b").await.expect("get"), b"2");
+    }
+
+    #[tokio::test]
+    async fn check_reports_and_repair_quarantines_bad_blobs() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = EncryptedFileStore::new(dir.path(), InMemoryKeyProvider::default());
+        for key in ["good", "garbled", "swapped", "rejected"] {
+            store.put(key, key.as_bytes()).await.expect("put");
+        }
+        fs::write(store.path_for("garbled"), b"{not json").expect("garble");
+        fs::copy(store.path_for("good"), store.path_for("swapped")).expect("swap");
+        let orphan = dir.path().join(".tmpA1b2C3");
+        let fresh = dir.path().join(".tmpD4e5F6");
+        fs::write(&orphan, b"partial").expect("orphan");
+        fs::write(&fresh, b"in flight").expect("fresh");
+        File::options()
+            .write(true)
+            .open(&orphan)
+            .and_then(|f| f.set_modified(std::time::SystemTime::now() - ORPHAN_AGE * 2))
+            .expect("age orphan");
+
+        let validate = |key: &str, value: &[u8]| match (key, value) {
+            ("rejected", _) => Err("not what this key holds".to_string()),
+            _ => Ok(()),
+        };
+        let report = store.check(validate).await.expect("check");
+        assert_eq!(report.healthy, 1);
+        let mut bad: Vec<&str> = report.corrupt.iter().map(|c| c.key.as_str()).collect();
+        bad.sort();
+        assert_eq!(bad, ["garbled", "rejected", "swapped"]);
+        assert_eq!(report.orphans, [".tmpA1b2C3"]);
+        assert!(!report.repaired && orphan.exists());
+
+        let repaired = store.repair(validate).await.expect("repair");
+        assert!(repaired.repaired);
+        assert_eq!(repaired.corrupt.len(), 3);
+        assert!(!orphan.exists() && fresh.exists());
+        let kept = fs::read_dir(dir.path().join(QUARANTINE_DIR))
+            .expect("quarantine")
+            .count();
+        assert_eq!(kept, 3);
+        assert_eq!(store.list("").await.expect("list"), ["good"]);
+        assert!(store.check(validate).await.expect("recheck").is_clean());
+    }
+
+    #[tokio::test]
+    async fn ciphertexts_are_bound_to_their_key_name() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = EncryptedFileStore::new(dir.path(), InMemoryKeyProvider::default());
+        store.put("tasks", b"real tasks").await.expect("put tasks");
+        store
+            .put("other", b"something else")
+            .await
+            .expect("put other");
+        assert_eq!(store.blob_format().await.unwrap(), BLOB_VERSION);
+
+        fs::copy(store.path_for("other"), store.path_for("tasks")).expect("swap");
+        let err = store.get("tasks").await.expect_err("swapped blob");
+        assert!(matches!(err, SecureStoreError::Storage { .. }));
+        assert_eq!(store.get("other").await.unwrap(), b"something else");
+    }
+
+    #[tokio::test]
+    async fn concurrent_compare_and_swap_loses_no_updates() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let keys = InMemoryKeyProvider::default();
+        let mut handles = Vec::new();
+        for _ in 0..4 {
+            let store = EncryptedFileStore::new(dir.path(), keys.clone());
+            handles.push(tokio::spawn(async move {
+                for _ in 0..10 {
+                    loop {
+                        let current = match store.get_versioned("counter").await {
+                            Ok(v) => Some(v),
+                            Err(SecureStoreError::NotFound { .. }) => None,
+                            Err(err) => panic!("read failed: {err}"),
+                        };
+                        let n: u32 = current
+                            .as_ref()
+                            .map(|v| String::from_utf8_lossy(&v.value).parse().unwrap())
+                            .unwrap_or(0);
+                        let next = (n + 1).to_string();
+                        match store
+                            .put_if("counter", next.as_bytes(), current.map(|v| v.revision))
+                            .await
+                        {
+                            Ok(_) => break,
+                            Err(SecureStoreError::Conflict { .. }) => continue,
+                            Err(err) => panic!("write failed: {err}"),
+                        }
+                    }
+                }
+            }));
+        }
+        for handle in handles {
+            handle.await.expect("task");
+        }
+
+        let store = EncryptedFileStore::new(dir.path(), InMemoryKeyProvider::default());
+        let blob = read_blob(&store.path_for("counter")).expect("blob");
+        assert_eq!(blob.revision, 40);
+    }
 }
@@ -0,0 +1,220 @@
+//! SQLite backend for the encrypted store.
+//!
+//! Rows hold the same sealed blobs the file backend writes (AES-GCM in the
+//! application, so no SQLCipher build is needed), keyed by the encoded key
+//! name. The database sits next to the store's header and lock as
+//! `.frodo.db` and runs in WAL mode, so readers never block the writer.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+};
+
+use frodo_core::storage::SecureStoreError;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{
+    key_provider::KeyProvider,
+    secure_file_store::{storage_err, BlobBackend, EncryptedFileStore, StoredBlob},
+};
+
+/// Database file inside the data dir. The leading dot keeps it (and its
+/// `-wal`/`-shm` companions) out of the file backend's listing.
+pub const DATABASE_FILE: &str = ".frodo.db";
+
+/// How long a connection waits for another process's write to finish.
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// An encrypted store whose blobs are rows in `<root>/.frodo.db`.
+pub type SqliteSecureStore<P> = EncryptedFileStore<P, SqliteBlobs>;
+
+impl<P: KeyProvider> SqliteSecureStore<P> {
+    /// Open (creating if needed) the SQLite store in `root`.
+    pub fn open(root: impl Into<PathBuf>, key_provider: P) -> Result<Self, SecureStoreError> {
+        let root = root.into();
+        let blobs = SqliteBlobs::open(&root)?;
+        Ok(Self::with_backend(root, key_provider, blobs))
+    }
+}
+
+/// Sealed blobs in a SQLite table; batches run in one transaction.
+pub struct SqliteBlobs {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteBlobs {
+    /// Open the database in `root`, creating it and its table if missing.
+    pub fn open(root: &Path) -> Result<Self, SecureStoreError> {
+        fs::create_dir_all(root).map_err(storage_err)?;
+        let conn = Connection::open(root.join(DATABASE_FILE)).map_err(storage_err)?;
+        conn.busy_timeout(BUSY_TIMEOUT).map_err(storage_err)?;
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(storage_err)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS blobs (
+                name TEXT PRIMARY KEY NOT NULL,
+                version INTEGER NOT NULL,
+                key_id TEXT NOT NULL,
+                revision INTEGER NOT NULL,
+                nonce TEXT NOT NULL,
+                ciphertext TEXT NOT NULL
+            );",
+        )
+        .map_err(storage_err)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Whether `root` holds a SQLite store.
+    pub fn exists(root: &Path) -> bool {
+        root.join(DATABASE_FILE).is_file()
+    }
+
+    /// Delete the database in `root` with its WAL files, e.g. once its blobs
+    /// have been transferred elsewhere.
+    pub fn remove(root: &Path) -> Result<(), SecureStoreError> {
+        for suffix in ["", "-wal", "-shm"] {
+            match fs::remove_file(root.join(format!("{DATABASE_FILE}{suffix}"))) {
+                Ok(()) => {}
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => return Err(storage_err(err)),
+            }
+        }
+        Ok(())
+    }
+
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>, SecureStoreError> {
+        self.conn.lock().map_err(|err| SecureStoreError::Storage {
+            reason: format!("lock poisoned: {err}"),
+        })
+    }
+}
+
+impl BlobBackend for SqliteBlobs {
+    fn names(&self) -> Result<Vec<String>, SecureStoreError> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare("SELECT name FROM blobs ORDER BY name")
+            .map_err(storage_err)?;
+        let names = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(storage_err)?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(storage_err)?;
+        Ok(names)
+    }
+
+    fn read(&self, name: &str) -> Result<Option<StoredBlob>, SecureStoreError> {
+        self.conn()?
+            .query_row(
+                "SELECT version, key_id, revision, nonce, ciphertext FROM blobs WHERE name = ?1",
+                [name],
+                |row| {
+                    Ok(StoredBlob {
+                        version: row.get(0)?,
+                        key_id: row.get(1)?,
+                        revision: row.get::<_, i64>(2)? as u64,
+                        nonce: row.get(3)?,
+                        ciphertext: row.get(4)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(storage_err)
+    }
+
+    fn apply(&self, changes: Vec<(String, Option<StoredBlob>)>) -> Result<(), SecureStoreError> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction().map_err(storage_err)?;
+        for (name, blob) in &changes {
+            match blob {
+                Some(blob) => tx.execute(
+                    "INSERT OR REPLACE INTO blobs (name, version, key_id, revision, nonce, ciphertext)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        name,
+                        blob.version,
+                        blob.key_id,
+                        blob.revision as i64,
+                        blob.nonce,
+                        blob.ciphertext
+                    ],
+                ),
+                None => tx.execute("DELETE FROM blobs WHERE name = ?1", [name]),
+            }
+            .map_err(storage_err)?;
+        }
+        tx.commit().map_err(storage_err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_provider::InMemoryKeyProvider;
+    use frodo_core::storage::SecureStore;
+
+    #[tokio::test]
+    async fn round_trips_and_lists_through_sqlite() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store =
+            SqliteSecureStore::open(dir.path(), InMemoryKeyProvider::default()).expect("open");
+
+        assert_eq!(store.put_if("tasks/a", b"v1", None).await.expect("put"), 1);
+        store.put("tasks/b", b"secret-b").await.expect("put");
+        assert_eq!(
+            store.put_if("tasks/a", b"v2", Some(1)).await.expect("cas"),
+            2
+        );
+        assert!(store.put_if("tasks/a", b"stale", Some(1)).await.is_err());
+
+        assert_eq!(store.get("tasks/a").await.expect("get"), b"v2");
+        assert_eq!(
+            store.namespace("tasks").list("").await.expect("list"),
+            ["a", "b"]
+        );
+        store.delete("tasks/a").await.expect("delete");
+        assert!(!store.exists("tasks/a").await.expect("exists"));
+
+        let raw = fs::read(dir.path().join(DATABASE_FILE)).expect("db");
+        assert!(!raw.windows(8).any(|w| w == b"secret-b"));
+    }
+
+    #[tokio::test]
+    async fn transfer_moves_blobs_between_backends_and_resumes() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let keys = InMemoryKeyProvider::default();
+        let files = EncryptedFileStore::new(dir.path(), keys.clone());
+        files.put("a", b"one").await.expect("put");
+        files.put("b", b"two").await.expect("put");
+        files.put("b", b"two again").await.expect("put");
+
+        let target = SqliteBlobs::open(dir.path()).expect("open");
+        // A newer row left by an earlier, interrupted run wins.
+        let sqlite = SqliteSecureStore::open(dir.path(), keys.clone()).expect("open");
+        sqlite.put("a", b"newer").await.expect("put");
+        sqlite.put("a", b"newest").await.expect("put");
+
+        let report = files.transfer_to(&target).await.expect("transfer");
+        assert_eq!((report.moved, report.skipped), (1, 1));
+        assert!(files.list("").await.expect("list").is_empty());
+
+        assert_eq!(sqlite.get("a").await.expect("a"), b"newest");
+        let b = sqlite.get_versioned("b").await.expect("b");
+        assert_eq!((b.value.as_slice(), b.revision), (&b"two again"[..], 2));
+
+        // And back again: the header is shared, so nothing is re-encrypted.
+        let back = sqlite
+            .transfer_then(
+                &crate::secure_file_store::FileBlobs::new(dir.path()),
+                || SqliteBlobs::remove(dir.path()),
+            )
+            .await
+            .expect("transfer back");
+        assert_eq!(back.moved, 2);
+        assert!(!SqliteBlobs::exists(dir.path()));
+        assert_eq!(files.get("a").await.expect("a"), b"newest");
+    }
+}
@@ -7,7 +7,6 @@ use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, USER_AGENT};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tracing::instrument;
-use uuid::Uuid;
 
 /// High-level sync contract for pulling/pushing tasks to remote providers.
 #[async_trait]
@@ -244,6 +243,8 @@ impl TaskSync for GitHubSync {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct GitHubIssue {
+    #[serde(default)]
+    number: Option<u64>,
     title: String,
     body: Option<String>,
     state: String,
@@ -267,20 +268,18 @@ fn task_from_github(issue: GitHubIssue) -> Task {
         "closed" => TaskStatus::Done,
         _ => TaskStatus::Todo,
     };
-    Task {
-        id: Uuid::new_v4(),
-        title: issue.title,
-        description: issue.body,
-        tags: issue
-            .labels
-            .unwrap_or_default()
-            .into_iter()
-            .map(|l| l.name)
-            .collect(),
-        status,
-        created_at: updated,
-        updated_at: updated,
-    }
+    let tags = issue
+        .labels
+        .unwrap_or_default()
+        .into_iter()
+        .map(|l| l.name)
+        .collect();
+    let mut task = Task::new(issue.title, issue.body, tags);
+    task.status = status;
+    task.remote_key = issue.number.map(|n| format!("#{n}"));
+    task.created_at = updated;
+    task.updated_at = updated;
+    task
 }
 
 #[derive(Debug, Deserialize)]
@@ -290,6 +289,8 @@ struct JiraSearchResponse {
 
 #[derive(Debug, Deserialize)]
 struct JiraIssue {
+    #[serde(default)]
+    key: Option<String>,
     fields: JiraFields,
 }
 
@@ -316,15 +317,16 @@ fn task_from_jira(issue: JiraIssue) -> Task {
         "in progress" => TaskStatus::InProgress,
         _ => TaskStatus::Todo,
     };
-    Task {
-        id: Uuid::new_v4(),
-        title: issue.fields.summary,
-        description: issue.fields.description,
-        tags: issue.fields.labels,
-        status,
-        created_at: updated,
-        updated_at: updated,
-    }
+    let mut task = Task::new(
+        issue.fields.summary,
+        issue.fields.description,
+        issue.fields.labels,
+    );
+    task.status = status;
+    task.remote_key = issue.key;
+    task.created_at = updated;
+    task.updated_at = updated;
+    task
 }
 
 #[cfg(test)]
@@ -0,0 +1,65 @@
+//! Update cost versus store size. With per-task keys, `set_status` and
+//! `update` should stay flat as the number of stored tasks grows.
+//!
+//! Run with `cargo bench -p frodo-task`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use frodo_core::{
+    storage::InMemorySecureStore,
+    tasks::{Priority, TaskRepository, TaskStatus},
+};
+use frodo_task::SecureStoreTaskRepo;
+use tokio::runtime::Runtime;
+
+fn seeded_repo(
+    rt: &Runtime,
+    count: usize,
+) -> (SecureStoreTaskRepo<InMemorySecureStore>, uuid::Uuid) {
+    let repo = SecureStoreTaskRepo::new(InMemorySecureStore::new());
+    let mut last = None;
+    rt.block_on(async {
+        for i in 0..count {
+            let task = repo
+                .create(
+                    format!("task {i}"),
+                    Some("benchmark".into()),
+                    vec!["bench".into()],
+                )
+                .await
+                .expect("create");
+            last = Some(task.id);
+        }
+    });
+    (repo, last.expect("at least one task"))
+}
+
+fn bench_updates(c: &mut Criterion) {
+    let rt = Runtime::new().expect("runtime");
+    let mut group = c.benchmark_group("task_update");
+    for count in [10usize, 1_000, 5_000] {
+        let (repo, id) = seeded_repo(&rt, count);
+        group.bench_with_input(BenchmarkId::new("set_status", count), &count, |b, _| {
+            b.iter(|| {
+                rt.block_on(repo.set_status(id, TaskStatus::InProgress))
+                    .expect("set_status")
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("update", count), &count, |b, _| {
+            let mut task = rt
+                .block_on(repo.set_status(id, TaskStatus::Todo))
+                .expect("fetch");
+            b.iter(|| {
+                task.priority = if task.priority == Priority::High {
+                    Priority::Low
+                } else {
+                    Priority::High
+                };
+                task = rt.block_on(repo.update(task.clone())).expect("update");
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_updates);
+criterion_main!(benches);
@@ -1,43 +1,687 @@
-use std::sync::Arc;
+//! Task repository on top of the encrypted `SecureStore`.
+//!
+//! Each task lives under its own key (`tasks/<uuid>`) next to an index
+//! (`tasks/index`) listing ids in creation order, so editing one task rewrites
+//! one small blob instead of the whole list. Stores written by older versions
+//! keep every task in a single `tasks` blob; it is migrated on first access.
+//!
+//! A full-text index (`tasks/search`) is kept alongside and updated in the
+//! same batch as any write that changes a task's searchable text. It is encrypted like every other
+//! blob, and rebuilt from the tasks when missing or outdated.
+//!
+//! Every mutation is recorded as a [`TaskEvent`] under `tasks/history/<uuid>`,
+//! and each repository call is appended to a capped journal (`tasks/journal`)
+//! that [`TaskRepository::undo`] walks backwards.
+//!
+//! Time entries live on the tasks themselves; `tasks/timer` points at the task
+//! whose timer is running so only one can run at a time, across processes.
+
+use std::{
+    collections::{btree_map, hash_map, BTreeMap, HashMap},
+    sync::Arc,
+};
 
 use anyhow::Result;
 use async_trait::async_trait;
 use frodo_core::{
-    storage::{SecureStore, SecureStoreError},
-    tasks::{Task, TaskRepository, TaskStatus},
+    graph::{self, GraphError},
+    history::{self, EventKind, TaskEvent},
+    search::{self, SearchHit, SearchIndex},
+    storage::{Batch, SecureStore, SecureStoreError},
+    tasks::{Task, TaskConflict, TaskRepository, TaskStatus},
+    timetrack::{TimeEntry, TimerError},
 };
-use tracing::instrument;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::{debug, info, instrument, warn};
 use uuid::Uuid;
 
-const TASKS_KEY: &str = "tasks";
+/// Legacy single-blob key holding every task (pre per-task layout).
+const LEGACY_TASKS_KEY: &str = "tasks";
+const INDEX_KEY: &str = "tasks/index";
+const INDEX_VERSION: u32 = 1;
+const SEARCH_KEY: &str = "tasks/search";
+const JOURNAL_KEY: &str = "tasks/journal";
+const TIMER_KEY: &str = "tasks/timer";
+/// Operations kept for undo; older ones remain visible in task history.
+const JOURNAL_LIMIT: usize = 500;
+/// Events kept per task, so busy tasks do not make every write slower.
+const HISTORY_LIMIT: usize = 200;
+/// Attempts for a conditional write before giving up with a conflict.
+const MAX_ATTEMPTS: usize = 5;
+
+fn task_key(id: Uuid) -> String {
+    format!("tasks/{id}")
+}
+
+fn history_key(id: Uuid) -> String {
+    format!("tasks/history/{id}")
+}
+
+/// Check that a decrypted blob holds what the task layout expects under
+/// `key`, for `frodo store fsck`. Keys outside the layout are accepted.
+pub fn validate_blob(key: &str, value: &[u8]) -> std::result::Result<(), String> {
+    fn parse<T: DeserializeOwned>(value: &[u8]) -> std::result::Result<T, String> {
+        serde_json::from_slice(value).map_err(|err| format!("invalid JSON for this key: {err}"))
+    }
+    match key {
+        LEGACY_TASKS_KEY => parse::<Vec<Task>>(value).map(drop),
+        INDEX_KEY => {
+            let index: TaskIndex = parse(value)?;
+            if index.version > INDEX_VERSION {
+                return Err(format!("index v{} is newer than supported", index.version));
+            }
+            Ok(())
+        }
+        SEARCH_KEY => parse::<SearchIndex>(value).map(drop),
+        JOURNAL_KEY => parse::<Vec<JournalEntry>>(value).map(drop),
+        TIMER_KEY => parse::<ActiveTimer>(value).map(drop),
+        _ => {
+            if let Some(id) = key.strip_prefix("tasks/history/") {
+                id.parse::<Uuid>()
+                    .map_err(|_| "history key is not a task id".to_string())?;
+                return parse::<Vec<TaskEvent>>(value).map(drop);
+            }
+            match key.strip_prefix("tasks/").map(Uuid::parse_str) {
+                Some(Ok(id)) => {
+                    let task: Task = parse(value)?;
+                    if task.id != id {
+                        return Err(format!("holds task {} instead", task.id));
+                    }
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+}
+
+/// Undo journal entry pointing at one recorded event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JournalEntry {
+    operation: Uuid,
+    task_id: Uuid,
+    event: Uuid,
+}
+
+/// Pointer to the task with the running timer, stored under `tasks/timer`.
+#[derive(Debug, Serialize, Deserialize)]
+struct ActiveTimer {
+    task_id: Uuid,
+}
+
+/// One repository call: its id groups the events it records.
+#[derive(Debug, Clone, Copy)]
+struct Operation {
+    id: Uuid,
+}
+
+impl Operation {
+    fn new() -> Self {
+        Self { id: Uuid::new_v4() }
+    }
+}
+
+/// Ordered list of task ids stored (encrypted) under `tasks/index`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TaskIndex {
+    version: u32,
+    ids: Vec<Uuid>,
+}
 
 /// Task repository backed by a `SecureStore` (encrypted at rest).
 pub struct SecureStoreTaskRepo<S: SecureStore> {
     store: Arc<S>,
+    actor: String,
 }
 
 impl<S: SecureStore> SecureStoreTaskRepo<S> {
     pub fn new(store: S) -> Self {
         Self {
             store: Arc::new(store),
+            actor: history::local_actor(),
+        }
+    }
+
+    /// Attribute recorded history events to `actor` instead of the local user.
+    pub fn with_actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = actor.into();
+        self
+    }
+
+    /// A JSON blob and its revision, or the default value and `None` when absent.
+    async fn load_blob<T>(&self, key: &str) -> Result<(T, Option<u64>)>
+    where
+        T: Default + DeserializeOwned,
+    {
+        match self.store.get_versioned(key).await {
+            Ok(stored) => Ok((
+                serde_json::from_slice(&stored.value)?,
+                Some(stored.revision),
+            )),
+            Err(SecureStoreError::NotFound { .. }) => Ok((T::default(), None)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Read the index, migrating the legacy blob the first time it is seen.
+    async fn load_index(&self) -> Result<TaskIndex> {
+        Ok(self.load_index_versioned().await?.0)
+    }
+
+    /// Index plus its store revision (`None` when no index exists yet).
+    async fn load_index_versioned(&self) -> Result<(TaskIndex, Option<u64>)> {
+        match self.store.get_versioned(INDEX_KEY).await {
+            Ok(stored) => Ok((
+                serde_json::from_slice(&stored.value)?,
+                Some(stored.revision),
+            )),
+            Err(SecureStoreError::NotFound { .. }) => self.migrate_legacy().await,
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Split the legacy `tasks` blob into per-task keys. Tasks and the index are
+    /// written before the legacy blob is removed, so an interrupted migration
+    /// simply runs again on the next access.
+    async fn migrate_legacy(&self) -> Result<(TaskIndex, Option<u64>)> {
+        let tasks: Vec<Task> = match self.store.get(LEGACY_TASKS_KEY).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(SecureStoreError::NotFound { .. }) => return Ok((TaskIndex::default(), None)),
+            Err(err) => return Err(err.into()),
+        };
+
+        info!(count = tasks.len(), "migrating tasks to per-task layout");
+        for task in &tasks {
+            self.put_task(task).await?;
+        }
+        let index = TaskIndex {
+            version: INDEX_VERSION,
+            ids: tasks.iter().map(|t| t.id).collect(),
+        };
+        let bytes = serde_json::to_vec(&index)?;
+        let revision = match self.store.put_if(INDEX_KEY, &bytes, None).await {
+            Ok(revision) => revision,
+            // Another process finished the migration first; use its index.
+            Err(SecureStoreError::Conflict { .. }) => {
+                let stored = self.store.get_versioned(INDEX_KEY).await?;
+                return Ok((
+                    serde_json::from_slice(&stored.value)?,
+                    Some(stored.revision),
+                ));
+            }
+            Err(err) => return Err(err.into()),
+        };
+        self.store.delete(LEGACY_TASKS_KEY).await?;
+        Ok((index, Some(revision)))
+    }
+
+    async fn get_task(&self, id: Uuid) -> Result<Option<(Task, u64)>> {
+        match self.store.get_versioned(&task_key(id)).await {
+            Ok(stored) => Ok(Some((
+                serde_json::from_slice(&stored.value)?,
+                stored.revision,
+            ))),
+            Err(SecureStoreError::NotFound { .. }) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn put_task(&self, task: &Task) -> Result<()> {
+        let bytes = serde_json::to_vec(task)?;
+        Ok(self.store.put(&task_key(task.id), &bytes).await?)
+    }
+
+    /// Store a new task together with its index entry and history event, in
+    /// one batch so a crash cannot leave either without the other. The index
+    /// is revision-checked and the batch rebuilt on conflict, so concurrent
+    /// creators never drop each other's entries.
+    async fn insert(&self, task: &Task, op: Operation) -> Result<()> {
+        for _ in 0..MAX_ATTEMPTS {
+            let mut batch = Batch::new();
+            self.stage_insert(&mut batch, task).await?;
+            self.stage_search(&mut batch, |index| index.upsert(task))
+                .await?;
+            self.record_into(
+                &mut batch,
+                op,
+                vec![(task.id, EventKind::Created, history::created(task))],
+            )
+            .await?;
+            match self.store.commit(batch).await {
+                Ok(()) => return Ok(()),
+                Err(SecureStoreError::Conflict { .. }) => {
+                    debug!(id = %task.id, "task index changed concurrently; retrying");
+                }
+                Err(err) => return Err(err.into()),
+            }
         }
+        Err(anyhow::anyhow!(
+            "task index kept changing while adding {}; try again",
+            task.id
+        ))
+    }
+
+    /// Add writes storing a new task and appending it to the index, which
+    /// is revision-checked against the copy read here.
+    async fn stage_insert(&self, batch: &mut Batch, task: &Task) -> Result<()> {
+        let (mut index, revision) = self.load_index_versioned().await?;
+        index.version = INDEX_VERSION;
+        index.ids.push(task.id);
+        batch
+            .put(task_key(task.id), serde_json::to_vec(task)?)
+            .put_if(INDEX_KEY, serde_json::to_vec(&index)?, revision);
+        Ok(())
     }
 
     async fn load(&self) -> Result<Vec<Task>> {
-        match self.store.get(TASKS_KEY).await {
-            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
-            Err(SecureStoreError::NotFound { .. }) => Ok(Vec::new()),
-            Err(err) => Err(anyhow::anyhow!(err.to_string())),
+        let index = self.load_index().await?;
+        let mut tasks = Vec::with_capacity(index.ids.len());
+        for id in index.ids {
+            match self.get_task(id).await? {
+                Some((task, _)) => tasks.push(task),
+                None => warn!(%id, "task listed in index is missing from store"),
+            }
         }
+        Ok(tasks)
     }
 
-    async fn save(&self, tasks: &[Task]) -> Result<()> {
-        let bytes = serde_json::to_vec(tasks)?;
+    /// A task and its revision, or [`GraphError::UnknownTask`].
+    async fn existing_task(&self, id: Uuid) -> Result<(Task, u64)> {
+        match self.get_task(id).await? {
+            Some(found) => Ok(found),
+            None => {
+                // The task may still sit in a legacy blob; migrate and retry once.
+                self.load_index().await?;
+                Ok(self
+                    .get_task(id)
+                    .await?
+                    .ok_or(GraphError::UnknownTask(id))?)
+            }
+        }
+    }
+
+    /// Read one task, apply `change`, bump `updated_at`, and write it back only
+    /// if nobody else wrote it in between. Lost races re-read and re-apply the
+    /// change; after `MAX_ATTEMPTS` a [`TaskConflict`] is returned.
+    async fn modify(
+        &self,
+        id: Uuid,
+        op: Operation,
+        mut change: impl FnMut(&mut Task) -> Result<()> + Send,
+    ) -> Result<Task> {
+        for _ in 0..MAX_ATTEMPTS {
+            let (mut task, revision) = self.existing_task(id).await?;
+            let before = task.clone();
+            change(&mut task)?;
+            task.updated_at = chrono::Utc::now();
+            let mut batch = Batch::new();
+            batch.put_if(task_key(id), serde_json::to_vec(&task)?, Some(revision));
+            if search::document(&task) != search::document(&before) {
+                self.stage_search(&mut batch, |index| index.upsert(&task))
+                    .await?;
+            }
+            let changes = history::diff(&before, &task);
+            if !changes.is_empty() {
+                self.record_into(&mut batch, op, vec![(id, EventKind::Updated, changes)])
+                    .await?;
+            }
+            match self.store.commit(batch).await {
+                Ok(()) => return Ok(task),
+                Err(SecureStoreError::Conflict { .. }) => {
+                    debug!(%id, "task changed concurrently; retrying");
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Err(TaskConflict { id }.into())
+    }
+
+    /// Search index plus its revision. A missing or outdated index is rebuilt
+    /// from the stored tasks; the flag reports that it still needs writing.
+    async fn load_search(&self) -> Result<(SearchIndex, Option<u64>, bool)> {
+        let (index, revision) = match self.store.get_versioned(SEARCH_KEY).await {
+            Ok(stored) => (
+                serde_json::from_slice::<SearchIndex>(&stored.value)?,
+                Some(stored.revision),
+            ),
+            Err(SecureStoreError::NotFound { .. }) => (SearchIndex::default(), None),
+            Err(err) => return Err(err.into()),
+        };
+        if index.is_current() {
+            return Ok((index, revision, false));
+        }
+        info!("building full-text search index");
+        Ok((SearchIndex::build(&self.load().await?), revision, true))
+    }
+
+    /// Add a revision-checked write of the search index with `edit`
+    /// applied, if it changed anything or the stored index was rebuilt.
+    async fn stage_search(
+        &self,
+        batch: &mut Batch,
+        edit: impl FnOnce(&mut SearchIndex) -> bool,
+    ) -> Result<()> {
+        let (mut index, revision, rebuilt) = self.load_search().await?;
+        if edit(&mut index) || rebuilt {
+            batch.put_if(SEARCH_KEY, serde_json::to_vec(&index)?, revision);
+        }
+        Ok(())
+    }
+
+    /// Add writes appending one event per `(task, kind, changes)` record to
+    /// that task's history, and their entries to the undo journal. All are
+    /// revision-checked, so the batch fails with a conflict if any changed
+    /// since it was read. Records must name distinct tasks.
+    async fn record_into(
+        &self,
+        batch: &mut Batch,
+        op: Operation,
+        records: Vec<(Uuid, EventKind, Vec<history::FieldChange>)>,
+    ) -> Result<()> {
+        let (mut journal, journal_revision) =
+            self.load_blob::<Vec<JournalEntry>>(JOURNAL_KEY).await?;
+        for (task_id, kind, changes) in records {
+            let event = self.event(op, task_id, kind, changes);
+            journal.push(JournalEntry {
+                operation: op.id,
+                task_id,
+                event: event.id,
+            });
+            let key = history_key(task_id);
+            let (mut events, revision) = self.load_blob::<Vec<TaskEvent>>(&key).await?;
+            events.push(event);
+            put_history(batch, task_id, &mut events, revision)?;
+        }
+        let excess = journal.len().saturating_sub(JOURNAL_LIMIT);
+        journal.drain(..excess);
+        batch.put_if(JOURNAL_KEY, serde_json::to_vec(&journal)?, journal_revision);
+        Ok(())
+    }
+
+    fn event(
+        &self,
+        op: Operation,
+        task_id: Uuid,
+        kind: EventKind,
+        changes: Vec<history::FieldChange>,
+    ) -> TaskEvent {
+        TaskEvent {
+            id: Uuid::new_v4(),
+            operation: op.id,
+            task_id,
+            at: chrono::Utc::now(),
+            actor: self.actor.clone(),
+            kind,
+            changes,
+        }
+    }
+
+    /// Revert the newest operation in the journal. Every task it touched,
+    /// the index, the search index, the `Undone` events and the journal minus its entries go
+    /// into one batch, so an undo applies completely or not at all and a
+    /// failed one stays in the journal to retry. Losing a race with another
+    /// writer (including a concurrent undo) rebuilds the batch. Returns
+    /// `None` once the journal is empty.
+    async fn undo_last(&self) -> Result<Option<Vec<TaskEvent>>> {
+        for _ in 0..MAX_ATTEMPTS {
+            let (mut journal, journal_revision) =
+                self.load_blob::<Vec<JournalEntry>>(JOURNAL_KEY).await?;
+            let Some(last) = journal.last().map(|entry| entry.operation) else {
+                return Ok(None);
+            };
+            let start = journal
+                .iter()
+                .rposition(|entry| entry.operation != last)
+                .map_or(0, |at| at + 1);
+            let entries = journal.split_off(start);
+
+            let op = Operation::new();
+            // Per task: revision read, state before the undo and after it
+            // (`None` once its creation is reverted).
+            let mut tasks: BTreeMap<Uuid, (u64, Task, Option<Task>)> = BTreeMap::new();
+            let mut histories: HashMap<Uuid, (Vec<TaskEvent>, Option<u64>)> = HashMap::new();
+            let mut removed = Vec::new();
+            let mut reverted = Vec::new();
+            for entry in entries.iter().rev() {
+                let id = entry.task_id;
+                let (events, _) = match histories.entry(id) {
+                    hash_map::Entry::Occupied(slot) => slot.into_mut(),
+                    hash_map::Entry::Vacant(slot) => {
+                        slot.insert(self.load_blob(&history_key(id)).await?)
+                    }
+                };
+                let Some(event) = events.iter().find(|e| e.id == entry.event).cloned() else {
+                    warn!(task = %id, "undo journal points at a missing event");
+                    continue;
+                };
+                let (_, _, state) = match tasks.entry(id) {
+                    btree_map::Entry::Occupied(slot) => slot.into_mut(),
+                    btree_map::Entry::Vacant(slot) => match self.get_task(id).await? {
+                        Some((task, revision)) => slot.insert((revision, task.clone(), Some(task))),
+                        None => {
+                            warn!(task = %id, "cannot undo change to a deleted task");
+                            continue;
+                        }
+                    },
+                };
+                let Some(current) = state.take() else {
+                    continue;
+                };
+                let changes = match event.kind {
+                    EventKind::Created => {
+                        removed.push(id);
+                        history::created(&current)
+                            .into_iter()
+                            .map(|change| history::FieldChange {
+                                old: change.new,
+                                new: serde_json::Value::Null,
+                                ..change
+                            })
+                            .collect()
+                    }
+                    EventKind::Updated => {
+                        let mut next = history::revert(&current, &event.changes)?;
+                        next.updated_at = chrono::Utc::now();
+                        let changes = history::diff(&current, &next);
+                        *state = Some(next);
+                        changes
+                    }
+                    EventKind::Undone => {
+                        *state = Some(current);
+                        continue;
+                    }
+                };
+                let event = self.event(op, id, EventKind::Undone, changes);
+                events.push(event.clone());
+                reverted.push(event);
+            }
+
+            let mut batch = Batch::new();
+            batch.put_if(JOURNAL_KEY, serde_json::to_vec(&journal)?, journal_revision);
+            for (id, (revision, _, state)) in &tasks {
+                match state {
+                    Some(task) => {
+                        batch.put_if(task_key(*id), serde_json::to_vec(task)?, Some(*revision));
+                    }
+                    None => {
+                        batch.delete(task_key(*id));
+                    }
+                }
+            }
+            if !removed.is_empty() {
+                let (mut index, revision) = self.load_index_versioned().await?;
+                index.version = INDEX_VERSION;
+                index.ids.retain(|id| !removed.contains(id));
+                batch.put_if(INDEX_KEY, serde_json::to_vec(&index)?, revision);
+            }
+            for (id, (mut events, revision)) in histories {
+                if reverted.iter().any(|event| event.task_id == id) {
+                    put_history(&mut batch, id, &mut events, revision)?;
+                }
+            }
+            let searchable = tasks.iter().any(|(_, (_, before, state))| match state {
+                Some(task) => search::document(task) != search::document(before),
+                None => true,
+            });
+            if searchable {
+                self.stage_search(&mut batch, |index| {
+                    tasks.iter().fold(false, |changed, (id, (_, _, state))| {
+                        let edited = match state {
+                            Some(task) => index.upsert(task),
+                            None => index.remove(*id),
+                        };
+                        changed | edited
+                    })
+                })
+                .await?;
+            }
+
+            match self.store.commit(batch).await {
+                Ok(()) => return Ok(Some(reverted)),
+                Err(SecureStoreError::Conflict { .. }) => {
+                    debug!(operation = %last, "tasks changed during undo; retrying");
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Err(anyhow::anyhow!(
+            "tasks kept changing during undo; try again"
+        ))
+    }
+
+    /// The timer pointer and its revision, if one is stored.
+    async fn load_timer(&self) -> Result<Option<(ActiveTimer, u64)>> {
+        match self.store.get_versioned(TIMER_KEY).await {
+            Ok(stored) => Ok(Some((
+                serde_json::from_slice(&stored.value)?,
+                stored.revision,
+            ))),
+            Err(SecureStoreError::NotFound { .. }) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn clear_timer(&self) -> Result<()> {
+        match self.store.delete(TIMER_KEY).await {
+            Ok(()) | Err(SecureStoreError::NotFound { .. }) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Point the timer slot at `id`. A pointer whose task no longer has a
+    /// running entry (deleted, completed, undone) is stale and taken over.
+    async fn claim_timer(&self, id: Uuid) -> Result<()> {
+        for _ in 0..MAX_ATTEMPTS {
+            let revision = match self.load_timer().await? {
+                Some((active, _)) if active.task_id == id => return Ok(()),
+                Some((active, revision)) => match self.get_task(active.task_id).await? {
+                    Some((task, _)) if task.running_entry().is_some() => {
+                        return Err(TimerError::AlreadyRunning {
+                            task_id: task.id,
+                            title: task.title,
+                        }
+                        .into());
+                    }
+                    _ => Some(revision),
+                },
+                None => None,
+            };
+            let bytes = serde_json::to_vec(&ActiveTimer { task_id: id })?;
+            match self.store.put_if(TIMER_KEY, &bytes, revision).await {
+                Ok(_) => return Ok(()),
+                Err(SecureStoreError::Conflict { .. }) => {
+                    debug!(%id, "timer changed concurrently; retrying");
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Err(anyhow::anyhow!("timer kept changing; try again"))
+    }
+
+    /// Ranked full-text search over titles, tags and descriptions. Index
+    /// entries whose task no longer exists are skipped.
+    #[instrument(skip(self))]
+    pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<(Task, SearchHit)>> {
+        self.search_where(query, limit, |_| true).await
+    }
+
+    /// Like [`search`](Self::search), but only returns tasks accepted by
+    /// `keep` (e.g. those in the active workspace); `limit` counts kept tasks.
+    pub async fn search_where(
+        &self,
+        query: &str,
+        limit: usize,
+        keep: impl Fn(&Task) -> bool,
+    ) -> Result<Vec<(Task, SearchHit)>> {
+        let (index, revision, rebuilt) = self.load_search().await?;
+        if rebuilt {
+            let bytes = serde_json::to_vec(&index)?;
+            // Losing this race is fine: someone else just wrote a fresh index.
+            match self.store.put_if(SEARCH_KEY, &bytes, revision).await {
+                Ok(_) | Err(SecureStoreError::Conflict { .. }) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let mut results = Vec::new();
+        for hit in index.search(query, index.len()) {
+            if results.len() == limit {
+                break;
+            }
+            if let Some((task, _)) = self.get_task(hit.id).await? {
+                if keep(&task) {
+                    results.push((task, hit));
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Recreate `tasks/index` from the task keys in the store, oldest first,
+    /// e.g. after a corrupt index was quarantined. Returns the number of tasks.
+    #[instrument(skip(self))]
+    pub async fn rebuild_index(&self) -> Result<usize> {
+        let mut tasks = Vec::new();
+        for key in self.store.list("tasks/").await? {
+            let Some(Ok(id)) = key.strip_prefix("tasks/").map(Uuid::parse_str) else {
+                continue;
+            };
+            if let Some((task, _)) = self.get_task(id).await? {
+                tasks.push(task);
+            }
+        }
+        tasks.sort_by_key(|task| task.created_at);
+        let index = TaskIndex {
+            version: INDEX_VERSION,
+            ids: tasks.iter().map(|task| task.id).collect(),
+        };
         self.store
-            .put(TASKS_KEY, &bytes)
-            .await
-            .map_err(|e| anyhow::anyhow!(e.to_string()))
+            .put(INDEX_KEY, &serde_json::to_vec(&index)?)
+            .await?;
+        Ok(index.ids.len())
     }
+
+    /// Rebuild the search index from scratch; returns the number of tasks indexed.
+    #[instrument(skip(self))]
+    pub async fn reindex(&self) -> Result<usize> {
+        let index = SearchIndex::build(&self.load().await?);
+        let bytes = serde_json::to_vec(&index)?;
+        self.store.put(SEARCH_KEY, &bytes).await?;
+        Ok(index.len())
+    }
+}
+
+/// Write a task's history, keeping the newest [`HISTORY_LIMIT`] events.
+fn put_history(
+    batch: &mut Batch,
+    task_id: Uuid,
+    events: &mut Vec<TaskEvent>,
+    revision: Option<u64>,
+) -> Result<()> {
+    let excess = events.len().saturating_sub(HISTORY_LIMIT);
+    events.drain(..excess);
+    batch.put_if(history_key(task_id), serde_json::to_vec(events)?, revision);
+    Ok(())
 }
 
 #[async_trait]
@@ -54,36 +698,206 @@ impl<S: SecureStore> TaskRepository for SecureStoreTaskRepo<S> {
         description: Option<String>,
         tags: Vec<String>,
     ) -> Result<Task> {
-        let mut tasks = self.load().await?;
         let task = Task::new(title, description, tags);
-        tasks.push(task.clone());
-        self.save(&tasks).await?;
+        self.insert(&task, Operation::new()).await?;
+        Ok(task)
+    }
+
+    #[instrument(skip(self, task), fields(id = %task.id))]
+    async fn add(&self, task: Task) -> Result<Task> {
+        if task.parent_id.is_some() || !task.blocked_by.is_empty() {
+            let mut tasks = self.load().await?;
+            tasks.push(task.clone());
+            if let Some(parent) = task.parent_id {
+                graph::check_parent(&tasks, task.id, parent)?;
+            }
+            for blocker in &task.blocked_by {
+                graph::check_dependency(&tasks, task.id, *blocker)?;
+            }
+        }
+        self.insert(&task, Operation::new()).await?;
         Ok(task)
     }
 
     #[instrument(skip(self))]
     async fn set_status(&self, id: Uuid, status: TaskStatus) -> Result<Task> {
-        let mut tasks = self.load().await?;
-        let mut updated: Option<Task> = None;
-        for task in &mut tasks {
-            if task.id == id {
-                task.status = status.clone();
-                task.updated_at = chrono::Utc::now();
-                updated = Some(task.clone());
-                break;
+        // The task, a recurring task's next occurrence, the index entry, both
+        // history events and the timer slot change in one batch, so the next
+        // occurrence cannot be lost between separate writes.
+        let op = Operation::new();
+        let done = status == TaskStatus::Done;
+        for _ in 0..MAX_ATTEMPTS {
+            let (mut task, revision) = self.existing_task(id).await?;
+            let before = task.clone();
+            let completing = done && task.status != TaskStatus::Done;
+            let now = chrono::Utc::now();
+            if done {
+                task.stop_timer(now);
+            }
+            task.status = status.clone();
+            task.updated_at = now;
+
+            let mut batch = Batch::new();
+            batch.put_if(task_key(id), serde_json::to_vec(&task)?, Some(revision));
+            let mut records = Vec::new();
+            let changes = history::diff(&before, &task);
+            if !changes.is_empty() {
+                records.push((id, EventKind::Updated, changes));
+            }
+            let next = if completing {
+                task.next_occurrence(task.updated_at)
+            } else {
+                None
+            };
+            if let Some(next) = &next {
+                self.stage_insert(&mut batch, next).await?;
+                records.push((next.id, EventKind::Created, history::created(next)));
+            }
+            if search::document(&task) != search::document(&before) || next.is_some() {
+                self.stage_search(&mut batch, |index| {
+                    let edited = index.upsert(&task);
+                    next.as_ref().is_some_and(|next| index.upsert(next)) | edited
+                })
+                .await?;
+            }
+            if !records.is_empty() {
+                self.record_into(&mut batch, op, records).await?;
+            }
+            if done && matches!(self.load_timer().await?, Some((active, _)) if active.task_id == id)
+            {
+                batch.delete(TIMER_KEY);
+            }
+
+            match self.store.commit(batch).await {
+                Ok(()) => return Ok(task),
+                Err(SecureStoreError::Conflict { .. }) => {
+                    debug!(%id, "task changed concurrently; retrying");
+                }
+                Err(err) => return Err(err.into()),
             }
         }
-        let updated = updated.ok_or_else(|| anyhow::anyhow!("task not found"))?;
-        self.save(&tasks).await?;
-        Ok(updated)
+        Err(TaskConflict { id }.into())
+    }
+
+    #[instrument(skip(self, task), fields(id = %task.id))]
+    async fn update(&self, task: Task) -> Result<Task> {
+        // `updated_at` doubles as an etag: refuse to overwrite changes the
+        // caller has not seen instead of silently clobbering them.
+        let id = task.id;
+        self.modify(id, Operation::new(), |slot| {
+            if slot.updated_at != task.updated_at {
+                return Err(TaskConflict { id }.into());
+            }
+            *slot = task.clone();
+            Ok(())
+        })
+        .await
+    }
+
+    #[instrument(skip(self))]
+    async fn set_parent(&self, id: Uuid, parent: Option<Uuid>) -> Result<Task> {
+        if let Some(parent) = parent {
+            graph::check_parent(&self.load().await?, id, parent)?;
+        }
+        self.modify(id, Operation::new(), |task| {
+            task.parent_id = parent;
+            Ok(())
+        })
+        .await
+    }
+
+    #[instrument(skip(self))]
+    async fn add_dependency(&self, id: Uuid, blocked_by: Uuid) -> Result<Task> {
+        graph::check_dependency(&self.load().await?, id, blocked_by)?;
+        self.modify(id, Operation::new(), |task| {
+            if !task.blocked_by.contains(&blocked_by) {
+                task.blocked_by.push(blocked_by);
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    #[instrument(skip(self))]
+    async fn remove_dependency(&self, id: Uuid, blocked_by: Uuid) -> Result<Task> {
+        self.modify(id, Operation::new(), |task| {
+            task.blocked_by.retain(|b| *b != blocked_by);
+            Ok(())
+        })
+        .await
+    }
+
+    #[instrument(skip(self))]
+    async fn start_timer(&self, id: Uuid) -> Result<Task> {
+        self.claim_timer(id).await?;
+        let started = self
+            .modify(id, Operation::new(), |task| {
+                if task.running_entry().is_none() {
+                    task.time_entries.push(TimeEntry::start(chrono::Utc::now()));
+                }
+                task.status = TaskStatus::InProgress;
+                Ok(())
+            })
+            .await;
+        if started.is_err() {
+            // Do not leave the slot pointing at a task we failed to start.
+            self.clear_timer().await?;
+        }
+        started
+    }
+
+    #[instrument(skip(self))]
+    async fn stop_timer(&self) -> Result<Option<Task>> {
+        let Some((active, _)) = self.load_timer().await? else {
+            return Ok(None);
+        };
+        let running = match self.get_task(active.task_id).await? {
+            Some((task, _)) => task.running_entry().is_some(),
+            None => false,
+        };
+        let stopped = if running {
+            Some(
+                self.modify(active.task_id, Operation::new(), |task| {
+                    task.stop_timer(chrono::Utc::now());
+                    Ok(())
+                })
+                .await?,
+            )
+        } else {
+            None
+        };
+        self.clear_timer().await?;
+        Ok(stopped)
+    }
+
+    #[instrument(skip(self))]
+    async fn history(&self, id: Uuid) -> Result<Vec<TaskEvent>> {
+        match self.store.get(&history_key(id)).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(SecureStoreError::NotFound { .. }) => Ok(Vec::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    #[instrument(skip(self))]
+    async fn undo(&self, count: usize) -> Result<Vec<TaskEvent>> {
+        let mut reverted = Vec::new();
+        for _ in 0..count {
+            match self.undo_last().await? {
+                Some(events) => reverted.extend(events),
+                None => break,
+            }
+        }
+        Ok(reverted)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use frodo_core::{
+        recurrence::Recurrence,
         storage::InMemorySecureStore,
-        tasks::{TaskRepository, TaskStatus},
+        tasks::{Priority, TaskRepository, TaskStatus},
     };
 
     use super::*;
@@ -124,4 +938,468 @@ mod tests {
         let tasks = repo.list().await.expect("list");
         assert_eq!(tasks[0].status, TaskStatus::Done);
     }
+
+    #[tokio::test]
+    async fn update_replaces_fields() {
+        let repo = SecureStoreTaskRepo::new(InMemorySecureStore::new());
+        let mut task = repo
+            .create("Plan".to_string(), None, vec![])
+            .await
+            .expect("create");
+        task.priority = frodo_core::tasks::Priority::High;
+
+        let updated = repo.update(task.clone()).await.expect("update");
+        assert_eq!(updated.priority, frodo_core::tasks::Priority::High);
+        assert!(updated.updated_at >= task.updated_at);
+        let tasks = repo.list().await.expect("list");
+        assert_eq!(tasks[0].priority, frodo_core::tasks::Priority::High);
+
+        let missing = Task::new("ghost".into(), None, vec![]);
+        assert!(repo.update(missing).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn completing_recurring_task_spawns_next_occurrence() {
+        let repo = SecureStoreTaskRepo::new(InMemorySecureStore::new());
+        let mut task = repo
+            .create("On-call checklist".into(), None, vec!["ops".into()])
+            .await
+            .unwrap();
+        let due = chrono::Utc::now() + chrono::Duration::hours(1);
+        task.due_at = Some(due);
+        task.recurrence = Some("weekly".parse().unwrap());
+        let task = repo.update(task).await.unwrap();
+
+        let done = repo.set_status(task.id, TaskStatus::Done).await.unwrap();
+        let tasks = repo.list().await.unwrap();
+        assert_eq!(tasks.len(), 2);
+        let next = tasks.iter().find(|t| t.id != task.id).unwrap();
+        assert_eq!(next.status, TaskStatus::Todo);
+        assert_eq!(next.title, "On-call checklist");
+        assert_eq!(next.tags, vec!["ops".to_string()]);
+        assert_eq!(next.due_at, Some(due + chrono::Duration::weeks(1)));
+        assert_eq!(next.recurrence, done.recurrence);
+
+        // Completing an already-done task does not spawn again.
+        repo.set_status(task.id, TaskStatus::Done).await.unwrap();
+        assert_eq!(repo.list().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn stores_each_task_under_its_own_key() {
+        let store = InMemorySecureStore::new();
+        let repo = SecureStoreTaskRepo::new(store.clone());
+        let a = repo.create("a".into(), None, vec![]).await.unwrap();
+        let b = repo.create("b".into(), None, vec![]).await.unwrap();
+
+        let raw = store.get(&task_key(a.id)).await.expect("per-task key");
+        let stored: Task = serde_json::from_slice(&raw).unwrap();
+        assert_eq!(stored.title, "a");
+        let index: TaskIndex =
+            serde_json::from_slice(&store.get(INDEX_KEY).await.unwrap()).unwrap();
+        assert_eq!(index.ids, vec![a.id, b.id]);
+        assert!(matches!(
+            store.get(LEGACY_TASKS_KEY).await,
+            Err(SecureStoreError::NotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn migrates_legacy_blob_transparently() {
+        let store = InMemorySecureStore::new();
+        let legacy = vec![
+            Task::new("first".into(), None, vec![]),
+            Task::new("second".into(), Some("desc".into()), vec!["x".into()]),
+        ];
+        store
+            .put(LEGACY_TASKS_KEY, &serde_json::to_vec(&legacy).unwrap())
+            .await
+            .unwrap();
+
+        let repo = SecureStoreTaskRepo::new(store.clone());
+        // Mutating before any list still sees the migrated data.
+        repo.set_status(legacy[1].id, TaskStatus::Done)
+            .await
+            .expect("update migrated task");
+
+        let tasks = repo.list().await.unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].id, legacy[0].id);
+        assert_eq!(tasks[1].status, TaskStatus::Done);
+        assert!(matches!(
+            store.get(LEGACY_TASKS_KEY).await,
+            Err(SecureStoreError::NotFound { .. })
+        ));
+        assert!(store.get(&task_key(legacy[0].id)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn skips_index_entries_with_missing_blobs() {
+        let store = InMemorySecureStore::new();
+        let repo = SecureStoreTaskRepo::new(store.clone());
+        let kept = repo.create("kept".into(), None, vec![]).await.unwrap();
+        let lost = repo.create("lost".into(), None, vec![]).await.unwrap();
+        store.delete(&task_key(lost.id)).await.unwrap();
+
+        let tasks = repo.list().await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, kept.id);
+    }
+
+    #[tokio::test]
+    async fn validates_blobs_and_rebuilds_a_lost_index() {
+        let store = InMemorySecureStore::new();
+        let repo = SecureStoreTaskRepo::new(store.clone());
+        let first = repo.create("first".into(), None, vec![]).await.unwrap();
+        let second = repo.create("second".into(), None, vec![]).await.unwrap();
+
+        for key in store.list("").await.unwrap() {
+            let value = store.get(&key).await.unwrap();
+            assert_eq!(validate_blob(&key, &value), Ok(()), "{key}");
+        }
+        let task = store.get(&task_key(first.id)).await.unwrap();
+        assert!(validate_blob(&task_key(second.id), &task).is_err());
+        assert!(validate_blob(INDEX_KEY, b"[1,2]").is_err());
+        assert!(validate_blob(&history_key(first.id), b"{}").is_err());
+        assert_eq!(validate_blob("cli/last-list", b"anything"), Ok(()));
+
+        store.delete(INDEX_KEY).await.unwrap();
+        assert!(repo.list().await.unwrap().is_empty());
+        assert_eq!(repo.rebuild_index().await.unwrap(), 2);
+        let ids: Vec<Uuid> = repo.list().await.unwrap().iter().map(|t| t.id).collect();
+        assert_eq!(ids, [first.id, second.id]);
+    }
+
+    #[tokio::test]
+    async fn update_rejects_stale_copies() {
+        let repo = SecureStoreTaskRepo::new(InMemorySecureStore::new());
+        let task = repo.create("Shared".into(), None, vec![]).await.unwrap();
+        let stale = task.clone();
+
+        // Another writer (e.g. the TUI) changes the task first.
+        repo.set_status(task.id, TaskStatus::InProgress)
+            .await
+            .unwrap();
+
+        let mut edit = stale;
+        edit.title = "Renamed".into();
+        let err = repo.update(edit).await.expect_err("stale update");
+        assert_eq!(
+            err.downcast_ref::<TaskConflict>(),
+            Some(&TaskConflict { id: task.id })
+        );
+        let stored = repo.list().await.unwrap();
+        assert_eq!(stored[0].title, "Shared");
+        assert_eq!(stored[0].status, TaskStatus::InProgress);
+    }
+
+    #[tokio::test]
+    async fn concurrent_creates_keep_every_index_entry() {
+        let store = InMemorySecureStore::new();
+        let mut handles = Vec::new();
+        for worker in 0..4 {
+            let repo = SecureStoreTaskRepo::new(store.clone());
+            handles.push(tokio::spawn(async move {
+                for i in 0..10 {
+                    repo.create(format!("w{worker}-{i}"), None, vec![])
+                        .await
+                        .expect("create");
+                    tokio::task::yield_now().await;
+                }
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        let repo = SecureStoreTaskRepo::new(store);
+        assert_eq!(repo.list().await.unwrap().len(), 40);
+    }
+
+    #[tokio::test]
+    async fn search_index_follows_text_changes_only() {
+        let store = InMemorySecureStore::new();
+        let repo = SecureStoreTaskRepo::new(store.clone());
+        let task = repo
+            .create(
+                "Rotate signing keys".into(),
+                Some("before the release".into()),
+                vec!["security".into()],
+            )
+            .await
+            .unwrap();
+        repo.create("Write docs".into(), None, vec![])
+            .await
+            .unwrap();
+
+        let hits = repo.search("signing", 10).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0.id, task.id);
+        assert_eq!(hits[0].1.terms, vec!["signing"]);
+
+        // Status changes do not touch the index.
+        let before = store.get_versioned(SEARCH_KEY).await.unwrap().revision;
+        repo.set_status(task.id, TaskStatus::Done).await.unwrap();
+        let after = store.get_versioned(SEARCH_KEY).await.unwrap().revision;
+        assert_eq!(before, after);
+
+        let mut renamed = repo.list().await.unwrap().remove(0);
+        renamed.title = "Rotate API tokens".into();
+        repo.update(renamed).await.unwrap();
+        assert!(repo.search("signing", 10).await.unwrap().is_empty());
+        assert_eq!(repo.search("tokens", 10).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn search_rebuilds_missing_index() {
+        let store = InMemorySecureStore::new();
+        let repo = SecureStoreTaskRepo::new(store.clone());
+        repo.create("Database backup".into(), None, vec![])
+            .await
+            .unwrap();
+        store.delete(SEARCH_KEY).await.unwrap();
+
+        assert_eq!(repo.search("backup", 10).await.unwrap().len(), 1);
+        assert!(store.get(SEARCH_KEY).await.is_ok());
+        assert_eq!(repo.reindex().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn search_index_is_written_with_the_task() {
+        let store = InMemorySecureStore::new();
+        let repo = SecureStoreTaskRepo::new(store.clone());
+        repo.create("Database backup".into(), None, vec![])
+            .await
+            .unwrap();
+
+        // An unreadable search index fails the write instead of leaving it stale.
+        let search = store.get(SEARCH_KEY).await.unwrap();
+        store.put(SEARCH_KEY, b"{not json").await.unwrap();
+        repo.create("Restore drill".into(), None, vec![])
+            .await
+            .expect_err("unreadable search index");
+        assert_eq!(repo.list().await.unwrap().len(), 1);
+
+        store.put(SEARCH_KEY, &search).await.unwrap();
+        let drill = repo
+            .create("Restore drill".into(), None, vec![])
+            .await
+            .unwrap();
+        assert_eq!(repo.search("drill", 10).await.unwrap()[0].0.id, drill.id);
+        repo.undo(1).await.unwrap();
+        let index: SearchIndex =
+            serde_json::from_slice(&store.get(SEARCH_KEY).await.unwrap()).unwrap();
+        assert!(index.search("drill", 10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn records_history_for_each_mutation() {
+        let repo = SecureStoreTaskRepo::new(InMemorySecureStore::new()).with_actor("alice");
+        let task = repo.create("Ship".into(), None, vec![]).await.unwrap();
+        repo.set_status(task.id, TaskStatus::Done).await.unwrap();
+        let mut renamed = repo.list().await.unwrap().remove(0);
+        renamed.title = "Ship v2".into();
+        repo.update(renamed).await.unwrap();
+
+        let events = repo.history(task.id).await.unwrap();
+        let kinds: Vec<EventKind> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![EventKind::Created, EventKind::Updated, EventKind::Updated]
+        );
+        assert!(events.iter().all(|e| e.actor == "alice"));
+        assert_eq!(events[1].changes.len(), 1);
+        assert_eq!(events[1].changes[0].field, "status");
+        assert_eq!(events[1].changes[0].old, serde_json::json!("Todo"));
+        assert_eq!(events[2].changes[0].new, serde_json::json!("Ship v2"));
+    }
+
+    #[tokio::test]
+    async fn undo_reverts_operations_newest_first() {
+        let repo = SecureStoreTaskRepo::new(InMemorySecureStore::new());
+        let task = repo.create("Ship".into(), None, vec![]).await.unwrap();
+        repo.set_status(task.id, TaskStatus::Done).await.unwrap();
+
+        let undone = repo.undo(1).await.unwrap();
+        assert_eq!(undone.len(), 1);
+        assert_eq!(undone[0].kind, EventKind::Undone);
+        assert_eq!(repo.list().await.unwrap()[0].status, TaskStatus::Todo);
+
+        // Undoing the creation removes the task but keeps its history.
+        repo.undo(1).await.unwrap();
+        assert!(repo.list().await.unwrap().is_empty());
+        assert!(repo.search("ship", 10).await.unwrap().is_empty());
+        assert_eq!(repo.history(task.id).await.unwrap().len(), 4);
+        assert!(repo.undo(1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn adding_a_linked_task_is_one_operation() {
+        let repo = SecureStoreTaskRepo::new(InMemorySecureStore::new());
+        let epic = repo.create("Epic".into(), None, vec![]).await.unwrap();
+        let infra = repo.create("Infra".into(), None, vec![]).await.unwrap();
+
+        let mut story = Task::new("Story".into(), None, vec![]);
+        story.parent_id = Some(epic.id);
+        story.blocked_by = vec![infra.id];
+        story.priority = Priority::High;
+        let story = repo.add(story).await.unwrap();
+        let events = repo.history(story.id).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, EventKind::Created);
+
+        assert_eq!(repo.undo(1).await.unwrap().len(), 1);
+        assert_eq!(repo.list().await.unwrap().len(), 2);
+
+        let mut orphan = Task::new("Orphan".into(), None, vec![]);
+        orphan.blocked_by = vec![Uuid::new_v4()];
+        repo.add(orphan).await.expect_err("unknown blocker");
+        assert_eq!(repo.list().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_undo_keeps_the_operation_for_a_retry() {
+        let store = InMemorySecureStore::new();
+        let repo = SecureStoreTaskRepo::new(store.clone());
+        let task = repo.create("Ship".into(), None, vec![]).await.unwrap();
+        repo.set_status(task.id, TaskStatus::Done).await.unwrap();
+
+        let key = task_key(task.id);
+        let stored = store.get(&key).await.unwrap();
+        store.put(&key, b"{not json").await.unwrap();
+        repo.undo(1).await.expect_err("unreadable task");
+
+        store.put(&key, &stored).await.unwrap();
+        assert_eq!(repo.undo(1).await.unwrap().len(), 1);
+        assert_eq!(repo.list().await.unwrap()[0].status, TaskStatus::Todo);
+    }
+
+    #[tokio::test]
+    async fn undo_treats_recurring_completion_as_one_operation() {
+        let repo = SecureStoreTaskRepo::new(InMemorySecureStore::new());
+        let mut task = repo.create("Standup".into(), None, vec![]).await.unwrap();
+        task.recurrence = Some(Recurrence::Days(1));
+        repo.update(task.clone()).await.unwrap();
+        repo.set_status(task.id, TaskStatus::Done).await.unwrap();
+        assert_eq!(repo.list().await.unwrap().len(), 2);
+
+        assert_eq!(repo.undo(1).await.unwrap().len(), 2);
+        let tasks = repo.list().await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].status, TaskStatus::Todo);
+        assert_eq!(tasks[0].recurrence, Some(Recurrence::Days(1)));
+    }
+
+    #[tokio::test]
+    async fn failed_recurring_completion_changes_nothing() {
+        let store = InMemorySecureStore::new();
+        let repo = SecureStoreTaskRepo::new(store.clone());
+        let mut task = repo.create("Standup".into(), None, vec![]).await.unwrap();
+        task.recurrence = Some(Recurrence::Days(1));
+        repo.update(task.clone()).await.unwrap();
+        repo.start_timer(task.id).await.unwrap();
+
+        // The next occurrence cannot be indexed, so the completion fails as a whole.
+        let index = store.get(INDEX_KEY).await.unwrap();
+        store.put(INDEX_KEY, b"{not json").await.unwrap();
+        repo.set_status(task.id, TaskStatus::Done)
+            .await
+            .expect_err("unreadable index");
+        store.put(INDEX_KEY, &index).await.unwrap();
+        let tasks = repo.list().await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].status, TaskStatus::InProgress);
+        assert!(store.exists(TIMER_KEY).await.unwrap());
+
+        // Retrying completes the task and spawns the next occurrence.
+        repo.set_status(task.id, TaskStatus::Done).await.unwrap();
+        assert_eq!(repo.list().await.unwrap().len(), 2);
+        assert!(!store.exists(TIMER_KEY).await.unwrap());
+        assert_eq!(repo.undo(1).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn one_timer_runs_at_a_time_and_survives_restarts() {
+        let store = InMemorySecureStore::new();
+        let repo = SecureStoreTaskRepo::new(store.clone());
+        let first = repo.create("First".into(), None, vec![]).await.unwrap();
+        let second = repo.create("Second".into(), None, vec![]).await.unwrap();
+
+        let started = repo.start_timer(first.id).await.unwrap();
+        assert_eq!(started.status, TaskStatus::InProgress);
+        assert!(started.running_entry().is_some());
+
+        let err = repo.start_timer(second.id).await.expect_err("busy");
+        assert_eq!(
+            err.downcast_ref::<TimerError>(),
+            Some(&TimerError::AlreadyRunning {
+                task_id: first.id,
+                title: "First".into()
+            })
+        );
+
+        // A new repository (another process) sees and stops the same timer.
+        let other = SecureStoreTaskRepo::new(store);
+        let stopped = other.stop_timer().await.unwrap().expect("was running");
+        assert_eq!(stopped.id, first.id);
+        assert_eq!(stopped.time_entries.len(), 1);
+        assert!(stopped.running_entry().is_none());
+        assert!(other.stop_timer().await.unwrap().is_none());
+
+        repo.start_timer(second.id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn completing_a_task_stops_its_timer() {
+        let repo = SecureStoreTaskRepo::new(InMemorySecureStore::new());
+        let task = repo.create("Work".into(), None, vec![]).await.unwrap();
+        repo.start_timer(task.id).await.unwrap();
+
+        let done = repo.set_status(task.id, TaskStatus::Done).await.unwrap();
+        assert!(done.running_entry().is_none());
+        assert!(repo.stop_timer().await.unwrap().is_none());
+
+        // Undoing a start leaves a stale pointer that no longer blocks others.
+        let next = repo.create("Next".into(), None, vec![]).await.unwrap();
+        repo.start_timer(next.id).await.unwrap();
+        repo.undo(1).await.unwrap();
+        repo.start_timer(task.id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn persists_hierarchy_and_dependencies() {
+        let repo = SecureStoreTaskRepo::new(InMemorySecureStore::new());
+        let epic = repo.create("Epic".into(), None, vec![]).await.unwrap();
+        let story = repo.create("Story".into(), None, vec![]).await.unwrap();
+        let infra = repo.create("Infra".into(), None, vec![]).await.unwrap();
+
+        repo.set_parent(story.id, Some(epic.id))
+            .await
+            .expect("parent");
+        repo.add_dependency(story.id, infra.id).await.expect("dep");
+        repo.add_dependency(story.id, infra.id)
+            .await
+            .expect("dep is idempotent");
+
+        let tasks = repo.list().await.unwrap();
+        let stored = tasks.iter().find(|t| t.id == story.id).unwrap();
+        assert_eq!(stored.parent_id, Some(epic.id));
+        assert_eq!(stored.blocked_by, vec![infra.id]);
+
+        let err = repo
+            .add_dependency(infra.id, story.id)
+            .await
+            .expect_err("cycle must be rejected");
+        assert!(err.to_string().contains("cycle"));
+        let err = repo
+            .set_parent(epic.id, Some(story.id))
+            .await
+            .expect_err("parent cycle must be rejected");
+        assert!(err.to_string().contains("cycle"));
+
+        let unlinked = repo.remove_dependency(story.id, infra.id).await.unwrap();
+        assert!(unlinked.blocked_by.is_empty());
+        let top = repo.set_parent(story.id, None).await.unwrap();
+        assert_eq!(top.parent_id, None);
+    }
 }